@@ -5,6 +5,8 @@ use std::time::Duration;
 // Notification priorities
 pub const GOTIFY_DEFAULT_PRIORITY: u8 = 5;
 pub const NTFY_DEFAULT_PRIORITY: u8 = 4;
+pub const GOTIFY_HIGH_PRIORITY: u8 = 8;
+pub const NTFY_HIGH_PRIORITY: u8 = 5;
 
 // Timeouts
 pub const SSH_CONNECTION_TIMEOUT_SECS: u64 = 30;
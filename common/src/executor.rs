@@ -1,3 +1,5 @@
+use std::process::Output;
+
 use anyhow::{anyhow, Result};
 use tokio::process::Command;
 use tokio::time::{timeout, Duration};
@@ -5,6 +7,29 @@ use tracing::{debug, info};
 
 use crate::Server;
 
+/// Everything a command produced, for callers that must judge its exit status themselves
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    /// Exit code, or `None` when the command was killed by a signal
+    pub status: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    fn from_output(output: Output) -> Self {
+        CommandOutput {
+            status: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
 /// Handles executing commands either locally or via SSH
 /// Shared executor used by updatemon, dockermon, and updatectl
 pub struct RemoteExecutor {
@@ -26,6 +51,17 @@ impl RemoteExecutor {
         self.execute(cmd, args).await
     }
 
+    /// Execute a command (locally or via SSH) and return its exit status and
+    /// stderr as well as stdout; a nonzero exit is not an error here
+    pub async fn execute_command_output(&self, cmd: &str, args: &[&str]) -> Result<CommandOutput> {
+        let output = if self.server.is_local() {
+            self.output_local(cmd, args).await?
+        } else {
+            self.output_ssh(cmd, args).await?
+        };
+        Ok(CommandOutput::from_output(output))
+    }
+
     /// Execute a command (locally or via SSH) - internal helper
     async fn execute(&self, cmd: &str, args: &[&str]) -> Result<String> {
         if self.server.is_local() {
@@ -39,16 +75,7 @@ impl RemoteExecutor {
 
     /// Execute command locally
     async fn execute_local(&self, cmd: &str, args: &[&str]) -> Result<String> {
-        info!(cmd = %cmd, args = ?args, "Executing command locally");
-
-        // Add timeout to prevent hanging (2 minutes max)
-        let output = timeout(
-            Duration::from_secs(120),
-            Command::new(cmd).args(args).output()
-        )
-        .await
-        .map_err(|_| anyhow!("Command timed out after 120s: {} {}", cmd, args.join(" ")))?
-        .map_err(|e| anyhow!("Failed to execute {}: {}", cmd, e))?;
+        let output = self.output_local(cmd, args).await?;
 
         // Note: Some commands use non-zero exit codes to indicate updates available
         // (e.g., dnf check-update returns 100 if updates exist)
@@ -64,8 +91,38 @@ impl RemoteExecutor {
         Ok(stdout)
     }
 
+    /// Run a command locally and capture its raw output
+    async fn output_local(&self, cmd: &str, args: &[&str]) -> Result<Output> {
+        info!(cmd = %cmd, args = ?args, "Executing command locally");
+
+        // Add timeout to prevent hanging (2 minutes max)
+        timeout(
+            Duration::from_secs(120),
+            Command::new(cmd).args(args).output()
+        )
+        .await
+        .map_err(|_| anyhow!("Command timed out after 120s: {} {}", cmd, args.join(" ")))?
+        .map_err(|e| anyhow!("Failed to execute {}: {}", cmd, e))
+    }
+
     /// Execute command via SSH
     async fn execute_ssh(&self, cmd: &str, args: &[&str]) -> Result<String> {
+        let output = self.output_ssh(cmd, args).await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            // Only fail on actual SSH errors, not command exit codes
+            if stderr.contains("Permission denied") || stderr.contains("Connection refused") {
+                return Err(anyhow!("SSH failed: {}", stderr));
+            }
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        Ok(stdout)
+    }
+
+    /// Run a command via SSH and capture its raw output
+    async fn output_ssh(&self, cmd: &str, args: &[&str]) -> Result<Output> {
         let ssh_host = self.server.ssh_host.as_ref()
             .ok_or_else(|| anyhow!("No SSH host configured"))?;
 
@@ -107,24 +164,13 @@ impl RemoteExecutor {
         ssh_cmd.arg(ssh_host).arg(remote_cmd);
 
         // Add timeout to prevent SSH from hanging (2 minutes max)
-        let output = timeout(
+        timeout(
             Duration::from_secs(120),
             ssh_cmd.output()
         )
         .await
         .map_err(|_| anyhow!("SSH command timed out after 120s to {}", ssh_host))?
-        .map_err(|e| anyhow!("Failed to SSH to {}: {}", ssh_host, e))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            // Only fail on actual SSH errors, not command exit codes
-            if stderr.contains("Permission denied") || stderr.contains("Connection refused") {
-                return Err(anyhow!("SSH failed: {}", stderr));
-            }
-        }
-
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        Ok(stdout)
+        .map_err(|e| anyhow!("Failed to SSH to {}: {}", ssh_host, e))
     }
 
     /// Get reference to the server
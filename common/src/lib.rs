@@ -14,13 +14,14 @@ pub mod executor;
 pub mod metrics;
 pub mod security;
 pub mod retry;
+pub mod state;
 
 // Re-exports
 pub use error::{
     AppError, NotificationError, RemoteExecutionError, DockerError,
     ServerConfigError, UpdateError, WebhookError, HealthCheckError,
 };
-pub use executor::RemoteExecutor;
+pub use executor::{CommandOutput, RemoteExecutor};
 pub use constants::*;
 
 pub fn dotenv_init() {
@@ -31,6 +32,32 @@ pub fn http_client() -> Client {
     Client::new()
 }
 
+/// Notification urgency, mapped onto each backend's own priority scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Priority {
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    /// Gotify priority (0-10)
+    pub fn gotify(self) -> u8 {
+        match self {
+            Priority::Normal => GOTIFY_DEFAULT_PRIORITY,
+            Priority::High => GOTIFY_HIGH_PRIORITY,
+        }
+    }
+
+    /// ntfy priority (1-5)
+    pub fn ntfy(self) -> u8 {
+        match self {
+            Priority::Normal => NTFY_DEFAULT_PRIORITY,
+            Priority::High => NTFY_HIGH_PRIORITY,
+        }
+    }
+}
+
 // Generic send_gotify (deprecated - prefer service-specific functions)
 // Checks GOTIFY_KEY → GOTIFY_KEY_FILE for backward compatibility
 pub async fn send_gotify(
@@ -38,7 +65,7 @@ pub async fn send_gotify(
     title: &str,
     body: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_gotify_with_key(client, title, body, "GOTIFY_KEY").await
+    send_gotify_with_key(client, title, body, "GOTIFY_KEY", None, Priority::Normal).await
 }
 
 // Service-specific functions - each checks only its own key + GOTIFY_KEY_FILE fallback
@@ -47,7 +74,19 @@ pub async fn send_gotify_weatherust(
     title: &str,
     body: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_gotify_with_key(client, title, body, "WEATHERUST_GOTIFY_KEY").await
+    send_gotify_with_key(client, title, body, "WEATHERUST_GOTIFY_KEY", None, Priority::Normal).await
+}
+
+/// Send to an explicit Gotify app token (per-location routing);
+/// `None` falls back to WEATHERUST_GOTIFY_KEY
+pub async fn send_gotify_weatherust_to(
+    client: &Client,
+    title: &str,
+    body: &str,
+    key: Option<&str>,
+    priority: Priority,
+) -> Result<(), Box<dyn std::error::Error>> {
+    send_gotify_with_key(client, title, body, "WEATHERUST_GOTIFY_KEY", key, priority).await
 }
 
 pub async fn send_gotify_updatemon(
@@ -55,7 +94,7 @@ pub async fn send_gotify_updatemon(
     title: &str,
     body: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_gotify_with_key(client, title, body, "UPDATEMON_GOTIFY_KEY").await
+    send_gotify_with_key(client, title, body, "UPDATEMON_GOTIFY_KEY", None, Priority::Normal).await
 }
 
 pub async fn send_gotify_dockermon(
@@ -63,7 +102,7 @@ pub async fn send_gotify_dockermon(
     title: &str,
     body: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_gotify_with_key(client, title, body, "DOCKERMON_GOTIFY_KEY").await
+    send_gotify_with_key(client, title, body, "DOCKERMON_GOTIFY_KEY", None, Priority::Normal).await
 }
 
 pub async fn send_gotify_healthmon(
@@ -71,7 +110,7 @@ pub async fn send_gotify_healthmon(
     title: &str,
     body: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_gotify_with_key(client, title, body, "HEALTHMON_GOTIFY_KEY").await
+    send_gotify_with_key(client, title, body, "HEALTHMON_GOTIFY_KEY", None, Priority::Normal).await
 }
 
 pub async fn send_gotify_speedynotify(
@@ -79,7 +118,7 @@ pub async fn send_gotify_speedynotify(
     title: &str,
     body: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_gotify_with_key(client, title, body, "SPEEDY_GOTIFY_KEY").await
+    send_gotify_with_key(client, title, body, "SPEEDY_GOTIFY_KEY", None, Priority::Normal).await
 }
 
 pub async fn send_gotify_updatectl(
@@ -87,7 +126,7 @@ pub async fn send_gotify_updatectl(
     title: &str,
     body: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_gotify_with_key(client, title, body, "UPDATECTL_GOTIFY_KEY").await
+    send_gotify_with_key(client, title, body, "UPDATECTL_GOTIFY_KEY", None, Priority::Normal).await
 }
 
 // Internal helper: checks a specific key, then GOTIFY_KEY_FILE fallback
@@ -97,15 +136,21 @@ async fn send_gotify_with_key(
     title: &str,
     body: &str,
     key_var: &str,
+    key_override: Option<&str>,
+    priority: Priority,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let gotify_url =
         std_env::var(env_keys::GOTIFY_URL).unwrap_or_else(|_| "http://localhost:8080/message".to_string());
 
     // Resolve key with precedence:
+    // 0) Explicit key from the caller (e.g., per-location routing)
     // 1) Specific key (e.g., WEATHERUST_GOTIFY_KEY)
     // 2) GOTIFY_KEY_FILE (file-based fallback)
     let mut key_source = "";
-    let gotify_key = if let Ok(v) = std_env::var(key_var) {
+    let gotify_key = if let Some(k) = key_override.map(str::trim).filter(|k| !k.is_empty()) {
+        key_source = "override";
+        k.to_string()
+    } else if let Ok(v) = std_env::var(key_var) {
         let v = v.trim().to_string();
         if !v.is_empty() {
             key_source = key_var;
@@ -163,7 +208,7 @@ async fn send_gotify_with_key(
         .json(&serde_json::json!({
             "title": title,
             "message": body,
-            "priority": GOTIFY_DEFAULT_PRIORITY
+            "priority": priority.gotify()
         }))
         .send()
         .await?
@@ -241,7 +286,20 @@ pub async fn send_ntfy_weatherust(
     body: &str,
     actions: Option<Vec<NtfyAction>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_ntfy_with_topic(client, title, body, "WEATHERUST_NTFY_TOPIC", actions).await
+    send_ntfy_with_topic(client, title, body, "WEATHERUST_NTFY_TOPIC", None, actions, Priority::Normal).await
+}
+
+/// Send to an explicit ntfy topic (per-location routing);
+/// `None` falls back to WEATHERUST_NTFY_TOPIC
+pub async fn send_ntfy_weatherust_to(
+    client: &Client,
+    title: &str,
+    body: &str,
+    topic: Option<&str>,
+    actions: Option<Vec<NtfyAction>>,
+    priority: Priority,
+) -> Result<(), Box<dyn std::error::Error>> {
+    send_ntfy_with_topic(client, title, body, "WEATHERUST_NTFY_TOPIC", topic, actions, priority).await
 }
 
 pub async fn send_ntfy_updatemon(
@@ -250,7 +308,7 @@ pub async fn send_ntfy_updatemon(
     body: &str,
     actions: Option<Vec<NtfyAction>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_ntfy_with_topic(client, title, body, "UPDATEMON_NTFY_TOPIC", actions).await
+    send_ntfy_with_topic(client, title, body, "UPDATEMON_NTFY_TOPIC", None, actions, Priority::Normal).await
 }
 
 pub async fn send_ntfy_dockermon(
@@ -259,7 +317,7 @@ pub async fn send_ntfy_dockermon(
     body: &str,
     actions: Option<Vec<NtfyAction>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_ntfy_with_topic(client, title, body, "DOCKERMON_NTFY_TOPIC", actions).await
+    send_ntfy_with_topic(client, title, body, "DOCKERMON_NTFY_TOPIC", None, actions, Priority::Normal).await
 }
 
 pub async fn send_ntfy_healthmon(
@@ -268,7 +326,7 @@ pub async fn send_ntfy_healthmon(
     body: &str,
     actions: Option<Vec<NtfyAction>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_ntfy_with_topic(client, title, body, "HEALTHMON_NTFY_TOPIC", actions).await
+    send_ntfy_with_topic(client, title, body, "HEALTHMON_NTFY_TOPIC", None, actions, Priority::Normal).await
 }
 
 pub async fn send_ntfy_speedynotify(
@@ -277,7 +335,7 @@ pub async fn send_ntfy_speedynotify(
     body: &str,
     actions: Option<Vec<NtfyAction>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_ntfy_with_topic(client, title, body, "SPEEDY_NTFY_TOPIC", actions).await
+    send_ntfy_with_topic(client, title, body, "SPEEDY_NTFY_TOPIC", None, actions, Priority::Normal).await
 }
 
 pub async fn send_ntfy_updatectl(
@@ -286,7 +344,7 @@ pub async fn send_ntfy_updatectl(
     body: &str,
     actions: Option<Vec<NtfyAction>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_ntfy_with_topic(client, title, body, "UPDATECTL_NTFY_TOPIC", actions).await
+    send_ntfy_with_topic(client, title, body, "UPDATECTL_NTFY_TOPIC", None, actions, Priority::Normal).await
 }
 
 // Internal helper: send ntfy notification with optional actions
@@ -296,15 +354,20 @@ async fn send_ntfy_with_topic(
     title: &str,
     body: &str,
     topic_var: &str,
+    topic_override: Option<&str>,
     actions: Option<Vec<NtfyAction>>,
+    priority: Priority,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Get ntfy server URL
     let ntfy_url = std_env::var(env_keys::NTFY_URL)
         .unwrap_or_else(|_| "https://ntfy.sh".to_string());
 
-    // Get topic for this service
-    let topic = match std_env::var(topic_var) {
-        Ok(t) if !t.trim().is_empty() => t.trim().to_string(),
+    // Get topic: explicit override first, then this service's env var
+    let topic = match topic_override
+        .map(|t| t.to_string())
+        .or_else(|| std_env::var(topic_var).ok())
+    {
+        Some(t) if !t.trim().is_empty() => t.trim().to_string(),
         _ => {
             // ntfy not configured for this service - skip silently
             return Ok(());
@@ -335,7 +398,7 @@ async fn send_ntfy_with_topic(
         "topic": topic,
         "title": title,
         "message": body,
-        "priority": NTFY_DEFAULT_PRIORITY,
+        "priority": priority.ntfy(),
         "markdown": true,
     });
 
@@ -76,13 +76,49 @@ pub fn record_updates_available(server: &str, os_updates: usize, docker_updates:
 }
 
 /// Record speedtest results
-pub fn record_speedtest_result(download_mbps: f64, upload_mbps: f64, ping_ms: f64, degraded: bool) {
-    gauge!("speedtest_download_mbps").set(download_mbps);
-    gauge!("speedtest_upload_mbps").set(upload_mbps);
-    gauge!("speedtest_ping_ms").set(ping_ms);
+///
+/// `failed` names the thresholds the run failed ("download", "ping", ...);
+/// the run counts as degraded when any failed. Multi-WAN and remote runs
+/// pass `origin`, e.g. `("wan", "eth1")` or `("host", "branch")`, which
+/// becomes a label on every series.
+pub fn record_speedtest_result(
+    origin: Option<(&str, &str)>,
+    download_mbps: f64,
+    upload_mbps: f64,
+    ping_ms: Option<f64>,
+    jitter_ms: Option<f64>,
+    packet_loss_pct: Option<f64>,
+    failed: &[&str],
+) {
+    let origin_labels: Vec<(String, String)> = origin
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .into_iter()
+        .collect();
+    let with_origin = |key: &str, value: String| {
+        let mut labels = origin_labels.clone();
+        labels.push((key.to_string(), value));
+        labels
+    };
+
+    gauge!("speedtest_download_mbps", &origin_labels).set(download_mbps);
+    gauge!("speedtest_upload_mbps", &origin_labels).set(upload_mbps);
+    if let Some(ping) = ping_ms {
+        gauge!("speedtest_ping_ms", &origin_labels).set(ping);
+    }
+    if let Some(jitter) = jitter_ms {
+        gauge!("speedtest_jitter_ms", &origin_labels).set(jitter);
+    }
+    if let Some(loss) = packet_loss_pct {
+        gauge!("speedtest_packet_loss_percent", &origin_labels).set(loss);
+    }
 
-    let labels = [("degraded", degraded.to_string())];
+    let labels = with_origin("degraded", (!failed.is_empty()).to_string());
     counter!("speedtest_runs_total", &labels).increment(1);
+
+    for threshold in failed {
+        let labels = with_origin("threshold", threshold.to_string());
+        counter!("speedtest_threshold_failures_total", &labels).increment(1);
+    }
 }
 
 /// Record weather API call
@@ -154,7 +190,16 @@ mod tests {
 
     #[test]
     fn test_record_speedtest() {
-        record_speedtest_result(100.0, 20.0, 15.5, false);
+        record_speedtest_result(None, 100.0, 20.0, Some(15.5), None, None, &[]);
+        record_speedtest_result(
+            Some(("wan", "eth1")),
+            80.0,
+            20.0,
+            Some(45.0),
+            Some(12.0),
+            Some(0.5),
+            &["download", "ping"],
+        );
     }
 
     #[test]
@@ -134,15 +134,16 @@ pub fn is_retryable_http_error(error: &reqwest::Error) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use anyhow::{Result, anyhow};
+    use anyhow::anyhow;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[tokio::test]
     async fn test_retry_eventually_succeeds() {
-        let mut attempt = 0;
+        let attempt = AtomicUsize::new(0);
+        let attempt = &attempt;
 
-        let result = retry_async(|| async {
-            attempt += 1;
-            if attempt < 3 {
+        let result = retry_async(|| async move {
+            if attempt.fetch_add(1, Ordering::SeqCst) + 1 < 3 {
                 Err(anyhow!("temporary error"))
             } else {
                 Ok("success")
@@ -151,21 +152,22 @@ mod tests {
 
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "success");
-        assert_eq!(attempt, 3);
+        assert_eq!(attempt.load(Ordering::SeqCst), 3);
     }
 
     #[tokio::test]
     async fn test_retry_max_attempts() {
-        let mut attempt = 0;
+        let attempt = AtomicUsize::new(0);
+        let attempt = &attempt;
 
-        let result = retry_async(|| async {
-            attempt += 1;
+        let result = retry_async(|| async move {
+            attempt.fetch_add(1, Ordering::SeqCst);
             Err::<String, _>(anyhow!("persistent error"))
         }).await;
 
         assert!(result.is_err());
         // Should try initial + 3 retries = 4 total
-        assert_eq!(attempt, DEFAULT_MAX_RETRIES + 1);
+        assert_eq!(attempt.load(Ordering::SeqCst), DEFAULT_MAX_RETRIES + 1);
     }
 
     #[tokio::test]
@@ -0,0 +1,163 @@
+//! Persistent run-to-run state
+//!
+//! Scheduled jobs (Ofelia, cron) start a fresh process on every run, so
+//! anything that has to remember earlier runs (deduplication, history,
+//! change detection) is stored as a small JSON document in the app's own
+//! state directory.
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::env as std_env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Resolve the state directory of one app, e.g. "weatherust" or "speedynotify"
+///
+/// Each app keeps its own directory so services never read each other's files.
+/// Precedence: `<APP>_STATE_DIR` (e.g. `WEATHERUST_STATE_DIR`) →
+/// `$XDG_STATE_HOME/<app>` → `$HOME/.local/state/<app>` → `<tmp>/<app>`
+pub fn state_dir(app: &str) -> PathBuf {
+    resolve_state_dir(app, |key| std_env::var(key).ok())
+}
+
+fn resolve_state_dir(app: &str, var: impl Fn(&str) -> Option<String>) -> PathBuf {
+    let var = |key: &str| var(key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    if let Some(dir) = var(&format!("{}_STATE_DIR", app.to_ascii_uppercase())) {
+        return PathBuf::from(dir);
+    }
+    if let Some(xdg) = var("XDG_STATE_HOME") {
+        return PathBuf::from(xdg).join(app);
+    }
+    if let Some(home) = var("HOME") {
+        return PathBuf::from(home).join(".local").join("state").join(app);
+    }
+    std_env::temp_dir().join(app)
+}
+
+/// Full path of a named file inside an app's state directory
+pub fn state_path(app: &str, file_name: &str) -> PathBuf {
+    state_dir(app).join(file_name)
+}
+
+/// Load a named state file, falling back to `T::default()` when missing or unreadable
+pub fn load_json<T: DeserializeOwned + Default>(app: &str, file_name: &str) -> T {
+    load_json_from(&state_path(app, file_name))
+}
+
+/// Save a named state file
+pub fn save_json<T: Serialize>(app: &str, file_name: &str, value: &T) -> Result<()> {
+    save_json_to(&state_path(app, file_name), value)
+}
+
+/// Load JSON state from an explicit path
+///
+/// A missing file is the normal first-run case. A corrupt file is logged and
+/// replaced by the default so one bad write never wedges a scheduled job.
+pub fn load_json_from<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            debug!(path = %path.display(), "State file not found; starting fresh");
+            return T::default();
+        }
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "State file read error; starting fresh");
+            return T::default();
+        }
+    };
+
+    match serde_json::from_str(&raw) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "State file is not valid JSON; starting fresh");
+            T::default()
+        }
+    }
+}
+
+/// Save JSON state to an explicit path
+///
+/// Writes to a temporary sibling and renames it so a crash mid-write
+/// leaves the previous state intact.
+pub fn save_json_to<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create state directory {}", parent.display()))?;
+    }
+
+    let tmp = path.with_extension("tmp");
+    let json = serde_json::to_string_pretty(value)?;
+    fs::write(&tmp, json).with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn scratch(name: &str) -> PathBuf {
+        std_env::temp_dir()
+            .join(format!("common-state-test-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn test_missing_file_loads_default() {
+        let path = scratch("missing.json");
+        let v: BTreeMap<String, i64> = load_json_from(&path);
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = scratch("round_trip.json");
+        let mut v = BTreeMap::new();
+        v.insert("a".to_string(), 1i64);
+        save_json_to(&path, &v).unwrap();
+
+        let loaded: BTreeMap<String, i64> = load_json_from(&path);
+        assert_eq!(loaded, v);
+    }
+
+    #[test]
+    fn test_corrupt_file_loads_default() {
+        let path = scratch("corrupt.json");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{not json").unwrap();
+
+        let v: BTreeMap<String, i64> = load_json_from(&path);
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn test_state_dir_per_app() {
+        let vars = |pairs: &'static [(&'static str, &'static str)]| {
+            move |key: &str| {
+                pairs
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        let env = vars(&[
+            ("WEATHERUST_STATE_DIR", "/state"),
+            ("XDG_STATE_HOME", "/home/u/.state"),
+            ("HOME", "/home/u"),
+        ]);
+        assert_eq!(resolve_state_dir("weatherust", env), PathBuf::from("/state"));
+        // Another app's override is not picked up
+        assert_eq!(
+            resolve_state_dir("speedynotify", env),
+            PathBuf::from("/home/u/.state/speedynotify")
+        );
+        let blank = vars(&[("SPEEDYNOTIFY_STATE_DIR", " "), ("HOME", "/home/u")]);
+        assert_eq!(
+            resolve_state_dir("speedynotify", blank),
+            PathBuf::from("/home/u/.local/state/speedynotify")
+        );
+    }
+}
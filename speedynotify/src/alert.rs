@@ -0,0 +1,312 @@
+//! Degraded/recovered alert state
+//!
+//! A single slow run is often just a busy server, so "Degraded" is only sent
+//! once the connection has been degraded for several runs in a row. The
+//! streak is kept in the state directory between runs, which also lets the
+//! first normal run afterwards report how long the outage lasted.
+//!
+//! Each WAN and host has its own streak, so one link recovering while
+//! another degrades is reported for both.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::Origin;
+
+/// State file holding the current degraded streak per origin
+const ALERT_STATE_FILE: &str = "speedynotify-alerts.json";
+
+/// Degraded streaks keyed by origin ("local", "wan:eth1", "host:hq")
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AlertStates(BTreeMap<String, AlertState>);
+
+impl AlertStates {
+    pub fn load() -> Self {
+        common::state::load_json(crate::STATE_APP, ALERT_STATE_FILE)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        common::state::save_json(crate::STATE_APP, ALERT_STATE_FILE, self)
+    }
+
+    /// Fold one origin's run into its own streak
+    pub fn advance(
+        &mut self,
+        origin: &Origin,
+        degraded: bool,
+        now: DateTime<Utc>,
+        degraded_after: u32,
+    ) -> Notice {
+        let key = match origin.metric_label() {
+            Some((kind, name)) => format!("{}:{}", kind, name),
+            None => "local".to_string(),
+        };
+        let state = self.0.entry(key.clone()).or_default();
+        let notice = state.advance(degraded, now, degraded_after);
+        // Nothing worth keeping once the origin is back to normal
+        if *state == AlertState::default() {
+            self.0.remove(&key);
+        }
+        notice
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlertState {
+    /// Degraded runs in a row, reset by the first normal run
+    #[serde(default)]
+    consecutive_degraded: u32,
+    /// Time of the first degraded run in the current streak
+    #[serde(default)]
+    degraded_since: Option<DateTime<Utc>>,
+    /// A "Degraded" notification went out for the current streak
+    #[serde(default)]
+    alerting: bool,
+}
+
+/// What this run means for notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notice {
+    /// Normal, and no alert was open
+    Ok,
+    /// Degraded, but not for enough runs in a row yet
+    Pending { runs: u32, needed: u32 },
+    /// Degraded for `needed` runs in a row: the alert opens
+    Degraded,
+    /// Degraded again while the alert is already open
+    StillDegraded { since: DateTime<Utc> },
+    /// First normal run after an open alert
+    Recovered { after: Duration },
+}
+
+impl AlertState {
+    /// Fold one run into the state; `degraded_after` is the number of
+    /// degraded runs in a row needed before the alert opens (at least 1)
+    pub fn advance(&mut self, degraded: bool, now: DateTime<Utc>, degraded_after: u32) -> Notice {
+        if !degraded {
+            let notice = match (self.alerting, self.degraded_since) {
+                (true, Some(since)) => Notice::Recovered { after: now - since },
+                _ => Notice::Ok,
+            };
+            *self = AlertState::default();
+            return notice;
+        }
+
+        let since = *self.degraded_since.get_or_insert(now);
+        self.consecutive_degraded = self.consecutive_degraded.saturating_add(1);
+        let needed = degraded_after.max(1);
+        if self.alerting {
+            Notice::StillDegraded { since }
+        } else if self.consecutive_degraded >= needed {
+            self.alerting = true;
+            Notice::Degraded
+        } else {
+            Notice::Pending {
+                runs: self.consecutive_degraded,
+                needed,
+            }
+        }
+    }
+}
+
+impl Notice {
+    /// Notification title, or `None` when this run sends nothing
+    ///
+    /// With `ok_on_change_only`, a plain OK run is not pushed; recoveries
+    /// still are.
+    pub fn title(&self, ok_on_change_only: bool) -> Option<&'static str> {
+        match self {
+            Notice::Ok if ok_on_change_only => None,
+            Notice::Ok => Some("Speedtest: OK"),
+            Notice::Pending { .. } => None,
+            Notice::Degraded | Notice::StillDegraded { .. } => Some("Speedtest: Degraded"),
+            Notice::Recovered { .. } => Some("Speedtest: Recovered"),
+        }
+    }
+
+    /// Leading body line for state changes, e.g. "Recovered after 3h 20m"
+    pub fn headline(&self, now: DateTime<Utc>) -> Option<String> {
+        match self {
+            Notice::StillDegraded { since } => {
+                Some(format!("Degraded for {}", format_duration(now - *since)))
+            }
+            Notice::Recovered { after } => {
+                Some(format!("Recovered after {}", format_duration(*after)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Order of precedence when one notification covers several origins
+    fn severity(&self) -> u8 {
+        match self {
+            Notice::Ok => 0,
+            Notice::Pending { .. } => 1,
+            Notice::Recovered { .. } => 2,
+            Notice::Degraded | Notice::StillDegraded { .. } => 3,
+        }
+    }
+}
+
+/// Title for a run covering several origins: the most severe notice wins,
+/// so a pending origin holds back an otherwise plain OK run
+pub fn combined_title(notices: &[Notice], ok_on_change_only: bool) -> Option<&'static str> {
+    notices
+        .iter()
+        .max_by_key(|n| n.severity())?
+        .title(ok_on_change_only)
+}
+
+/// "45m", "3h", "3h 20m", "2d 4h"
+fn format_duration(d: Duration) -> String {
+    let minutes = d.num_minutes().max(0);
+    let (days, hours, mins) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        if hours > 0 {
+            format!("{}d {}h", days, hours)
+        } else {
+            format!("{}d", days)
+        }
+    } else if hours > 0 {
+        if mins > 0 {
+            format!("{}h {}m", hours, mins)
+        } else {
+            format!("{}h", hours)
+        }
+    } else {
+        format!("{}m", mins)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 20, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_degraded_after_consecutive_runs() {
+        let mut state = AlertState::default();
+        assert_eq!(
+            state.advance(true, at(1), 3),
+            Notice::Pending { runs: 1, needed: 3 }
+        );
+        // A normal run in between resets the streak without a recovery
+        assert_eq!(state.advance(false, at(2), 3), Notice::Ok);
+        assert_eq!(
+            state.advance(true, at(3), 3),
+            Notice::Pending { runs: 1, needed: 3 }
+        );
+        assert_eq!(
+            state.advance(true, at(4), 3),
+            Notice::Pending { runs: 2, needed: 3 }
+        );
+        assert_eq!(state.advance(true, at(5), 3), Notice::Degraded);
+        assert_eq!(
+            state.advance(true, at(6), 3),
+            Notice::StillDegraded { since: at(3) }
+        );
+        let recovered = state.advance(false, at(6) + Duration::minutes(20), 3);
+        assert_eq!(
+            recovered,
+            Notice::Recovered {
+                after: Duration::minutes(200)
+            }
+        );
+        assert_eq!(
+            recovered.headline(at(7)).as_deref(),
+            Some("Recovered after 3h 20m")
+        );
+        assert_eq!(state, AlertState::default());
+    }
+
+    #[test]
+    fn test_default_alerts_on_first_degraded_run() {
+        let mut state = AlertState::default();
+        assert_eq!(state.advance(true, at(1), 1), Notice::Degraded);
+        // 0 is treated as 1
+        let mut state = AlertState::default();
+        assert_eq!(state.advance(true, at(1), 0), Notice::Degraded);
+    }
+
+    #[test]
+    fn test_titles() {
+        assert_eq!(Notice::Ok.title(false), Some("Speedtest: OK"));
+        assert_eq!(Notice::Ok.title(true), None);
+        assert_eq!(Notice::Pending { runs: 1, needed: 2 }.title(false), None);
+        assert_eq!(Notice::Degraded.title(true), Some("Speedtest: Degraded"));
+        let recovered = Notice::Recovered {
+            after: Duration::hours(3),
+        };
+        assert_eq!(recovered.title(true), Some("Speedtest: Recovered"));
+        assert_eq!(format_duration(Duration::minutes(45)), "45m");
+        assert_eq!(format_duration(Duration::hours(3)), "3h");
+        assert_eq!(format_duration(Duration::hours(52)), "2d 4h");
+    }
+
+    #[test]
+    fn test_streaks_per_origin() {
+        let eth0 = Origin::Wan("eth0".to_string());
+        let wwan0 = Origin::Wan("wwan0".to_string());
+        let mut states = AlertStates::default();
+        assert_eq!(states.advance(&eth0, true, at(1), 1), Notice::Degraded);
+        assert_eq!(states.advance(&wwan0, false, at(1), 1), Notice::Ok);
+
+        // eth0 recovers while wwan0 degrades: both are reported
+        let notices = [
+            states.advance(&eth0, false, at(3), 1),
+            states.advance(&wwan0, true, at(3), 1),
+        ];
+        assert_eq!(
+            notices,
+            [
+                Notice::Recovered {
+                    after: Duration::hours(2)
+                },
+                Notice::Degraded
+            ]
+        );
+        assert_eq!(combined_title(&notices, true), Some("Speedtest: Degraded"));
+        assert_eq!(states.0.keys().collect::<Vec<_>>(), ["wan:wwan0"]);
+
+        // A host that keeps failing does not mask another host's recovery
+        let hq = Origin::Host("hq".to_string());
+        let branch = Origin::Host("branch".to_string());
+        states.advance(&hq, true, at(1), 1);
+        states.advance(&branch, true, at(1), 1);
+        let notices = [
+            states.advance(&hq, true, at(2), 1),
+            states.advance(&branch, false, at(2), 1),
+        ];
+        assert_eq!(
+            notices[1],
+            Notice::Recovered {
+                after: Duration::hours(1)
+            }
+        );
+    }
+
+    #[test]
+    fn test_combined_title() {
+        let pending = Notice::Pending { runs: 1, needed: 2 };
+        let recovered = Notice::Recovered {
+            after: Duration::hours(1),
+        };
+        assert_eq!(
+            combined_title(&[Notice::Ok, Notice::Ok], false),
+            Some("Speedtest: OK")
+        );
+        assert_eq!(combined_title(&[Notice::Ok, pending], false), None);
+        assert_eq!(
+            combined_title(&[pending, recovered], true),
+            Some("Speedtest: Recovered")
+        );
+        assert_eq!(combined_title(&[], false), None);
+    }
+}
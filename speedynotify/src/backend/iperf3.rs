@@ -0,0 +1,423 @@
+//! iperf3 backend (`iperf3 -c <server> -J`)
+//!
+//! Measures a link to a host you run `iperf3 -s` on (another site over
+//! WireGuard, a NAS on the LAN) instead of the internet uplink. Upload is a
+//! normal run, download a reverse (`-R`) run. TCP mode reports retransmits
+//! and the sender's mean RTT as ping; UDP mode reports jitter and loss.
+
+use std::env;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::process::Command;
+
+use super::{Bind, SpeedtestBackend};
+use crate::model::SpeedResult;
+
+/// iperf3 client settings from `SPEEDY_IPERF3_*`
+pub struct Iperf3 {
+    server: String,
+    port: Option<u16>,
+    udp: bool,
+    seconds: Option<u32>,
+    /// Target bitrate (`-b`), e.g. "200M"; iperf3 sends UDP at 1 Mbit/s without it
+    bitrate: Option<String>,
+}
+
+impl Iperf3 {
+    pub fn from_env() -> Result<Self> {
+        let server = env::var("SPEEDY_IPERF3_SERVER")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow!("SPEEDY_IPERF3_SERVER is not set"))?;
+        Ok(Iperf3 {
+            server,
+            port: env::var("SPEEDY_IPERF3_PORT")
+                .ok()
+                .and_then(|p| p.parse().ok()),
+            udp: env::var("SPEEDY_IPERF3_UDP")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            seconds: env::var("SPEEDY_IPERF3_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+            bitrate: env::var("SPEEDY_IPERF3_BITRATE")
+                .ok()
+                .filter(|b| !b.trim().is_empty()),
+        })
+    }
+
+    /// Client arguments for one direction; `reverse` measures download
+    fn args(&self, bind: Option<&Bind>, reverse: bool) -> Vec<String> {
+        let mut args = vec!["-c".to_string(), self.server.clone(), "-J".to_string()];
+        if let Some(port) = self.port {
+            args.push("-p".to_string());
+            args.push(port.to_string());
+        }
+        if self.udp {
+            args.push("-u".to_string());
+        }
+        if let Some(secs) = self.seconds {
+            args.push("-t".to_string());
+            args.push(secs.to_string());
+        }
+        if let Some(rate) = &self.bitrate {
+            args.push("-b".to_string());
+            args.push(rate.clone());
+        }
+        match bind {
+            Some(Bind::Interface(name)) => {
+                args.push("--bind-dev".to_string());
+                args.push(name.clone());
+            }
+            Some(Bind::SourceIp(ip)) => {
+                args.push("-B".to_string());
+                args.push(ip.clone());
+            }
+            None => {}
+        }
+        if reverse {
+            args.push("-R".to_string());
+        }
+        args
+    }
+
+    async fn run_cli(&self, bind: Option<&Bind>, reverse: bool) -> Result<String> {
+        let mut cmd = Command::new("iperf3");
+        cmd.args(self.args(bind, reverse));
+        let output = cmd.output().await?;
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        if !output.status.success() {
+            // -J reports failures as {"error": "..."} on stdout
+            let reason = serde_json::from_str::<IperfOutput>(&stdout)
+                .ok()
+                .and_then(|o| o.error)
+                .unwrap_or_else(|| String::from_utf8_lossy(&output.stderr).trim().to_string());
+            bail!("iperf3 exited {}: {}", output.status, reason);
+        }
+        Ok(stdout)
+    }
+}
+
+impl SpeedtestBackend for Iperf3 {
+    fn name(&self) -> &'static str {
+        "iperf3"
+    }
+
+    /// `server_id` is an Ookla concept and is ignored here
+    async fn run(&self, _server_id: Option<u32>, bind: Option<&Bind>) -> Result<SpeedResult> {
+        let upload = self.run_cli(bind, false).await?;
+        let download = self.run_cli(bind, true).await?;
+        Ok(combine(&parse(&download)?, &parse(&upload)?))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IperfOutput {
+    #[serde(default)]
+    start: Option<Start>,
+    #[serde(default)]
+    end: Option<End>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Start {
+    #[serde(default)]
+    connected: Vec<Connected>,
+    connecting_to: Option<ConnectingTo>,
+    timestamp: Option<Timestamp>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Connected {
+    local_host: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectingTo {
+    host: String,
+    port: Option<u16>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Timestamp {
+    timesecs: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct End {
+    #[serde(default)]
+    streams: Vec<Stream>,
+    /// TCP (and UDP on iperf3 3.13+)
+    sum_sent: Option<Sum>,
+    sum_received: Option<Sum>,
+    /// UDP on older iperf3
+    sum: Option<Sum>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Stream {
+    sender: Option<StreamSender>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamSender {
+    /// Microseconds, Linux senders only
+    mean_rtt: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Sum {
+    bytes: Option<u64>,
+    bits_per_second: f64,
+    retransmits: Option<u64>,
+    jitter_ms: Option<f64>,
+    lost_percent: Option<f64>,
+}
+
+/// One direction of a test
+#[derive(Debug)]
+struct Run {
+    /// Throughput seen by the receiver
+    mbps: f64,
+    /// Throughput reported by the sender; missing from older UDP output
+    sent_mbps: Option<f64>,
+    bytes: Option<u64>,
+    retransmits: Option<u64>,
+    jitter_ms: Option<f64>,
+    loss: Option<f64>,
+    rtt_ms: Option<f64>,
+    server: String,
+    local_host: String,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+fn parse(json: &str) -> Result<Run> {
+    let out: IperfOutput = serde_json::from_str(json)?;
+    if let Some(err) = out.error {
+        bail!("iperf3: {}", err);
+    }
+    let end = out
+        .end
+        .ok_or_else(|| anyhow!("iperf3 output has no 'end' section"))?;
+    let received = end
+        .sum_received
+        .as_ref()
+        .or(end.sum.as_ref())
+        .ok_or_else(|| anyhow!("iperf3 output has no received summary"))?;
+    let sent = end.sum_sent.as_ref().or(end.sum.as_ref());
+    let rtt_ms = end
+        .streams
+        .iter()
+        .filter_map(|s| s.sender.as_ref()?.mean_rtt)
+        .next()
+        .map(|us| us / 1000.0);
+
+    let start = out.start;
+    let server = start
+        .as_ref()
+        .and_then(|s| s.connecting_to.as_ref())
+        .map(|c| match c.port {
+            Some(port) => format!("{}:{}", c.host, port),
+            None => c.host.clone(),
+        })
+        .unwrap_or_default();
+    let local_host = start
+        .as_ref()
+        .and_then(|s| s.connected.first()?.local_host.clone())
+        .unwrap_or_default();
+    let timestamp = start
+        .as_ref()
+        .and_then(|s| s.timestamp.as_ref())
+        .and_then(|t| DateTime::from_timestamp(t.timesecs, 0));
+
+    Ok(Run {
+        mbps: received.bits_per_second / 1_000_000.0,
+        sent_mbps: end
+            .sum_sent
+            .as_ref()
+            .map(|s| s.bits_per_second / 1_000_000.0),
+        bytes: received.bytes,
+        retransmits: sent.and_then(|s| s.retransmits),
+        jitter_ms: received.jitter_ms,
+        loss: received.lost_percent,
+        rtt_ms,
+        server,
+        local_host,
+        timestamp,
+    })
+}
+
+/// Merge the reverse (download) and normal (upload) runs; jitter and loss
+/// are the worse of the two directions
+fn combine(down: &Run, up: &Run) -> SpeedResult {
+    let worst = |a: Option<f64>, b: Option<f64>| match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    };
+    let retransmits = match (down.retransmits, up.retransmits) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    };
+    let mut result = SpeedResult {
+        jitter_ms: worst(down.jitter_ms, up.jitter_ms),
+        packet_loss: worst(down.loss, up.loss),
+        retransmits,
+        download_sent_mbps: down.sent_mbps,
+        upload_sent_mbps: up.sent_mbps,
+        bytes_received: down.bytes,
+        bytes_sent: up.bytes,
+        server: up.server.clone(),
+        // The interface name comes from the bind, not from iperf3
+        local_ip: Some(up.local_host.clone()).filter(|ip| !ip.is_empty()),
+        // UDP runs and non-Linux senders report no RTT
+        ping_ms: up.rtt_ms,
+        ..SpeedResult::new("iperf3", down.mbps, up.mbps, 0.0)
+    };
+    if let Some(ts) = up.timestamp {
+        result.timestamp = ts;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tcp(reverse: bool, bps: f64, retransmits: u64) -> String {
+        json!({
+            "start": {
+                "connected": [{"socket": 5, "local_host": "10.8.0.2", "local_port": 48122,
+                               "remote_host": "10.8.0.1", "remote_port": 5201}],
+                "connecting_to": {"host": "10.8.0.1", "port": 5201},
+                "timestamp": {"time": "Mon, 15 Jan 2024 10:30:00 GMT", "timesecs": 1_705_314_600},
+                "test_start": {"protocol": "TCP", "reverse": reverse as u8}
+            },
+            "end": {
+                "streams": [{"sender": {"bytes": 1, "bits_per_second": bps,
+                                        "retransmits": retransmits, "mean_rtt": 23_456}}],
+                "sum_sent": {"bytes": 120_000_000u64, "bits_per_second": bps + 1e6,
+                             "retransmits": retransmits},
+                "sum_received": {"bytes": 118_000_000u64, "bits_per_second": bps}
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_tcp_runs() {
+        let down = parse(&tcp(true, 94_000_000.0, 3)).unwrap();
+        let up = parse(&tcp(false, 41_500_000.0, 17)).unwrap();
+        let r = combine(&down, &up);
+        assert_eq!(r.backend, "iperf3");
+        assert_eq!(r.download_mbps, 94.0);
+        assert_eq!(r.upload_mbps, 41.5);
+        assert_eq!(r.download_sent_mbps, Some(95.0));
+        assert_eq!(r.upload_sent_mbps, Some(42.5));
+        assert_eq!(r.ping_ms, Some(23.456));
+        assert_eq!(r.retransmits, Some(20));
+        assert_eq!(r.jitter_ms, None);
+        assert_eq!(r.packet_loss, None);
+        assert_eq!(r.bytes_received, Some(118_000_000));
+        assert_eq!(r.server, "10.8.0.1:5201");
+        assert_eq!(r.interface, "");
+        assert_eq!(r.local_ip.as_deref(), Some("10.8.0.2"));
+        assert_eq!(r.timestamp.timestamp(), 1_705_314_600);
+    }
+
+    #[test]
+    fn test_parse_udp_runs() {
+        // Older iperf3: a single "sum" with the receiver's jitter and loss
+        let udp = |bps: f64, jitter: f64, loss: f64| {
+            json!({
+                "start": {"connecting_to": {"host": "site-b.example", "port": 5201}},
+                "end": {
+                    "streams": [{"udp": {"jitter_ms": jitter}}],
+                    "sum": {"bytes": 25_000_000u64, "bits_per_second": bps, "jitter_ms": jitter,
+                            "lost_packets": 12, "packets": 17_000, "lost_percent": loss}
+                }
+            })
+            .to_string()
+        };
+        let down = parse(&udp(20_000_000.0, 0.8, 0.07)).unwrap();
+        let up = parse(&udp(19_500_000.0, 1.6, 0.0)).unwrap();
+        let r = combine(&down, &up);
+        assert_eq!(r.download_mbps, 20.0);
+        assert_eq!(r.upload_mbps, 19.5);
+        assert_eq!(r.download_sent_mbps, None);
+        assert_eq!(r.jitter_ms, Some(1.6));
+        assert_eq!(r.packet_loss, Some(0.07));
+        assert_eq!(r.retransmits, None);
+        assert_eq!(r.ping_ms, None);
+        assert_eq!(r.server, "site-b.example:5201");
+    }
+
+    fn client(udp: bool) -> Iperf3 {
+        Iperf3 {
+            server: "10.8.0.1".to_string(),
+            port: Some(5202),
+            udp,
+            seconds: Some(5),
+            bitrate: udp.then(|| "200M".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_client_args() {
+        assert_eq!(
+            client(false).args(None, false),
+            ["-c", "10.8.0.1", "-J", "-p", "5202", "-t", "5"]
+        );
+        assert_eq!(
+            client(true).args(Some(&Bind::Interface("wg0".to_string())), true),
+            [
+                "-c", "10.8.0.1", "-J", "-p", "5202", "-u", "-t", "5", "-b", "200M", "--bind-dev",
+                "wg0", "-R"
+            ]
+        );
+        let bound = client(false).args(Some(&Bind::SourceIp("10.8.0.2".to_string())), false);
+        assert_eq!(bound[bound.len() - 2..], ["-B", "10.8.0.2"]);
+    }
+
+    /// Needs iperf3 installed: `cargo test -p speedynotify -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_against_local_server() {
+        let port = 5299;
+        let mut server = std::process::Command::new("iperf3")
+            .args(["-s", "-B", "127.0.0.1", "-p", &port.to_string()])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .expect("iperf3 is not installed");
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        let iperf = Iperf3 {
+            server: "127.0.0.1".to_string(),
+            port: Some(port),
+            udp: false,
+            seconds: Some(1),
+            bitrate: None,
+        };
+        let result = iperf.run(None, None).await;
+        server.kill().ok();
+        server.wait().ok();
+
+        let r = result.unwrap();
+        assert_eq!(r.backend, "iperf3");
+        assert!(r.download_mbps > 0.0 && r.upload_mbps > 0.0);
+        assert_eq!(r.server, format!("127.0.0.1:{port}"));
+        assert!(r.retransmits.is_some());
+    }
+
+    #[test]
+    fn test_parse_error_output() {
+        let err = parse(r#"{"start": {}, "end": {}, "error": "unable to connect to server: Connection refused"}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("Connection refused"));
+        assert!(parse(r#"{"start": {}}"#).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! LibreSpeed backend (`librespeed-cli --json`)
+//!
+//! Intended for self-hosted LibreSpeed instances: `SPEEDY_LIBRESPEED_SERVERS`
+//! points at a server list, either a URL or a local JSON file, in the format
+//! librespeed-cli expects. Without it the public LibreSpeed list is used.
+
+use std::env;
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+
+use super::{Bind, SpeedtestBackend};
+use crate::model::{parse_timestamp, SpeedResult};
+
+/// librespeed-cli settings from `SPEEDY_LIBRESPEED_*`
+pub struct LibreSpeed {
+    /// Server list URL or local JSON file
+    servers: Option<String>,
+    /// Server id within the list
+    server_id: Option<u32>,
+}
+
+impl LibreSpeed {
+    pub fn from_env() -> Self {
+        LibreSpeed {
+            servers: env::var("SPEEDY_LIBRESPEED_SERVERS")
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            server_id: env::var("SPEEDY_LIBRESPEED_SERVER_ID")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+        }
+    }
+
+    pub(super) fn args(&self, bind: Option<&Bind>) -> Vec<String> {
+        let mut args = vec!["--json".to_string()];
+        if let Some(list) = &self.servers {
+            let flag = if list.starts_with("http://") || list.starts_with("https://") {
+                "--server-json"
+            } else {
+                "--local-json"
+            };
+            args.push(flag.to_string());
+            args.push(list.clone());
+        }
+        if let Some(id) = self.server_id {
+            args.push("--server".to_string());
+            args.push(id.to_string());
+        }
+        match bind {
+            Some(Bind::Interface(name)) => {
+                args.push("--interface".to_string());
+                args.push(name.clone());
+            }
+            Some(Bind::SourceIp(ip)) => {
+                args.push("--source".to_string());
+                args.push(ip.clone());
+            }
+            None => {}
+        }
+        args
+    }
+}
+
+// librespeed-cli JSON format: one entry per tested server
+#[derive(Debug, Deserialize)]
+struct LsResult {
+    timestamp: Option<String>,
+    server: Option<LsServer>,
+    client: Option<LsClient>,
+    bytes_sent: Option<u64>,
+    bytes_received: Option<u64>,
+    ping: f64,
+    jitter: Option<f64>,
+    /// Mbps
+    upload: f64,
+    download: f64,
+    /// Share image URL, only with --share
+    share: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LsServer {
+    name: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LsClient {
+    /// e.g. "AS64500 Example ISP"
+    org: Option<String>,
+}
+
+impl SpeedtestBackend for LibreSpeed {
+    fn name(&self) -> &'static str {
+        "librespeed"
+    }
+
+    /// `server_id` is an Ookla id; use `SPEEDY_LIBRESPEED_SERVER_ID` instead
+    async fn run(&self, _server_id: Option<u32>, bind: Option<&Bind>) -> Result<SpeedResult> {
+        let output = Command::new("librespeed-cli")
+            .args(self.args(bind))
+            .output()
+            .await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("librespeed-cli exited {}: {}", output.status, stderr.trim());
+        }
+        parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+pub(super) fn parse(json: &str) -> Result<SpeedResult> {
+    let results: Vec<LsResult> = serde_json::from_str(json)?;
+    let res = results
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("librespeed-cli returned no results"))?;
+    let server = res
+        .server
+        .map(|s| match (s.name, s.url) {
+            (Some(name), Some(url)) => format!("{} ({})", name, url),
+            (name, url) => name.or(url).unwrap_or_default(),
+        })
+        .unwrap_or_default();
+
+    Ok(SpeedResult {
+        jitter_ms: res.jitter,
+        bytes_received: res.bytes_received,
+        bytes_sent: res.bytes_sent,
+        server,
+        isp: res.client.and_then(|c| c.org).unwrap_or_default(),
+        timestamp: parse_timestamp(res.timestamp.as_deref()),
+        result_url: res.share.filter(|s| !s.is_empty()),
+        ..SpeedResult::new("librespeed", res.download, res.upload, res.ping)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_librespeed_json() {
+        let sample_json = json!([{
+            "timestamp": "2024-01-15T10:30:00.123456789Z",
+            "server": {"name": "Home", "url": "https://speed.example.net/backend/"},
+            "client": {"ip": "203.0.113.7", "hostname": "", "org": "AS64500 Example ISP"},
+            "bytes_sent": 31_457_280,
+            "bytes_received": 157_286_400,
+            "ping": 8.25,
+            "jitter": 0.91,
+            "upload": 48.73,
+            "download": 412.9,
+            "share": ""
+        }]);
+
+        let r = parse(&sample_json.to_string()).unwrap();
+        assert_eq!(r.backend, "librespeed");
+        assert_eq!(r.download_mbps, 412.9);
+        assert_eq!(r.upload_mbps, 48.73);
+        assert_eq!(r.ping_ms, Some(8.25));
+        assert_eq!(r.jitter_ms, Some(0.91));
+        assert_eq!(r.packet_loss, None);
+        assert_eq!(r.bytes_sent, Some(31_457_280));
+        assert_eq!(r.server, "Home (https://speed.example.net/backend/)");
+        assert_eq!(r.isp, "AS64500 Example ISP");
+        assert_eq!(r.timestamp.timestamp(), 1_705_314_600);
+        assert_eq!(r.result_url, None);
+        assert!(parse("[]").is_err());
+    }
+
+    #[test]
+    fn test_server_list_flag() {
+        let ls = |servers: &str| LibreSpeed {
+            servers: Some(servers.to_string()),
+            server_id: Some(3),
+        };
+        assert_eq!(
+            ls("https://speed.example.net/servers.json").args(None),
+            vec![
+                "--json",
+                "--server-json",
+                "https://speed.example.net/servers.json",
+                "--server",
+                "3"
+            ]
+        );
+        assert_eq!(
+            ls("/etc/speedynotify/servers.json").args(None)[1],
+            "--local-json"
+        );
+        let bound = ls("/etc/speedynotify/servers.json")
+            .args(Some(&Bind::SourceIp("192.0.2.10".to_string())));
+        assert_eq!(bound[bound.len() - 2..], ["--source", "192.0.2.10"]);
+    }
+}
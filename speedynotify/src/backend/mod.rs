@@ -0,0 +1,221 @@
+//! Speed test backends
+//!
+//! Each backend runs one speed test tool and returns the backend-neutral
+//! `SpeedResult` from `crate::model`. Backends are tried in the order given
+//! by `SPEEDY_BACKENDS` until one succeeds.
+
+mod iperf3;
+mod librespeed;
+mod ookla;
+mod python;
+pub mod remote;
+mod text;
+
+pub use iperf3::Iperf3;
+pub use librespeed::LibreSpeed;
+pub use ookla::Ookla;
+pub use python::PythonCli;
+pub use text::TextCli;
+
+use std::env;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use tracing::warn;
+
+use crate::model::SpeedResult;
+
+/// Interface implemented by every speed test backend
+///
+/// Used generically (`B: SpeedtestBackend`) so the async method needs no boxing.
+pub trait SpeedtestBackend {
+    /// Short name used in logs and `SpeedResult::backend`
+    fn name(&self) -> &'static str;
+
+    /// Run one test, optionally against a specific server and bound to one
+    /// uplink
+    async fn run(&self, server_id: Option<u32>, bind: Option<&Bind>) -> Result<SpeedResult>;
+}
+
+/// Where a test's traffic leaves the host, for multi-WAN setups
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bind {
+    /// Network interface, e.g. "eth1"
+    Interface(String),
+    /// Local source address, e.g. "192.0.2.10"
+    SourceIp(String),
+}
+
+impl Bind {
+    /// The interface name or address, used to label the WAN
+    pub fn label(&self) -> &str {
+        match self {
+            Bind::Interface(name) => name,
+            Bind::SourceIp(ip) => ip,
+        }
+    }
+}
+
+/// Selectable backends (`SPEEDY_BACKENDS`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    /// Ookla Speedtest CLI (`speedtest -f json`)
+    Ookla,
+    /// Python speedtest-cli (`speedtest-cli --json`)
+    Python,
+    /// Human-readable `speedtest` output, parsed best-effort
+    Text,
+    /// iperf3 against `SPEEDY_IPERF3_SERVER` (LAN or site-to-site links)
+    Iperf3,
+    /// librespeed-cli against `SPEEDY_LIBRESPEED_SERVERS` (self-hosted LibreSpeed)
+    LibreSpeed,
+}
+
+impl FromStr for BackendKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "ookla" => Ok(BackendKind::Ookla),
+            "python" | "speedtest-cli" => Ok(BackendKind::Python),
+            "text" => Ok(BackendKind::Text),
+            "iperf3" | "iperf" => Ok(BackendKind::Iperf3),
+            "librespeed" | "librespeed-cli" => Ok(BackendKind::LibreSpeed),
+            other => bail!(
+                "unknown speedtest backend '{other}' \
+                 (expected ookla, python, text, iperf3 or librespeed)"
+            ),
+        }
+    }
+}
+
+/// Backends to try, in order
+///
+/// `SPEEDY_BACKENDS` is a comma-separated list such as "ookla,python".
+/// Without it, Ookla is tried first, then Python speedtest-cli, then the
+/// text parser if `SPEEDY_ALLOW_TEXT_FALLBACK` is set. The text parser is
+/// opt-in because a bare `speedtest` may be a GUI build on some desktops.
+pub fn backend_order() -> Result<Vec<BackendKind>> {
+    match env::var("SPEEDY_BACKENDS") {
+        Ok(list) if !list.trim().is_empty() => parse_backend_list(&list),
+        _ => {
+            let allow_text = env::var("SPEEDY_ALLOW_TEXT_FALLBACK")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false);
+            let mut order = vec![BackendKind::Ookla, BackendKind::Python];
+            if allow_text {
+                order.push(BackendKind::Text);
+            }
+            Ok(order)
+        }
+    }
+}
+
+fn parse_backend_list(list: &str) -> Result<Vec<BackendKind>> {
+    let mut order = Vec::new();
+    for name in list.split(',').filter(|n| !n.trim().is_empty()) {
+        let kind: BackendKind = name.parse()?;
+        if !order.contains(&kind) {
+            order.push(kind);
+        }
+    }
+    if order.is_empty() {
+        bail!("SPEEDY_BACKENDS lists no backends");
+    }
+    Ok(order)
+}
+
+/// Run the first backend in `order` that succeeds
+pub async fn run_with_fallback(
+    order: &[BackendKind],
+    server_id: Option<u32>,
+    bind: Option<&Bind>,
+) -> Result<SpeedResult> {
+    let mut last_err = None;
+
+    for kind in order {
+        let result = match kind {
+            BackendKind::Ookla => run_backend(&Ookla, server_id, bind).await,
+            BackendKind::Python => run_backend(&PythonCli, server_id, bind).await,
+            BackendKind::Text => run_backend(&TextCli, server_id, bind).await,
+            BackendKind::Iperf3 => match Iperf3::from_env() {
+                Ok(iperf3) => run_backend(&iperf3, server_id, bind).await,
+                Err(e) => Err(e),
+            },
+            BackendKind::LibreSpeed => run_backend(&LibreSpeed::from_env(), server_id, bind).await,
+        };
+
+        match result {
+            Ok(r) => return Ok(r),
+            Err(e) => {
+                warn!(backend = ?kind, error = %e, "Speedtest backend failed; trying next backend");
+                last_err = Some(e);
+            }
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| anyhow!("no speedtest backend configured")))
+}
+
+async fn run_backend<B: SpeedtestBackend>(
+    backend: &B,
+    server_id: Option<u32>,
+    bind: Option<&Bind>,
+) -> Result<SpeedResult> {
+    let mut result = backend
+        .run(server_id, bind)
+        .await
+        .map_err(|e| anyhow!("{}: {e}", backend.name()))?;
+    if let (true, Some(Bind::Interface(name))) = (result.interface.is_empty(), bind) {
+        result.interface = name.clone();
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_list() {
+        assert_eq!(
+            parse_backend_list("python, ookla,python").unwrap(),
+            vec![BackendKind::Python, BackendKind::Ookla]
+        );
+        assert_eq!(parse_backend_list("Text").unwrap(), vec![BackendKind::Text]);
+        assert_eq!(
+            parse_backend_list("iperf3").unwrap(),
+            vec![BackendKind::Iperf3]
+        );
+        assert_eq!(
+            parse_backend_list("librespeed,ookla").unwrap(),
+            vec![BackendKind::LibreSpeed, BackendKind::Ookla]
+        );
+        assert!(parse_backend_list("ookla,fast").is_err());
+        assert!(parse_backend_list(" , ").is_err());
+    }
+
+    /// Reports only the local address, like iperf3
+    struct AddressOnly;
+
+    impl SpeedtestBackend for AddressOnly {
+        fn name(&self) -> &'static str {
+            "iperf3"
+        }
+
+        async fn run(&self, _server_id: Option<u32>, _bind: Option<&Bind>) -> Result<SpeedResult> {
+            Ok(SpeedResult {
+                local_ip: Some("10.8.0.2".to_string()),
+                ..SpeedResult::new("iperf3", 900.0, 900.0, 1.0)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bound_interface_labels_result() {
+        let bind = Bind::Interface("wg0".to_string());
+        let r = run_backend(&AddressOnly, None, Some(&bind)).await.unwrap();
+        assert_eq!(r.interface, "wg0");
+        assert_eq!(r.local_ip.as_deref(), Some("10.8.0.2"));
+    }
+}
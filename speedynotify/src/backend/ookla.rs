@@ -0,0 +1,229 @@
+//! Ookla Speedtest CLI backend (`speedtest -f json`)
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+
+use super::{Bind, SpeedtestBackend};
+use crate::model::{parse_timestamp, SpeedResult};
+
+pub struct Ookla;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OoklaResult {
+    timestamp: Option<String>,
+    ping: Ping,
+    download: Transfer,
+    upload: Transfer,
+    /// Percent; absent when the server does not support the measurement
+    packet_loss: Option<f64>,
+    isp: Option<String>,
+    interface: Option<Interface>,
+    server: Option<Server>,
+    result: Option<ResultLink>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Ping {
+    latency: f64,
+    jitter: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Transfer {
+    /// Bytes per second
+    bandwidth: f64,
+    bytes: Option<u64>,
+    /// Latency measured during the transfer
+    latency: Option<LoadedLatency>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoadedLatency {
+    /// Interquartile mean, ms
+    iqm: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Interface {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Server {
+    id: Option<u32>,
+    name: Option<String>,
+    location: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultLink {
+    url: Option<String>,
+}
+
+impl SpeedtestBackend for Ookla {
+    fn name(&self) -> &'static str {
+        "ookla"
+    }
+
+    async fn run(&self, server_id: Option<u32>, bind: Option<&Bind>) -> Result<SpeedResult> {
+        match run_cli(server_id, bind, true).await {
+            Ok(stdout) => parse(&stdout),
+            Err(e) => {
+                // Older builds do not know the acceptance flags
+                let msg = e.to_string().to_lowercase();
+                if msg.contains("unknown option") || msg.contains("unrecognized option") {
+                    if let Ok(stdout) = run_cli(server_id, bind, false).await {
+                        return parse(&stdout);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+async fn run_cli(server_id: Option<u32>, bind: Option<&Bind>, accept: bool) -> Result<String> {
+    let mut cmd = Command::new("speedtest");
+    if accept {
+        cmd.arg("--accept-license").arg("--accept-gdpr");
+    }
+    cmd.arg("-f").arg("json");
+    if let Some(id) = server_id {
+        cmd.arg("-s").arg(id.to_string());
+    }
+    match bind {
+        Some(Bind::Interface(name)) => {
+            cmd.arg("-I").arg(name);
+        }
+        Some(Bind::SourceIp(ip)) => {
+            cmd.arg("-i").arg(ip);
+        }
+        None => {}
+    }
+    let output = cmd.output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Ookla speedtest exited {}: {}", output.status, stderr);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub(super) fn parse(json: &str) -> Result<SpeedResult> {
+    let res: OoklaResult = serde_json::from_str(json)?;
+    let server = res
+        .server
+        .map(|s| {
+            format!(
+                "{}{}{}",
+                s.name.unwrap_or_default(),
+                s.location.map(|l| format!(", {}", l)).unwrap_or_default(),
+                s.id.map(|i| format!(" (#{})", i)).unwrap_or_default()
+            )
+        })
+        .unwrap_or_default();
+
+    Ok(SpeedResult {
+        jitter_ms: res.ping.jitter,
+        packet_loss: res.packet_loss,
+        download_latency_ms: res.download.latency.and_then(|l| l.iqm),
+        upload_latency_ms: res.upload.latency.and_then(|l| l.iqm),
+        bytes_received: res.download.bytes,
+        bytes_sent: res.upload.bytes,
+        server,
+        isp: res.isp.unwrap_or_default(),
+        interface: res.interface.and_then(|i| i.name).unwrap_or_default(),
+        timestamp: parse_timestamp(res.timestamp.as_deref()),
+        result_url: res.result.and_then(|r| r.url),
+        ..SpeedResult::new(
+            "ookla",
+            // bytes/s -> Mbps
+            res.download.bandwidth * 8.0 / 1_000_000.0,
+            res.upload.bandwidth * 8.0 / 1_000_000.0,
+            res.ping.latency,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_ookla_json() {
+        let sample_json = json!({
+            "type": "result",
+            "timestamp": "2024-01-15T10:30:00Z",
+            "ping": {
+                "jitter": 1.234,
+                "latency": 15.5
+            },
+            "download": {
+                "bandwidth": 125000000,
+                "bytes": 50000000,
+                "elapsed": 5000,
+                "latency": {"iqm": 48.7, "low": 11.2, "high": 230.5, "jitter": 6.1}
+            },
+            "upload": {
+                "bandwidth": 25000000,
+                "bytes": 10000000,
+                "elapsed": 4000,
+                "latency": {"iqm": 31.0, "low": 12.0, "high": 90.0, "jitter": 3.4}
+            },
+            "packetLoss": 0.5,
+            "isp": "Example ISP",
+            "interface": {
+                "name": "eth0"
+            },
+            "server": {
+                "id": 1234,
+                "name": "TestServer",
+                "location": "TestCity",
+                "sponsor": "TestSponsor"
+            },
+            "result": {
+                "id": "abc",
+                "url": "https://www.speedtest.net/result/c/abc"
+            }
+        });
+
+        let r = parse(&sample_json.to_string()).unwrap();
+        assert_eq!(r.backend, "ookla");
+        // 125000000 bytes/sec = 1000 Mbps
+        assert_eq!(r.download_mbps, 1000.0);
+        assert_eq!(r.upload_mbps, 200.0);
+        assert_eq!(r.ping_ms, Some(15.5));
+        assert_eq!(r.jitter_ms, Some(1.234));
+        assert_eq!(r.packet_loss, Some(0.5));
+        assert_eq!(r.download_latency_ms, Some(48.7));
+        assert_eq!(r.upload_latency_ms, Some(31.0));
+        assert_eq!(r.bytes_received, Some(50_000_000));
+        assert_eq!(r.bytes_sent, Some(10_000_000));
+        assert_eq!(r.isp, "Example ISP");
+        assert_eq!(r.interface, "eth0");
+        assert_eq!(r.server, "TestServer, TestCity (#1234)");
+        assert_eq!(r.timestamp.to_rfc3339(), "2024-01-15T10:30:00+00:00");
+        assert_eq!(
+            r.result_url.as_deref(),
+            Some("https://www.speedtest.net/result/c/abc")
+        );
+    }
+
+    #[test]
+    fn test_parse_minimal_ookla_json() {
+        let r = parse(
+            r#"{"ping": {"latency": 9.0}, "download": {"bandwidth": 1000000},
+                "upload": {"bandwidth": 500000}}"#,
+        )
+        .unwrap();
+        assert_eq!(r.download_mbps, 8.0);
+        assert_eq!(r.upload_mbps, 4.0);
+        assert_eq!(r.jitter_ms, None);
+        assert_eq!(r.packet_loss, None);
+        assert_eq!(r.download_latency_ms, None);
+        assert_eq!(r.server, "");
+        assert!(parse("not json").is_err());
+    }
+}
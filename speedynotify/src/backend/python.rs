@@ -0,0 +1,168 @@
+//! Python speedtest-cli backend (`speedtest-cli --json`)
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+use tracing::warn;
+
+use super::{Bind, SpeedtestBackend};
+use crate::model::{parse_timestamp, SpeedResult};
+
+pub struct PythonCli;
+
+// speedtest-cli (Python) JSON format
+#[derive(Debug, Deserialize)]
+struct PyResult {
+    /// Bits per second
+    download: f64,
+    upload: f64,
+    ping: f64,
+    timestamp: Option<String>,
+    bytes_sent: Option<u64>,
+    bytes_received: Option<u64>,
+    /// Share image URL, only with --share
+    share: Option<String>,
+    client: Option<PyClient>,
+    server: Option<PyServer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PyClient {
+    isp: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PyServer {
+    id: Option<String>,
+    name: Option<String>,
+    sponsor: Option<String>,
+}
+
+impl SpeedtestBackend for PythonCli {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    async fn run(&self, server_id: Option<u32>, bind: Option<&Bind>) -> Result<SpeedResult> {
+        let source = match bind {
+            Some(Bind::SourceIp(ip)) => Some(ip),
+            Some(Bind::Interface(name)) => {
+                bail!("speedtest-cli cannot bind to interface {name}; use --source-ip")
+            }
+            None => None,
+        };
+        // Try python variants, preferring HTTPS (--secure) to avoid 403s
+        let candidates: &[(&str, &[&str])] = &[
+            ("speedtest-cli", &["--json", "--secure"][..]),
+            ("speedtest-cli", &["--json"][..]),
+            ("python3", &["-m", "speedtest", "--json", "--secure"][..]),
+            ("python3", &["-m", "speedtest", "--json"][..]),
+            ("python", &["-m", "speedtest", "--json", "--secure"][..]),
+            ("python", &["-m", "speedtest", "--json"][..]),
+            ("speedtest", &["--json"][..]),
+        ];
+        for (bin, base_args) in candidates {
+            let mut args: Vec<String> = base_args.iter().map(|s| s.to_string()).collect();
+            if let Some(id) = server_id {
+                args.push("-s".into());
+                args.push(id.to_string());
+            }
+            if let Some(ip) = source {
+                args.push("--source".into());
+                args.push(ip.clone());
+            }
+            match Command::new(bin).args(&args).output().await {
+                Ok(output) if output.status.success() => {
+                    return parse(&String::from_utf8_lossy(&output.stdout));
+                }
+                Ok(output) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    warn!(bin = %bin, status = %output.status, stderr = %stderr, "Speedtest command failed");
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        bail!(
+            "no compatible speedtest CLI found. Install 'speedtest-cli' (python) and retry. \
+             Fedora: sudo dnf install -y speedtest-cli (or: sudo dnf install -y python3-speedtest-cli) \
+             Or via pipx: pipx install speedtest-cli"
+        )
+    }
+}
+
+pub(super) fn parse(json: &str) -> Result<SpeedResult> {
+    let res: PyResult = serde_json::from_str(json)?;
+    let server = res
+        .server
+        .map(|s| {
+            let name = s.name.unwrap_or_default();
+            let sponsor = s.sponsor.unwrap_or_default();
+            let id = s.id.unwrap_or_default();
+            if !id.is_empty() {
+                format!("{} ({}) #{}", name, sponsor, id)
+            } else {
+                format!("{} ({})", name, sponsor)
+            }
+        })
+        .unwrap_or_default();
+
+    Ok(SpeedResult {
+        bytes_received: res.bytes_received,
+        bytes_sent: res.bytes_sent,
+        server,
+        isp: res.client.and_then(|c| c.isp).unwrap_or_default(),
+        timestamp: parse_timestamp(res.timestamp.as_deref()),
+        result_url: res.share,
+        ..SpeedResult::new(
+            "python",
+            // bits/s -> Mbps
+            res.download / 1_000_000.0,
+            res.upload / 1_000_000.0,
+            res.ping,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_python_speedtest_json() {
+        let sample_json = json!({
+            "ping": 15.5,
+            "download": 125000000.0,
+            "upload": 25000000.0,
+            "timestamp": "2024-01-15T10:30:00.123456Z",
+            "bytes_sent": 31457280,
+            "bytes_received": 157286400,
+            "share": null,
+            "client": {
+                "ip": "1.2.3.4",
+                "isp": "Example ISP"
+            },
+            "server": {
+                "id": "4321",
+                "name": "TestServer",
+                "sponsor": "TestSponsor",
+                "country": "US"
+            }
+        });
+
+        let r = parse(&sample_json.to_string()).unwrap();
+        assert_eq!(r.backend, "python");
+        // 125000000 bps = 125 Mbps
+        assert_eq!(r.download_mbps, 125.0);
+        assert_eq!(r.upload_mbps, 25.0);
+        assert_eq!(r.ping_ms, Some(15.5));
+        assert_eq!(r.jitter_ms, None);
+        assert_eq!(r.bytes_received, Some(157_286_400));
+        assert_eq!(r.isp, "Example ISP");
+        assert_eq!(r.interface, "");
+        assert_eq!(r.server, "TestServer (TestSponsor) #4321");
+        assert_eq!(r.timestamp.timestamp(), 1_705_314_600);
+        assert_eq!(r.result_url, None);
+    }
+}
@@ -0,0 +1,163 @@
+//! Speed tests on remote hosts over SSH
+//!
+//! The speed test CLI runs on the remote host through `common::RemoteExecutor`;
+//! only its output comes back and is parsed here, exactly like a local run.
+//! The backend order is the same as locally, except that iperf3 is skipped.
+
+use anyhow::{anyhow, bail, Result};
+use common::{CommandOutput, RemoteExecutor};
+use tracing::warn;
+
+use super::{librespeed, ookla, python, text, BackendKind, LibreSpeed};
+use crate::model::SpeedResult;
+
+/// Run the first backend in `order` that succeeds on the executor's host
+pub async fn run_with_fallback(
+    executor: &RemoteExecutor,
+    order: &[BackendKind],
+    server_id: Option<u32>,
+) -> Result<SpeedResult> {
+    let mut last_err = None;
+
+    for kind in order {
+        match run_backend(executor, *kind, server_id).await {
+            Ok(r) => return Ok(r),
+            Err(e) => {
+                warn!(host = %executor.server().name, backend = ?kind, error = %e, "Remote speedtest backend failed; trying next backend");
+                last_err = Some(e);
+            }
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| anyhow!("no speedtest backend configured")))
+}
+
+type Parser = fn(&str) -> Result<SpeedResult>;
+
+/// Command line and parser for one backend
+fn command(
+    kind: BackendKind,
+    server_id: Option<u32>,
+) -> Result<(&'static str, Vec<String>, Parser)> {
+    let owned = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    let mut command = match kind {
+        BackendKind::Ookla => (
+            "speedtest",
+            owned(&["--accept-license", "--accept-gdpr", "-f", "json"]),
+            ookla::parse as Parser,
+        ),
+        BackendKind::Python => (
+            "speedtest-cli",
+            owned(&["--json", "--secure"]),
+            python::parse as Parser,
+        ),
+        BackendKind::Text => ("speedtest", Vec::new(), text::parse as Parser),
+        BackendKind::LibreSpeed => (
+            "librespeed-cli",
+            LibreSpeed::from_env().args(None),
+            librespeed::parse as Parser,
+        ),
+        BackendKind::Iperf3 => bail!("iperf3 is not supported on remote hosts"),
+    };
+    if let (Some(id), BackendKind::Ookla | BackendKind::Python) = (server_id, kind) {
+        command.1.push("-s".to_string());
+        command.1.push(id.to_string());
+    }
+    Ok(command)
+}
+
+async fn run_backend(
+    executor: &RemoteExecutor,
+    kind: BackendKind,
+    server_id: Option<u32>,
+) -> Result<SpeedResult> {
+    let (cmd, args, parse) = command(kind, server_id)?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = executor.execute_command_output(cmd, &args).await?;
+    let stdout = stdout_of(cmd, &output)?;
+    parse(stdout).map_err(|e| match output.stderr.trim() {
+        "" => anyhow!("{}: {e}", cmd),
+        stderr => anyhow!("{}: {e} (stderr: {stderr})", cmd),
+    })
+}
+
+/// Stdout of a remote run, or an error carrying its exit status and stderr
+fn stdout_of<'a>(cmd: &str, output: &'a CommandOutput) -> Result<&'a str> {
+    let stderr = output.stderr.trim();
+    if !output.success() {
+        let status = output
+            .status
+            .map_or_else(|| "a signal".to_string(), |code| format!("status {code}"));
+        if stderr.is_empty() {
+            bail!("'{}' exited with {}", cmd, status);
+        }
+        bail!("'{}' exited with {}: {}", cmd, status, stderr);
+    }
+    if output.stdout.trim().is_empty() {
+        bail!(
+            "'{}' produced no output (is it installed on the host?)",
+            cmd
+        );
+    }
+    Ok(&output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_commands() {
+        let (cmd, args, _) = command(BackendKind::Ookla, Some(1234)).unwrap();
+        assert_eq!(cmd, "speedtest");
+        assert_eq!(
+            args,
+            [
+                "--accept-license",
+                "--accept-gdpr",
+                "-f",
+                "json",
+                "-s",
+                "1234"
+            ]
+        );
+        let (cmd, args, _) = command(BackendKind::Python, None).unwrap();
+        assert_eq!(
+            (cmd, args.join(" ")),
+            ("speedtest-cli", "--json --secure".to_string())
+        );
+        assert!(command(BackendKind::Iperf3, None).is_err());
+    }
+
+    #[test]
+    fn test_failed_remote_run_reports_status_and_stderr() {
+        let output = CommandOutput {
+            status: Some(1),
+            stdout: "{}".to_string(),
+            stderr: "Limit reached: too many requests\n".to_string(),
+        };
+        let err = stdout_of("speedtest", &output).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "'speedtest' exited with status 1: Limit reached: too many requests"
+        );
+
+        let output = CommandOutput {
+            status: None,
+            stdout: String::new(),
+            stderr: String::new(),
+        };
+        let err = stdout_of("speedtest", &output).unwrap_err().to_string();
+        assert_eq!(err, "'speedtest' exited with a signal");
+
+        let output = CommandOutput {
+            status: Some(0),
+            stdout: "{\"type\":\"result\"}".to_string(),
+            stderr: String::new(),
+        };
+        assert_eq!(
+            stdout_of("speedtest", &output).unwrap(),
+            "{\"type\":\"result\"}"
+        );
+    }
+}
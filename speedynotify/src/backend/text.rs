@@ -0,0 +1,119 @@
+//! Best-effort parser for human-readable `speedtest` output
+
+use anyhow::{anyhow, bail, Result};
+use tokio::process::Command;
+
+use super::{Bind, SpeedtestBackend};
+use crate::model::SpeedResult;
+
+pub struct TextCli;
+
+impl SpeedtestBackend for TextCli {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    async fn run(&self, _server_id: Option<u32>, bind: Option<&Bind>) -> Result<SpeedResult> {
+        let mut cmd = Command::new("speedtest");
+        // Same flags as the Ookla CLI this output comes from
+        match bind {
+            Some(Bind::Interface(name)) => {
+                cmd.arg("-I").arg(name);
+            }
+            Some(Bind::SourceIp(ip)) => {
+                cmd.arg("-i").arg(ip);
+            }
+            None => {}
+        }
+        let output = cmd.output().await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("'speedtest' failed: {}\n{}", output.status, stderr);
+        }
+        parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+pub(super) fn parse(stdout: &str) -> Result<SpeedResult> {
+    let mut down_mbps: Option<f64> = None;
+    let mut up_mbps: Option<f64> = None;
+    let mut ping_ms: Option<f64> = None;
+
+    for line in stdout.lines() {
+        let l = line.trim();
+        let lower = l.to_lowercase();
+        if down_mbps.is_none() && lower.contains("download") {
+            down_mbps = parse_speed_line(l);
+        }
+        if up_mbps.is_none() && lower.contains("upload") {
+            up_mbps = parse_speed_line(l);
+        }
+        if ping_ms.is_none() && (lower.contains("ping") || lower.contains("latency")) {
+            ping_ms = parse_first_number(l);
+        }
+    }
+
+    let down =
+        down_mbps.ok_or_else(|| anyhow!("could not parse download speed from text output"))?;
+    let up = up_mbps.ok_or_else(|| anyhow!("could not parse upload speed from text output"))?;
+    Ok(SpeedResult::new("text", down, up, ping_ms.unwrap_or(0.0)))
+}
+
+fn parse_speed_line(s: &str) -> Option<f64> {
+    // Extract first float and unit, normalize to Mbps
+    let num = parse_first_number(s)?;
+    let sl = s.to_lowercase();
+    if sl.contains("gbps") || sl.contains("gbit/s") {
+        Some(num * 1000.0)
+    } else if sl.contains("mbps") || sl.contains("mbit/s") {
+        Some(num)
+    } else if sl.contains("kbps") || sl.contains("kbit/s") {
+        Some(num / 1000.0)
+    } else if sl.contains("bps") {
+        Some(num / 1_000_000.0)
+    }
+    // bits per second
+    else {
+        Some(num)
+    }
+}
+
+fn parse_first_number(s: &str) -> Option<f64> {
+    let mut start = None;
+    let mut end = None;
+    for (i, ch) in s.char_indices() {
+        if start.is_none() {
+            if ch.is_ascii_digit() {
+                start = Some(i);
+            }
+        } else if !(ch.is_ascii_digit() || ch == '.') {
+            end = Some(i);
+            break;
+        }
+    }
+    let sfx = match (start, end) {
+        (Some(a), Some(b)) => &s[a..b],
+        (Some(a), None) => &s[a..],
+        _ => return None,
+    };
+    sfx.parse::<f64>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_text_output() {
+        let out = "   Server: Example - Chicago, IL (id: 1234)\n\
+                   Idle Latency:    12.34 ms   (jitter: 0.50ms)\n\
+                       Download:   940.12 Mbps (data used: 1.1 GB)\n\
+                         Upload:     0.04 Gbps (data used: 45 MB)\n";
+        let r = parse(out).unwrap();
+        assert_eq!(r.backend, "text");
+        assert_eq!(r.download_mbps, 940.12);
+        assert_eq!(r.upload_mbps, 40.0);
+        assert_eq!(r.ping_ms, Some(12.34));
+        assert!(parse("Download: 5 Mbps").is_err());
+    }
+}
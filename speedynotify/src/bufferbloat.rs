@@ -0,0 +1,151 @@
+//! Bufferbloat grade from latency under load
+//!
+//! Ookla measures latency while the download and upload are running. The
+//! increase over idle latency shows how much the router queues under load,
+//! which is what SQM (fq_codel, cake) is meant to keep small. Grade bands
+//! follow the widely used Waveform test, without its A+.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::bail;
+
+use crate::model::SpeedResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Grade {
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+
+impl Grade {
+    /// Grade for a latency increase under load in ms
+    pub fn from_increase(ms: f64) -> Self {
+        if ms < 30.0 {
+            Grade::A
+        } else if ms < 60.0 {
+            Grade::B
+        } else if ms < 200.0 {
+            Grade::C
+        } else if ms < 400.0 {
+            Grade::D
+        } else {
+            Grade::F
+        }
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Grade::A => "A",
+            Grade::B => "B",
+            Grade::C => "C",
+            Grade::D => "D",
+            Grade::F => "F",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for Grade {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_uppercase().as_str() {
+            "A" => Ok(Grade::A),
+            "B" => Ok(Grade::B),
+            "C" => Ok(Grade::C),
+            "D" => Ok(Grade::D),
+            "F" => Ok(Grade::F),
+            other => bail!("unknown bufferbloat grade '{other}' (expected A, B, C, D or F)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bufferbloat {
+    /// Worse of download/upload loaded latency minus idle latency
+    pub increase_ms: f64,
+    pub grade: Grade,
+}
+
+impl Bufferbloat {
+    /// `None` when the backend did not measure idle and loaded latency
+    pub fn from_result(result: &SpeedResult) -> Option<Self> {
+        let idle = result.ping_ms?;
+        let loaded = match (result.download_latency_ms, result.upload_latency_ms) {
+            (Some(down), Some(up)) => down.max(up),
+            (down, up) => down.or(up)?,
+        };
+        let increase_ms = (loaded - idle).max(0.0);
+        Some(Bufferbloat {
+            increase_ms,
+            grade: Grade::from_increase(increase_ms),
+        })
+    }
+
+    /// "Bufferbloat: B (+42 ms under load; down 51 ms, up 38 ms)"
+    pub fn line(&self, result: &SpeedResult) -> String {
+        let mut loaded = Vec::new();
+        if let Some(ms) = result.download_latency_ms {
+            loaded.push(format!("down {:.0} ms", ms));
+        }
+        if let Some(ms) = result.upload_latency_ms {
+            loaded.push(format!("up {:.0} ms", ms));
+        }
+        format!(
+            "Bufferbloat: {} (+{:.0} ms under load; {})",
+            self.grade,
+            self.increase_ms,
+            loaded.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loaded(ping: f64, down: Option<f64>, up: Option<f64>) -> SpeedResult {
+        SpeedResult {
+            download_latency_ms: down,
+            upload_latency_ms: up,
+            ..SpeedResult::new("ookla", 500.0, 40.0, ping)
+        }
+    }
+
+    #[test]
+    fn test_grade_bands() {
+        assert_eq!(Grade::from_increase(4.0), Grade::A);
+        assert_eq!(Grade::from_increase(30.0), Grade::B);
+        assert_eq!(Grade::from_increase(150.0), Grade::C);
+        assert_eq!(Grade::from_increase(399.9), Grade::D);
+        assert_eq!(Grade::from_increase(400.0), Grade::F);
+        assert!(Grade::A < Grade::F);
+        assert_eq!("b".parse::<Grade>().unwrap(), Grade::B);
+        assert!("E".parse::<Grade>().is_err());
+    }
+
+    #[test]
+    fn test_bufferbloat_from_loaded_latency() {
+        let r = loaded(9.0, Some(51.2), Some(38.0));
+        let b = Bufferbloat::from_result(&r).unwrap();
+        assert!((b.increase_ms - 42.2).abs() < 1e-9);
+        assert_eq!(b.grade, Grade::B);
+        assert_eq!(
+            b.line(&r),
+            "Bufferbloat: B (+42 ms under load; down 51 ms, up 38 ms)"
+        );
+
+        // Only one direction measured; loaded below idle counts as no increase
+        let b = Bufferbloat::from_result(&loaded(9.0, None, Some(8.5))).unwrap();
+        assert_eq!(b.increase_ms, 0.0);
+        assert_eq!(b.grade, Grade::A);
+
+        assert!(Bufferbloat::from_result(&loaded(9.0, None, None)).is_none());
+    }
+}
@@ -0,0 +1,403 @@
+//! Speed test history and rolling baseline
+//!
+//! Every result is added to `speedynotify-history.jsonl` in the state
+//! directory. The baseline is the per-metric median of the results from the
+//! last few days, so a run can be judged against what the line normally
+//! delivers instead of a fixed minimum. Results older than the baseline
+//! window are dropped as new ones are recorded.
+
+use std::fs;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::model::{Origin, SpeedResult};
+
+/// History file (JSON lines)
+const HISTORY_FILE: &str = "speedynotify-history.jsonl";
+
+/// Results needed in the window before the baseline is trusted
+const MIN_BASELINE_SAMPLES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub timestamp: DateTime<Utc>,
+    pub backend: String,
+    pub download_mbps: f64,
+    pub upload_mbps: f64,
+    /// Missing when the backend did not measure it
+    #[serde(default)]
+    pub ping_ms: Option<f64>,
+    pub jitter_ms: Option<f64>,
+    pub packet_loss: Option<f64>,
+    /// Interface or source IP of a multi-WAN run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wan: Option<String>,
+    /// Remote host the test ran on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+}
+
+impl Sample {
+    pub fn new(result: &SpeedResult, origin: &Origin) -> Self {
+        let mut sample = Sample::from(result);
+        match origin {
+            Origin::Local => {}
+            Origin::Wan(name) => sample.wan = Some(name.clone()),
+            Origin::Host(name) => sample.host = Some(name.clone()),
+        }
+        sample
+    }
+
+    fn origin(&self) -> Origin {
+        match (&self.host, &self.wan) {
+            (Some(host), _) => Origin::Host(host.clone()),
+            (None, Some(wan)) => Origin::Wan(wan.clone()),
+            (None, None) => Origin::Local,
+        }
+    }
+}
+
+impl From<&SpeedResult> for Sample {
+    fn from(r: &SpeedResult) -> Self {
+        Sample {
+            timestamp: r.timestamp,
+            backend: r.backend.to_string(),
+            download_mbps: r.download_mbps,
+            upload_mbps: r.upload_mbps,
+            ping_ms: r.ping_ms,
+            jitter_ms: r.jitter_ms,
+            packet_loss: r.packet_loss,
+            wan: None,
+            host: None,
+        }
+    }
+}
+
+/// Add one result to the history file, dropping results more than
+/// `keep_days` days older than it
+pub fn record(sample: &Sample, keep_days: u32) -> Result<()> {
+    let path = common::state::state_path(crate::STATE_APP, HISTORY_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create state directory {}", parent.display()))?;
+    }
+    let mut kept = prune(load(), sample.timestamp, keep_days);
+    kept.push(sample.clone());
+    let mut text = String::new();
+    for s in &kept {
+        text.push_str(&serde_json::to_string(s)?);
+        text.push('\n');
+    }
+    // Replace atomically so a crash mid-write keeps the old history
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, text).with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// Results from the last `days` days before `now`
+fn prune(history: Vec<Sample>, now: DateTime<Utc>, days: u32) -> Vec<Sample> {
+    let since = now - Duration::days(i64::from(days));
+    history.into_iter().filter(|s| s.timestamp > since).collect()
+}
+
+/// Every stored result; unreadable lines are skipped
+pub fn load() -> Vec<Sample> {
+    let path = common::state::state_path(crate::STATE_APP, HISTORY_FILE);
+    let Ok(text) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    parse_lines(&text)
+}
+
+fn parse_lines(text: &str) -> Vec<Sample> {
+    let mut skipped = 0;
+    let list = text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| {
+            let parsed = serde_json::from_str(l).ok();
+            if parsed.is_none() {
+                skipped += 1;
+            }
+            parsed
+        })
+        .collect();
+    if skipped > 0 {
+        warn!(skipped, "Skipped unreadable history lines");
+    }
+    list
+}
+
+/// Median of each metric over the last `days` days
+#[derive(Debug, Clone, PartialEq)]
+pub struct Baseline {
+    pub days: u32,
+    pub samples: usize,
+    pub download_mbps: f64,
+    pub upload_mbps: f64,
+    /// `None` when no result in the window measured ping
+    pub ping_ms: Option<f64>,
+}
+
+impl Baseline {
+    /// Baseline from results measured at `origin` by `backend` in
+    /// `(now - days, now)`; `None` until there are enough of them. Backends
+    /// measure different paths (iperf3 a LAN or tunnel, Ookla the internet),
+    /// so their results are never mixed.
+    pub fn from_history(
+        history: &[Sample],
+        origin: &Origin,
+        backend: &str,
+        now: DateTime<Utc>,
+        days: u32,
+    ) -> Option<Self> {
+        let since = now - Duration::days(i64::from(days));
+        let window: Vec<&Sample> = history
+            .iter()
+            .filter(|s| s.origin() == *origin && s.backend == backend)
+            .filter(|s| s.timestamp > since && s.timestamp < now)
+            .collect();
+        if window.len() < MIN_BASELINE_SAMPLES {
+            return None;
+        }
+        Some(Baseline {
+            days,
+            samples: window.len(),
+            download_mbps: median(window.iter().map(|s| s.download_mbps).collect())?,
+            upload_mbps: median(window.iter().map(|s| s.upload_mbps).collect())?,
+            ping_ms: median(window.iter().filter_map(|s| s.ping_ms).collect()),
+        })
+    }
+
+    /// "Down 212 Mbps (−38% vs 7-day median 342)"
+    pub fn compare(&self, label: &str, value: f64, median: f64, unit: &str) -> String {
+        format!(
+            "{} {} {} ({} vs {}-day median {})",
+            label,
+            number(value),
+            unit,
+            percent_change(value, median),
+            self.days,
+            number(median)
+        )
+    }
+
+    /// Current result against the baseline, one metric per part; ping only
+    /// when both sides measured it
+    pub fn summary(&self, result: &SpeedResult) -> String {
+        let mut parts = vec![
+            self.compare("Down", result.download_mbps, self.download_mbps, "Mbps"),
+            self.compare("Up", result.upload_mbps, self.upload_mbps, "Mbps"),
+        ];
+        if let (Some(ping), Some(median)) = (result.ping_ms, self.ping_ms) {
+            parts.push(self.compare("Ping", ping, median, "ms"));
+        }
+        parts.join(" | ")
+    }
+}
+
+/// Percent `value` lies below `baseline` (negative when above)
+pub fn drop_pct(value: f64, baseline: f64) -> f64 {
+    if baseline <= 0.0 {
+        return 0.0;
+    }
+    (1.0 - value / baseline) * 100.0
+}
+
+/// "−38%", "+5%", "±0%"
+fn percent_change(value: f64, baseline: f64) -> String {
+    let change = -drop_pct(value, baseline).round();
+    if change > 0.0 {
+        format!("+{:.0}%", change)
+    } else if change < 0.0 {
+        format!("\u{2212}{:.0}%", -change)
+    } else {
+        "±0%".to_string()
+    }
+}
+
+/// Whole numbers from 10 up, one decimal below
+fn number(v: f64) -> String {
+    if v.abs() >= 10.0 {
+        format!("{:.0}", v)
+    } else {
+        format!("{:.1}", v)
+    }
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 20, 2, 10, 0).unwrap()
+    }
+
+    fn sample(days_ago: i64, down: f64, up: f64, ping: f64) -> Sample {
+        Sample {
+            timestamp: now() - Duration::days(days_ago),
+            backend: "ookla".to_string(),
+            download_mbps: down,
+            upload_mbps: up,
+            ping_ms: Some(ping),
+            jitter_ms: None,
+            packet_loss: None,
+            wan: None,
+            host: None,
+        }
+    }
+
+    #[test]
+    fn test_baseline_is_median_of_window() {
+        let history = vec![
+            sample(9, 10.0, 1.0, 90.0), // outside the window
+            sample(6, 350.0, 21.0, 12.0),
+            sample(4, 342.0, 20.0, 11.0),
+            sample(2, 120.0, 19.0, 40.0), // one bad night does not move the median
+            sample(1, 345.0, 20.5, 10.0),
+        ];
+        let b = Baseline::from_history(&history, &Origin::Local, "ookla", now(), 7).unwrap();
+        assert_eq!(b.samples, 4);
+        assert_eq!(b.download_mbps, 343.5);
+        assert_eq!(b.upload_mbps, 20.25);
+        assert_eq!(b.ping_ms, Some(11.5));
+
+        // Too few results for a baseline
+        assert!(Baseline::from_history(&history[..3], &Origin::Local, "ookla", now(), 7).is_none());
+    }
+
+    #[test]
+    fn test_baseline_per_backend() {
+        let lan = |days_ago, down| Sample {
+            backend: "iperf3".to_string(),
+            ..sample(days_ago, down, 900.0, 1.0)
+        };
+        let history = vec![
+            sample(4, 340.0, 20.0, 11.0),
+            lan(4, 940.0),
+            sample(3, 345.0, 20.0, 10.0),
+            lan(2, 935.0),
+            sample(1, 342.0, 21.0, 12.0),
+            lan(1, 945.0),
+        ];
+        let b = Baseline::from_history(&history, &Origin::Local, "ookla", now(), 7).unwrap();
+        assert_eq!(b.samples, 3);
+        assert_eq!(b.download_mbps, 342.0);
+        let b = Baseline::from_history(&history, &Origin::Local, "iperf3", now(), 7).unwrap();
+        assert_eq!(b.download_mbps, 940.0);
+        assert!(Baseline::from_history(&history, &Origin::Local, "python", now(), 7).is_none());
+    }
+
+    #[test]
+    fn test_unmeasured_ping_is_left_out_of_baseline() {
+        let unmeasured = |days_ago, down| Sample {
+            ping_ms: None,
+            ..sample(days_ago, down, 20.0, 0.0)
+        };
+        let history = vec![
+            unmeasured(3, 940.0),
+            sample(2, 930.0, 20.0, 12.0),
+            unmeasured(1, 950.0),
+        ];
+        let b = Baseline::from_history(&history, &Origin::Local, "ookla", now(), 7).unwrap();
+        assert_eq!(b.ping_ms, Some(12.0));
+
+        let none = vec![
+            unmeasured(3, 940.0),
+            unmeasured(2, 930.0),
+            unmeasured(1, 950.0),
+        ];
+        let b = Baseline::from_history(&none, &Origin::Local, "ookla", now(), 7).unwrap();
+        assert_eq!(b.ping_ms, None);
+        let r = SpeedResult::new("iperf3", 940.0, 20.0, 12.0);
+        assert!(!b.summary(&r).contains("Ping"));
+
+        // Records written before ping was optional still load
+        let old = r#"{"timestamp":"2024-06-19T02:10:00Z","backend":"ookla","download_mbps":1.0,"upload_mbps":1.0,"ping_ms":9.0,"jitter_ms":null,"packet_loss":null}"#;
+        assert_eq!(parse_lines(old)[0].ping_ms, Some(9.0));
+    }
+
+    #[test]
+    fn test_baseline_per_origin() {
+        let eth1 = Origin::Wan("eth1".to_string());
+        let on = |origin: &Origin, s: Sample| {
+            let r = SpeedResult {
+                ping_ms: s.ping_ms,
+                ..SpeedResult::new("ookla", s.download_mbps, s.upload_mbps, 0.0)
+            };
+            Sample {
+                timestamp: s.timestamp,
+                ..Sample::new(&r, origin)
+            }
+        };
+        let history = vec![
+            sample(3, 900.0, 40.0, 8.0),
+            on(&eth1, sample(3, 50.0, 10.0, 30.0)),
+            on(&eth1, sample(2, 48.0, 10.0, 31.0)),
+            on(&eth1, sample(1, 52.0, 10.0, 29.0)),
+            on(&Origin::Host("eth1".to_string()), sample(1, 5.0, 1.0, 90.0)),
+        ];
+        let b = Baseline::from_history(&history, &eth1, "ookla", now(), 7).unwrap();
+        assert_eq!(b.download_mbps, 50.0);
+        // Unbound runs never mix with a WAN's results
+        assert!(Baseline::from_history(&history, &Origin::Local, "ookla", now(), 7).is_none());
+    }
+
+    #[test]
+    fn test_comparison_text() {
+        let b = Baseline {
+            days: 7,
+            samples: 7,
+            download_mbps: 342.0,
+            upload_mbps: 20.0,
+            ping_ms: Some(9.6),
+        };
+        let r = SpeedResult::new("ookla", 212.4, 21.0, 9.6);
+        assert_eq!(
+            b.summary(&r),
+            "Down 212 Mbps (\u{2212}38% vs 7-day median 342) | Up 21 Mbps (+5% vs 7-day median 20) \
+             | Ping 9.6 ms (±0% vs 7-day median 9.6)"
+        );
+        assert!((drop_pct(212.4, 342.0) - 37.9).abs() < 0.1);
+        assert!(drop_pct(400.0, 342.0) < 0.0);
+        assert_eq!(drop_pct(5.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_history_lines_round_trip() {
+        let s = sample(0, 300.0, 20.0, 10.0);
+        let mut text = serde_json::to_string(&s).unwrap();
+        text.push_str("\nnot json\n\n");
+        assert_eq!(parse_lines(&text), vec![s]);
+    }
+
+    #[test]
+    fn test_prune_keeps_baseline_window() {
+        let history = vec![
+            sample(30, 100.0, 10.0, 10.0),
+            sample(8, 200.0, 10.0, 10.0),
+            sample(6, 300.0, 10.0, 10.0),
+            sample(1, 400.0, 10.0, 10.0),
+        ];
+        let kept = prune(history, now(), 7);
+        let downs: Vec<f64> = kept.iter().map(|s| s.download_mbps).collect();
+        assert_eq!(downs, [300.0, 400.0]);
+    }
+}
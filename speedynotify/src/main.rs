@@ -1,10 +1,30 @@
+mod alert;
+mod backend;
+mod bufferbloat;
+mod history;
+mod model;
+mod report;
+mod thresholds;
+
+use alert::AlertStates;
+use backend::Bind;
+use bufferbloat::Grade;
+use chrono::Utc;
 use clap::Parser;
-use common::{dotenv_init, http_client, send_gotify_speedynotify, send_ntfy_speedynotify};
-use serde::Deserialize;
+use common::{
+    dotenv_init, http_client, parse_servers, send_gotify_speedynotify, send_ntfy_speedynotify,
+    RemoteExecutor, Server,
+};
+use history::Baseline;
+use model::{Origin, SpeedResult};
+use report::Measurement;
+use thresholds::Thresholds;
 use std::env;
-use tokio::process::Command;
 use tracing::{error, info, warn};
 
+/// Name of speedynotify's state directory (override: `SPEEDYNOTIFY_STATE_DIR`)
+const STATE_APP: &str = "speedynotify";
+
 #[derive(Parser, Debug)]
 #[command(name = "speedynotify")]
 #[command(about = "Run Ookla speedtest and send Gotify summary")]
@@ -17,67 +37,68 @@ struct Args {
     #[arg(long)]
     min_up: Option<f64>,
 
-    /// Optional server id to target
+    /// Maximum acceptable ping (idle latency) in ms
     #[arg(long)]
-    server_id: Option<u32>,
+    max_ping: Option<f64>,
 
-    /// Suppress stdout; only send Gotify
-    #[arg(long, default_value_t = false)]
-    quiet: bool,
-}
+    /// Maximum acceptable jitter in ms (backends that measure it)
+    #[arg(long)]
+    max_jitter: Option<f64>,
 
-#[derive(Debug, Deserialize)]
-struct OoklaResult {
-    ping: Ping,
-    download: Transfer,
-    upload: Transfer,
-    isp: Option<String>,
-    interface: Option<Interface>,
-    server: Option<Server>,
-}
+    /// Maximum acceptable packet loss in percent (backends that measure it)
+    #[arg(long)]
+    max_loss: Option<f64>,
 
-#[derive(Debug, Deserialize)]
-struct Ping {
-    latency: f64,
-}
+    /// Mark the run degraded when download or upload falls more than this
+    /// many percent below the rolling median of recent results
+    #[arg(long)]
+    baseline_drop: Option<f64>,
 
-#[derive(Debug, Deserialize)]
-struct Transfer {
-    bandwidth: f64,
-}
+    /// Worst acceptable bufferbloat grade (A-F) from latency under load
+    #[arg(long)]
+    min_bufferbloat_grade: Option<Grade>,
 
-#[derive(Debug, Deserialize)]
-struct Interface {
-    name: Option<String>,
-}
+    /// Days of history in the rolling baseline
+    #[arg(long)]
+    baseline_days: Option<u32>,
 
-#[derive(Debug, Deserialize)]
-struct Server {
-    id: Option<u32>,
-    name: Option<String>,
-    location: Option<String>,
-}
+    /// Only send "Degraded" after this many degraded runs in a row
+    #[arg(long)]
+    degraded_after: Option<u32>,
 
-// speedtest-cli (Python) JSON format
-#[derive(Debug, Deserialize)]
-struct PyResult {
-    download: f64,
-    upload: f64,
-    ping: f64,
-    client: Option<PyClient>,
-    server: Option<PyServer>,
-}
+    /// Do not send "OK" notifications; only state changes (degraded,
+    /// recovered) are pushed
+    #[arg(long, default_value_t = false)]
+    ok_on_change_only: bool,
 
-#[derive(Debug, Deserialize)]
-struct PyClient {
-    isp: Option<String>,
-}
+    /// Optional server id to target
+    #[arg(long)]
+    server_id: Option<u32>,
+
+    /// Test through this network interface (repeat for each WAN)
+    #[arg(long = "interface", value_name = "NAME")]
+    interface: Vec<String>,
 
-#[derive(Debug, Deserialize)]
-struct PyServer {
-    id: Option<String>,
-    name: Option<String>,
-    sponsor: Option<String>,
+    /// Test from this local source address (repeat for each WAN)
+    #[arg(long = "source-ip", value_name = "IP")]
+    source_ip: Vec<String>,
+
+    /// Comma-separated hosts to run the speedtest on over SSH
+    /// (name:user@host or user@host, as for updatemon; defaults to UPDATE_SERVERS)
+    #[arg(long)]
+    servers: Option<String>,
+
+    /// Also test this host when --servers is given (always on for UPDATE_SERVERS)
+    #[arg(long, default_value_t = false)]
+    local: bool,
+
+    /// SSH key path for remote hosts
+    #[arg(long)]
+    ssh_key: Option<String>,
+
+    /// Suppress stdout; only send Gotify
+    #[arg(long, default_value_t = false)]
+    quiet: bool,
 }
 
 #[tokio::main]
@@ -102,255 +123,252 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Resolve thresholds with env fallbacks
-    let min_down = args
-        .min_down
-        .or_else(|| env::var("SPEEDTEST_MIN_DOWN").ok()?.parse().ok());
-    let min_up = args
-        .min_up
-        .or_else(|| env::var("SPEEDTEST_MIN_UP").ok()?.parse().ok());
+    let thresholds = Thresholds {
+        min_down: args
+            .min_down
+            .or_else(|| env::var("SPEEDTEST_MIN_DOWN").ok()?.parse().ok()),
+        min_up: args
+            .min_up
+            .or_else(|| env::var("SPEEDTEST_MIN_UP").ok()?.parse().ok()),
+        max_ping: args
+            .max_ping
+            .or_else(|| env::var("SPEEDTEST_MAX_PING").ok()?.parse().ok()),
+        max_jitter: args
+            .max_jitter
+            .or_else(|| env::var("SPEEDTEST_MAX_JITTER").ok()?.parse().ok()),
+        max_loss: args
+            .max_loss
+            .or_else(|| env::var("SPEEDTEST_MAX_LOSS").ok()?.parse().ok()),
+        baseline_drop: args
+            .baseline_drop
+            .or_else(|| env::var("SPEEDTEST_BASELINE_DROP").ok()?.parse().ok()),
+        min_grade: args.min_bufferbloat_grade.or_else(|| {
+            env::var("SPEEDTEST_MIN_BUFFERBLOAT_GRADE")
+                .ok()?
+                .parse()
+                .ok()
+        }),
+    };
+    let baseline_days = args
+        .baseline_days
+        .or_else(|| env::var("SPEEDTEST_BASELINE_DAYS").ok()?.parse().ok())
+        .unwrap_or(7);
+    let degraded_after = args
+        .degraded_after
+        .or_else(|| env::var("SPEEDY_DEGRADED_AFTER").ok()?.parse().ok())
+        .unwrap_or(1);
+    let ok_on_change_only = args.ok_on_change_only
+        || env::var("SPEEDY_OK_ON_CHANGE_ONLY")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
     let server_id = args
         .server_id
         .or_else(|| env::var("SPEEDTEST_SERVER_ID").ok()?.parse().ok());
 
-    // Try Ookla CLI first; fall back to python speedtest-cli if needed
-    match run_and_parse_ookla(server_id).await {
-        Ok((down_mbps, up_mbps, ping_ms, isp, iface, server)) => {
-            emit_and_notify(
-                args.quiet, down_mbps, up_mbps, ping_ms, isp, iface, server, min_down, min_up,
-            )
-            .await?;
-        }
-        Err(e) => {
-            let err_s = format!("{}", e).to_lowercase();
-            // If Ookla flags are not recognized, try without acceptance flags
-            if err_s.contains("unknown option") || err_s.contains("unrecognized option") {
-                if let Ok((down_mbps, up_mbps, ping_ms, isp, iface, server)) =
-                    run_and_parse_ookla_no_accept(server_id).await
-                {
-                    emit_and_notify(
-                        args.quiet, down_mbps, up_mbps, ping_ms, isp, iface, server, min_down,
-                        min_up,
-                    )
-                    .await?;
-                    return Ok(());
-                }
-            }
-            warn!(error = %e, "Ookla speedtest failed, falling back to Python speedtest-cli if available");
-            match run_and_parse_python(server_id).await {
-                Ok((down_mbps, up_mbps, ping_ms, isp, iface, server)) => {
-                    emit_and_notify(
-                        args.quiet, down_mbps, up_mbps, ping_ms, isp, iface, server, min_down,
-                        min_up,
-                    )
-                    .await?;
-                }
-                Err(e2) => {
-                    // Avoid launching GUI variants of 'speedtest' by default
-                    let allow_text = std::env::var("SPEEDY_ALLOW_TEXT_FALLBACK")
-                        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-                        .unwrap_or(false);
-                    if allow_text {
-                        warn!(
-                            error = %e2,
-                            "Python speedtest-cli unavailable, attempting text fallback (SPEEDY_ALLOW_TEXT_FALLBACK=1)"
-                        );
-                        let (down_mbps, up_mbps, ping_ms, isp, iface, server) =
-                            run_and_parse_text().await?;
-                        emit_and_notify(
-                            args.quiet, down_mbps, up_mbps, ping_ms, isp, iface, server, min_down,
-                            min_up,
-                        )
-                        .await?;
-                    } else {
-                        error!(
-                            "No JSON-capable speedtest CLI found. Install 'speedtest-cli' (python) and retry. \
-                            Fedora: sudo dnf install -y speedtest-cli (or: sudo dnf install -y python3-speedtest-cli) \
-                            Or via pipx: pipx install speedtest-cli"
-                        );
-                        return Err("speedtest-cli not installed".into());
-                    }
+    // Backends in SPEEDY_BACKENDS order, first success wins
+    let order = backend::backend_order()?;
+    let wans = wan_binds(&args.interface, &args.source_ip);
+    let (list, from_env) =
+        server_list(args.servers.as_deref(), env::var("UPDATE_SERVERS").ok());
+    let servers = remote_servers(&list, args.local || from_env)?;
+    if !servers.is_empty() && !wans.is_empty() {
+        return Err("--interface/--source-ip cannot be combined with --servers".into());
+    }
+    let ssh_key = args.ssh_key.or_else(|| env::var("UPDATE_SSH_KEY").ok());
+
+    let mut outcomes = Vec::new();
+    if !servers.is_empty() {
+        // One host at a time: sites may share an uplink
+        for server in servers {
+            let origin = Origin::Host(server.name.clone());
+            let outcome = match RemoteExecutor::new(server, ssh_key.as_deref()) {
+                Ok(executor) => {
+                    backend::remote::run_with_fallback(&executor, &order, server_id).await
                 }
-            }
+                Err(e) => Err(e),
+            };
+            outcomes.push((origin, outcome));
+        }
+    } else if !wans.is_empty() {
+        // One uplink at a time so the tests do not compete for bandwidth
+        for bind in &wans {
+            let origin = Origin::Wan(bind.label().to_string());
+            let outcome = backend::run_with_fallback(&order, server_id, Some(bind)).await;
+            outcomes.push((origin, outcome));
         }
+    } else {
+        let result = match backend::run_with_fallback(&order, server_id, None).await {
+            Ok(r) => r,
+            Err(e) => {
+                error!(error = %e, "All speedtest backends failed");
+                return Err(e.into());
+            }
+        };
+        outcomes.push((Origin::Local, Ok(result)));
     }
 
+    let history = history::load();
+    let measurements: Vec<Measurement> = outcomes
+        .into_iter()
+        .map(|(origin, outcome)| evaluate(origin, outcome, &thresholds, &history, baseline_days))
+        .collect();
+
+    emit_and_notify(args.quiet, &measurements, degraded_after, ok_on_change_only).await?;
+
     Ok(())
 }
 
-async fn run_and_parse_ookla(
-    server_id: Option<u32>,
-) -> Result<(f64, f64, f64, String, String, String), Box<dyn std::error::Error>> {
-    let mut cmd = Command::new("speedtest");
-    cmd.arg("--accept-license")
-        .arg("--accept-gdpr")
-        .arg("-f")
-        .arg("json");
-    if let Some(id) = server_id {
-        cmd.arg("-s").arg(id.to_string());
-    }
-    let output = cmd.output().await?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Ookla speedtest exited {}: {}", output.status, stderr).into());
+/// Uplinks from `--interface`/`--source-ip`, or `SPEEDTEST_INTERFACES` /
+/// `SPEEDTEST_SOURCE_IPS` (comma-separated) when neither flag is given
+fn wan_binds(interfaces: &[String], source_ips: &[String]) -> Vec<Bind> {
+    let env_list = |var: &str| -> Vec<String> {
+        env::var(var)
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    };
+    let (interfaces, source_ips) = if interfaces.is_empty() && source_ips.is_empty() {
+        (
+            env_list("SPEEDTEST_INTERFACES"),
+            env_list("SPEEDTEST_SOURCE_IPS"),
+        )
+    } else {
+        (interfaces.to_vec(), source_ips.to_vec())
+    };
+    interfaces
+        .into_iter()
+        .map(Bind::Interface)
+        .chain(source_ips.into_iter().map(Bind::SourceIp))
+        .collect()
+}
+
+/// Server list from `--servers`, falling back to `UPDATE_SERVERS` like updatemon,
+/// and whether it came from the fallback
+///
+/// A fallback list always includes this host: `UPDATE_SERVERS` is shared with
+/// updatemon, and setting it must not stop the home uplink being tested.
+fn server_list(servers: Option<&str>, fallback: Option<String>) -> (String, bool) {
+    match (servers, fallback) {
+        (Some(list), _) => (list.to_string(), false),
+        (None, Some(list)) if !list.trim().is_empty() => (list, true),
+        _ => (String::new(), false),
     }
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let res: OoklaResult = serde_json::from_str(&stdout)?;
-    let down_mbps = (res.download.bandwidth * 8.0) / 1_000_000.0;
-    let up_mbps = (res.upload.bandwidth * 8.0) / 1_000_000.0;
-    let ping_ms = res.ping.latency;
-    let iface = res.interface.and_then(|i| i.name).unwrap_or_default();
-    let server = res
-        .server
-        .map(|s| {
-            format!(
-                "{}{}{}",
-                s.name.unwrap_or_default(),
-                s.location.map(|l| format!(", {}", l)).unwrap_or_default(),
-                s.id.map(|i| format!(" (#{})", i)).unwrap_or_default()
-            )
-        })
-        .unwrap_or_default();
-    let isp = res.isp.unwrap_or_default();
-    Ok((down_mbps, up_mbps, ping_ms, isp, iface, server))
 }
 
-async fn run_and_parse_ookla_no_accept(
-    server_id: Option<u32>,
-) -> Result<(f64, f64, f64, String, String, String), Box<dyn std::error::Error>> {
-    let mut cmd = Command::new("speedtest");
-    cmd.arg("-f").arg("json");
-    if let Some(id) = server_id {
-        cmd.arg("-s").arg(id.to_string());
+/// Hosts to test over SSH, plus this host with `--local`; empty for a plain
+/// local run, including a list that names only this host ("local")
+fn remote_servers(list: &str, local: bool) -> anyhow::Result<Vec<Server>> {
+    let mut servers = parse_servers(list)?;
+    if servers.iter().all(Server::is_local) {
+        return Ok(Vec::new());
     }
-    let output = cmd.output().await?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!(
-            "Ookla speedtest (no-accept) exited {}: {}",
-            output.status, stderr
-        )
-        .into());
+    if local && !servers.iter().any(Server::is_local) {
+        servers.push(Server::local());
     }
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let res: OoklaResult = serde_json::from_str(&stdout)?;
-    let down_mbps = (res.download.bandwidth * 8.0) / 1_000_000.0;
-    let up_mbps = (res.upload.bandwidth * 8.0) / 1_000_000.0;
-    let ping_ms = res.ping.latency;
-    let iface = res.interface.and_then(|i| i.name).unwrap_or_default();
-    let server = res
-        .server
-        .map(|s| {
-            format!(
-                "{}{}{}",
-                s.name.unwrap_or_default(),
-                s.location.map(|l| format!(", {}", l)).unwrap_or_default(),
-                s.id.map(|i| format!(" (#{})", i)).unwrap_or_default()
-            )
-        })
-        .unwrap_or_default();
-    let isp = res.isp.unwrap_or_default();
-    Ok((down_mbps, up_mbps, ping_ms, isp, iface, server))
+    Ok(servers)
 }
 
-async fn run_and_parse_python(
-    server_id: Option<u32>,
-) -> Result<(f64, f64, f64, String, String, String), Box<dyn std::error::Error>> {
-    // Try python variants, preferring HTTPS (--secure) to avoid 403s
-    let candidates: &[(&str, &[&str])] = &[
-        ("speedtest-cli", &["--json", "--secure"][..]),
-        ("speedtest-cli", &["--json"][..]),
-        ("python3", &["-m", "speedtest", "--json", "--secure"][..]),
-        ("python3", &["-m", "speedtest", "--json"][..]),
-        ("python", &["-m", "speedtest", "--json", "--secure"][..]),
-        ("python", &["-m", "speedtest", "--json"][..]),
-        ("speedtest", &["--json"][..]),
-    ];
-    for (bin, base_args) in candidates {
-        let mut args: Vec<String> = base_args.iter().map(|s| s.to_string()).collect();
-        if let Some(id) = server_id {
-            args.push("-s".into());
-            args.push(id.to_string());
-        }
-        let out = Command::new(bin).args(&args).output().await;
-        match out {
-            Ok(output) if output.status.success() => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let res: PyResult = serde_json::from_str(&stdout)?;
-                let down_mbps = res.download / 1_000_000.0;
-                let up_mbps = res.upload / 1_000_000.0;
-                let ping_ms = res.ping;
-                let isp = res.client.and_then(|c| c.isp).unwrap_or_default();
-                let iface = String::new();
-                let server = res
-                    .server
-                    .map(|s| {
-                        let name = s.name.unwrap_or_default();
-                        let sponsor = s.sponsor.unwrap_or_default();
-                        let id = s.id.unwrap_or_default();
-                        if !id.is_empty() {
-                            format!("{} ({}) #{}", name, sponsor, id)
-                        } else {
-                            format!("{} ({})", name, sponsor)
-                        }
-                    })
-                    .unwrap_or_default();
-                return Ok((down_mbps, up_mbps, ping_ms, isp, iface, server));
-            }
-            Ok(output) => {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                warn!(bin = %bin, status = %output.status, stderr = %stderr, "Speedtest command failed");
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
-            Err(e) => return Err(e.into()),
+/// Judge a result against its thresholds and recent history, then add it to the history
+fn evaluate(
+    origin: Origin,
+    outcome: anyhow::Result<SpeedResult>,
+    thresholds: &Thresholds,
+    history: &[history::Sample],
+    baseline_days: u32,
+) -> Measurement {
+    let result = match outcome {
+        Ok(r) => r,
+        Err(e) => {
+            error!(origin = ?origin, error = %e, "All speedtest backends failed");
+            return Measurement {
+                origin,
+                result: Err(e.to_string()),
+                baseline: None,
+                failures: Vec::new(),
+            };
         }
+    };
+    let thresholds = match origin.label() {
+        Some(label) => thresholds.for_label(label),
+        None => *thresholds,
+    };
+    let baseline = Baseline::from_history(
+        history,
+        &origin,
+        result.backend,
+        result.timestamp,
+        baseline_days,
+    );
+    if let Err(e) = history::record(&history::Sample::new(&result, &origin), baseline_days) {
+        warn!(error = %e, "Failed to record speedtest history");
+    }
+    Measurement {
+        failures: thresholds.check(&result, baseline.as_ref()),
+        origin,
+        result: Ok(result),
+        baseline,
     }
-    Err("No compatible speedtest CLI found".into())
 }
 
 async fn emit_and_notify(
     quiet: bool,
-    down_mbps: f64,
-    up_mbps: f64,
-    ping_ms: f64,
-    isp: String,
-    iface: String,
-    server: String,
-    min_down: Option<f64>,
-    min_up: Option<f64>,
+    measurements: &[Measurement],
+    degraded_after: u32,
+    ok_on_change_only: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut lines = Vec::new();
-    lines.push(format!("ISP: {} | IF: {} | Server: {}", isp, iface, server));
-    lines.push(format!(
-        "Down: {:.2} Mbps | Up: {:.2} Mbps | Ping: {:.1} ms",
-        down_mbps, up_mbps, ping_ms
-    ));
-    let human = lines.join("\n");
-
-    let mut degraded = false;
-    if let Some(min) = min_down {
-        if down_mbps < min {
-            degraded = true;
-        }
+    let now = measurements
+        .iter()
+        .find_map(|m| m.result.as_ref().ok().map(|r| r.timestamp))
+        .unwrap_or_else(Utc::now);
+
+    // Fold this run into each WAN's/host's degraded streak
+    let mut states = AlertStates::load();
+    let notices: Vec<_> = measurements
+        .iter()
+        .map(|m| states.advance(&m.origin, m.degraded(), now, degraded_after))
+        .collect();
+    if let Err(e) = states.save() {
+        warn!(error = %e, "Failed to save speedtest alert state");
     }
-    if let Some(min) = min_up {
-        if up_mbps < min {
-            degraded = true;
+
+    let mut lines = Vec::new();
+    for (m, notice) in measurements.iter().zip(&notices) {
+        if let Some(headline) = notice.headline(now) {
+            lines.push(match m.origin.label() {
+                Some(label) => format!("{}: {}", label, headline),
+                None => headline,
+            });
         }
     }
+    lines.extend(report::body(measurements));
+    let human = lines.join("\n");
 
     if !quiet {
         println!("{}", human);
     }
 
     // Record metrics
-    common::metrics::record_speedtest_result(down_mbps, up_mbps, ping_ms, degraded);
+    for m in measurements {
+        if let Ok(result) = &m.result {
+            common::metrics::record_speedtest_result(
+                m.origin.metric_label(),
+                result.download_mbps,
+                result.upload_mbps,
+                result.ping_ms,
+                result.jitter_ms,
+                result.packet_loss,
+                &m.failures.iter().map(|f| f.threshold).collect::<Vec<_>>(),
+            );
+        }
+    }
 
-    let client = http_client();
-    let title = if degraded {
-        "Speedtest: Degraded"
-    } else {
-        "Speedtest: OK"
+    let Some(title) = alert::combined_title(&notices, ok_on_change_only) else {
+        info!(?notices, "No speedtest notification for this run");
+        return Ok(());
     };
+    let client = http_client();
     // Send to Gotify (if configured)
     if let Err(e) = send_gotify_speedynotify(&client, title, &human).await {
         warn!(error = %e, "Gotify send error");
@@ -362,78 +380,5 @@ async fn emit_and_notify(
     Ok(())
 }
 
-async fn run_and_parse_text(
-) -> Result<(f64, f64, f64, String, String, String), Box<dyn std::error::Error>> {
-    let output = Command::new("speedtest").output().await?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("'speedtest' failed: {}\n{}", output.status, stderr).into());
-    }
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    let mut down_mbps: Option<f64> = None;
-    let mut up_mbps: Option<f64> = None;
-    let mut ping_ms: Option<f64> = None;
-
-    for line in stdout.lines() {
-        let l = line.trim();
-        let lower = l.to_lowercase();
-        if down_mbps.is_none() && lower.contains("download") {
-            down_mbps = parse_speed_line(l);
-        }
-        if up_mbps.is_none() && lower.contains("upload") {
-            up_mbps = parse_speed_line(l);
-        }
-        if ping_ms.is_none() && (lower.contains("ping") || lower.contains("latency")) {
-            ping_ms = parse_first_number(l);
-        }
-    }
-
-    let down = down_mbps.ok_or("could not parse download speed from text output")?;
-    let up = up_mbps.ok_or("could not parse upload speed from text output")?;
-    let ping = ping_ms.unwrap_or(0.0);
-    Ok((down, up, ping, String::new(), String::new(), String::new()))
-}
-
-fn parse_speed_line(s: &str) -> Option<f64> {
-    // Extract first float and unit, normalize to Mbps
-    let num = parse_first_number(s)?;
-    let sl = s.to_lowercase();
-    if sl.contains("gbps") || sl.contains("gbit/s") {
-        Some(num * 1000.0)
-    } else if sl.contains("mbps") || sl.contains("mbit/s") {
-        Some(num)
-    } else if sl.contains("kbps") || sl.contains("kbit/s") {
-        Some(num / 1000.0)
-    } else if sl.contains("bps") {
-        Some(num / 1_000_000.0)
-    }
-    // bits per second
-    else {
-        Some(num)
-    }
-}
-
-fn parse_first_number(s: &str) -> Option<f64> {
-    let mut start = None;
-    let mut end = None;
-    for (i, ch) in s.char_indices() {
-        if start.is_none() {
-            if ch.is_ascii_digit() {
-                start = Some(i);
-            }
-        } else if !(ch.is_ascii_digit() || ch == '.') {
-            end = Some(i);
-            break;
-        }
-    }
-    let sfx = match (start, end) {
-        (Some(a), Some(b)) => &s[a..b],
-        (Some(a), None) => &s[a..],
-        _ => return None,
-    };
-    sfx.parse::<f64>().ok()
-}
-
 #[cfg(test)]
 mod tests;
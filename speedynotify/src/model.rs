@@ -0,0 +1,109 @@
+//! Backend-neutral speed test result
+//!
+//! Every `SpeedtestBackend` converts its own output into `SpeedResult`, so
+//! thresholds, notifications and metrics never depend on a specific CLI.
+//! Fields a backend cannot measure are `None` (or empty for the labels).
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpeedResult {
+    /// Backend that produced the result ("ookla", "python", ...)
+    pub backend: &'static str,
+    pub download_mbps: f64,
+    pub upload_mbps: f64,
+    /// Download rate the sending side reported (iperf3); `download_mbps` is
+    /// what the receiver saw
+    pub download_sent_mbps: Option<f64>,
+    /// Upload rate the sending side reported (iperf3)
+    pub upload_sent_mbps: Option<f64>,
+    /// Idle latency; `None` when the backend did not measure it
+    pub ping_ms: Option<f64>,
+    pub jitter_ms: Option<f64>,
+    /// Percent of packets lost, 0-100
+    pub packet_loss: Option<f64>,
+    /// Latency while downloading (interquartile mean)
+    pub download_latency_ms: Option<f64>,
+    /// Latency while uploading (interquartile mean)
+    pub upload_latency_ms: Option<f64>,
+    /// TCP segments retransmitted (iperf3)
+    pub retransmits: Option<u64>,
+    pub bytes_received: Option<u64>,
+    pub bytes_sent: Option<u64>,
+    /// Display name of the test server, e.g. "Example, Chicago, IL (#1234)"
+    pub server: String,
+    pub isp: String,
+    /// Local network interface used for the test
+    pub interface: String,
+    /// Local address the test ran from, when the backend reports it
+    pub local_ip: Option<String>,
+    /// When the test ran, as reported by the backend
+    pub timestamp: DateTime<Utc>,
+    /// Shareable result page, when the backend publishes one
+    pub result_url: Option<String>,
+}
+
+impl SpeedResult {
+    /// Result with only the throughput and latency known
+    pub fn new(backend: &'static str, download_mbps: f64, upload_mbps: f64, ping_ms: f64) -> Self {
+        SpeedResult {
+            backend,
+            download_mbps,
+            upload_mbps,
+            download_sent_mbps: None,
+            upload_sent_mbps: None,
+            ping_ms: Some(ping_ms),
+            jitter_ms: None,
+            packet_loss: None,
+            download_latency_ms: None,
+            upload_latency_ms: None,
+            retransmits: None,
+            bytes_received: None,
+            bytes_sent: None,
+            server: String::new(),
+            isp: String::new(),
+            interface: String::new(),
+            local_ip: None,
+            timestamp: Utc::now(),
+            result_url: None,
+        }
+    }
+}
+
+/// Where a result was measured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// This host, default route
+    Local,
+    /// This host, bound to one uplink (interface name or source IP)
+    Wan(String),
+    /// A host from `--servers`, reached over SSH
+    Host(String),
+}
+
+impl Origin {
+    /// WAN or host name; `None` for a plain local run
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            Origin::Local => None,
+            Origin::Wan(name) | Origin::Host(name) => Some(name),
+        }
+    }
+
+    /// Metric label distinguishing this origin, e.g. ("wan", "eth1")
+    pub fn metric_label(&self) -> Option<(&'static str, &str)> {
+        match self {
+            Origin::Local => None,
+            Origin::Wan(name) => Some(("wan", name)),
+            Origin::Host(name) => Some(("host", name)),
+        }
+    }
+}
+
+/// Backend timestamp as UTC; now when missing or unparseable
+pub fn parse_timestamp(raw: Option<&str>) -> DateTime<Utc> {
+    raw.and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
@@ -0,0 +1,259 @@
+//! Notification body for one or more speed tests
+//!
+//! A plain run reports a single result. Multi-WAN runs (`--interface`,
+//! `--source-ip`) and remote runs (`--servers`) start with a table of all
+//! uplinks or hosts, then give each one's details, so a single notification
+//! covers every link.
+
+use crate::bufferbloat::Bufferbloat;
+use crate::history::Baseline;
+use crate::model::{Origin, SpeedResult};
+use crate::thresholds::Failure;
+
+/// One speed test and its verdict
+#[derive(Debug)]
+pub struct Measurement {
+    pub origin: Origin,
+    /// Result, or why every backend failed there
+    pub result: Result<SpeedResult, String>,
+    pub baseline: Option<Baseline>,
+    pub failures: Vec<Failure>,
+}
+
+impl Measurement {
+    /// A failed test counts as degraded too
+    pub fn degraded(&self) -> bool {
+        self.result.is_err() || !self.failures.is_empty()
+    }
+
+    fn status(&self) -> &'static str {
+        match (&self.result, self.failures.is_empty()) {
+            (Err(_), _) => "Failed",
+            (Ok(_), true) => "OK",
+            (Ok(_), false) => "Degraded",
+        }
+    }
+
+    /// Server, speeds, baseline and failed thresholds for this test
+    fn details(&self) -> Vec<String> {
+        let result = match &self.result {
+            Ok(r) => r,
+            Err(e) => return vec![format!("Error: {}", e)],
+        };
+        let mut lines = Vec::new();
+        let interface = match &result.local_ip {
+            Some(ip) if result.interface.is_empty() => ip.clone(),
+            Some(ip) => format!("{} ({})", result.interface, ip),
+            None => result.interface.clone(),
+        };
+        lines.push(format!(
+            "ISP: {} | IF: {} | Server: {}",
+            result.isp, interface, result.server
+        ));
+        let mut speeds = format!(
+            "Down: {:.2} Mbps | Up: {:.2} Mbps",
+            result.download_mbps, result.upload_mbps
+        );
+        if let Some(ping) = result.ping_ms {
+            speeds.push_str(&format!(" | Ping: {:.1} ms", ping));
+        }
+        if let Some(jitter) = result.jitter_ms {
+            speeds.push_str(&format!(" | Jitter: {:.1} ms", jitter));
+        }
+        if let Some(loss) = result.packet_loss {
+            speeds.push_str(&format!(" | Loss: {:.1}%", loss));
+        }
+        if let Some(retransmits) = result.retransmits {
+            speeds.push_str(&format!(" | Retransmits: {}", retransmits));
+        }
+        lines.push(speeds);
+        if result.download_sent_mbps.is_some() || result.upload_sent_mbps.is_some() {
+            let rate =
+                |mbps: Option<f64>| mbps.map_or_else(|| "-".to_string(), |v| format!("{:.2}", v));
+            lines.push(format!(
+                "Sent: Down: {} Mbps | Up: {} Mbps",
+                rate(result.download_sent_mbps),
+                rate(result.upload_sent_mbps)
+            ));
+        }
+        if let Some(b) = Bufferbloat::from_result(result) {
+            lines.push(b.line(result));
+        }
+        if let Some(b) = &self.baseline {
+            lines.push(b.summary(result));
+        }
+        if !self.failures.is_empty() {
+            let failed: Vec<&str> = self.failures.iter().map(|f| f.text.as_str()).collect();
+            lines.push(format!("Failed: {}", failed.join("; ")));
+        }
+        if let Some(url) = &result.result_url {
+            lines.push(format!("Result: {}", url));
+        }
+        lines
+    }
+}
+
+/// Body lines for all measurements
+pub fn body(measurements: &[Measurement]) -> Vec<String> {
+    match measurements {
+        [single] if single.origin == Origin::Local => single.details(),
+        _ => {
+            let mut lines = table(measurements);
+            for m in measurements {
+                lines.push(String::new());
+                lines.push(format!("{}:", label(m)));
+                lines.extend(m.details());
+            }
+            lines
+        }
+    }
+}
+
+fn label(m: &Measurement) -> &str {
+    m.origin.label().unwrap_or("default")
+}
+
+/// Fixed-width summary, one row per WAN or host
+fn table(measurements: &[Measurement]) -> Vec<String> {
+    let heading = match measurements.first().map(|m| &m.origin) {
+        Some(Origin::Host(_)) => "Host",
+        _ => "WAN",
+    };
+    let width = measurements
+        .iter()
+        .map(|m| label(m).len())
+        .max()
+        .unwrap_or(0)
+        .max(heading.len());
+    let mut rows = vec![format!(
+        "{:<width$}  {:>9}  {:>7}  {:>7}  Status",
+        heading, "Down Mbps", "Up Mbps", "Ping ms"
+    )];
+    for m in measurements {
+        let (down, up, ping) = match &m.result {
+            Ok(r) => (
+                format!("{:.2}", r.download_mbps),
+                format!("{:.2}", r.upload_mbps),
+                r.ping_ms
+                    .map_or_else(|| "-".to_string(), |ms| format!("{:.1}", ms)),
+            ),
+            Err(_) => ("-".to_string(), "-".to_string(), "-".to_string()),
+        };
+        rows.push(format!(
+            "{:<width$}  {:>9}  {:>7}  {:>7}  {}",
+            label(m),
+            down,
+            up,
+            ping,
+            m.status()
+        ));
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measured(origin: Origin, down: f64, failed: Option<&str>) -> Measurement {
+        Measurement {
+            result: Ok(SpeedResult {
+                isp: "Example ISP".to_string(),
+                interface: match &origin {
+                    Origin::Wan(name) => name.clone(),
+                    _ => "eth0".to_string(),
+                },
+                ..SpeedResult::new("ookla", down, 20.0, 9.64)
+            }),
+            baseline: None,
+            failures: failed
+                .map(|text| Failure {
+                    threshold: "download",
+                    text: text.to_string(),
+                })
+                .into_iter()
+                .collect(),
+            origin,
+        }
+    }
+
+    fn wan(name: &str) -> Origin {
+        Origin::Wan(name.to_string())
+    }
+
+    #[test]
+    fn test_single_result_body() {
+        let m = measured(
+            Origin::Local,
+            250.0,
+            Some("Download 250.00 Mbps < 300 Mbps"),
+        );
+        assert!(m.degraded());
+        assert_eq!(
+            body(&[m]),
+            vec![
+                "ISP: Example ISP | IF: eth0 | Server: ",
+                "Down: 250.00 Mbps | Up: 20.00 Mbps | Ping: 9.6 ms",
+                "Failed: Download 250.00 Mbps < 300 Mbps",
+            ]
+        );
+
+        let mut m = measured(wan("wg0"), 900.0, None);
+        if let Ok(r) = &mut m.result {
+            r.local_ip = Some("10.8.0.2".to_string());
+            r.download_sent_mbps = Some(940.0);
+            r.upload_sent_mbps = Some(20.5);
+        }
+        let details = m.details();
+        assert_eq!(
+            details[0],
+            "ISP: Example ISP | IF: wg0 (10.8.0.2) | Server: "
+        );
+        assert_eq!(details[2], "Sent: Down: 940.00 Mbps | Up: 20.50 Mbps");
+    }
+
+    #[test]
+    fn test_multi_wan_table() {
+        let lines = body(&[
+            measured(wan("eth0"), 940.12, None),
+            measured(wan("wwan0"), 48.2, Some("Download 48.20 Mbps < 100 Mbps")),
+            Measurement {
+                origin: wan("192.0.2.10"),
+                result: Err("ookla: no route to host".to_string()),
+                baseline: None,
+                failures: Vec::new(),
+            },
+        ]);
+        assert_eq!(
+            lines[..4],
+            [
+                "WAN         Down Mbps  Up Mbps  Ping ms  Status",
+                "eth0           940.12    20.00      9.6  OK",
+                "wwan0           48.20    20.00      9.6  Degraded",
+                "192.0.2.10          -        -        -  Failed",
+            ]
+        );
+        assert_eq!(lines[4], "");
+        assert_eq!(lines[5], "eth0:");
+        assert!(lines.contains(&"Failed: Download 48.20 Mbps < 100 Mbps".to_string()));
+        assert_eq!(lines.last().unwrap(), "Error: ookla: no route to host");
+    }
+
+    #[test]
+    fn test_remote_host_table() {
+        let host = |name: &str| Origin::Host(name.to_string());
+        let lines = body(&[
+            measured(host("hq"), 940.0, None),
+            measured(host("branch-office"), 95.5, None),
+        ]);
+        assert_eq!(
+            lines[..3],
+            [
+                "Host           Down Mbps  Up Mbps  Ping ms  Status",
+                "hq                940.00    20.00      9.6  OK",
+                "branch-office      95.50    20.00      9.6  OK",
+            ]
+        );
+        assert!(lines.contains(&"branch-office:".to_string()));
+    }
+}
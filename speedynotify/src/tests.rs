@@ -2,80 +2,6 @@
 
 #[cfg(test)]
 mod tests {
-    use serde_json::json;
-    use super::super::*;
-
-    #[test]
-    fn test_parse_ookla_json() {
-        let sample_json = json!({
-            "type": "result",
-            "timestamp": "2024-01-15T10:30:00Z",
-            "ping": {
-                "jitter": 1.234,
-                "latency": 15.5
-            },
-            "download": {
-                "bandwidth": 125000000,
-                "bytes": 50000000,
-                "elapsed": 5000
-            },
-            "upload": {
-                "bandwidth": 25000000,
-                "bytes": 10000000,
-                "elapsed": 4000
-            },
-            "isp": "Example ISP",
-            "interface": {
-                "name": "eth0"
-            },
-            "server": {
-                "name": "TestServer",
-                "location": "TestCity",
-                "sponsor": "TestSponsor"
-            }
-        });
-
-        let result: Result<OoklaResult, _> = serde_json::from_value(sample_json);
-        assert!(result.is_ok());
-
-        let ookla = result.unwrap();
-        assert_eq!(ookla.ping.latency, 15.5);
-        assert_eq!(ookla.download.bandwidth, 125000000.0);
-        assert_eq!(ookla.upload.bandwidth, 25000000.0);
-        assert_eq!(ookla.isp.unwrap(), "Example ISP");
-        assert_eq!(ookla.interface.unwrap().name.unwrap(), "eth0");
-        assert_eq!(ookla.server.as_ref().unwrap().name.as_ref().unwrap(), "TestServer");
-        assert_eq!(ookla.server.unwrap().location.unwrap(), "TestCity");
-    }
-
-    #[test]
-    fn test_parse_python_speedtest_json() {
-        let sample_json = json!({
-            "ping": 15.5,
-            "download": 125000000.0,
-            "upload": 25000000.0,
-            "client": {
-                "ip": "1.2.3.4",
-                "isp": "Example ISP"
-            },
-            "server": {
-                "name": "TestServer",
-                "sponsor": "TestSponsor",
-                "country": "US"
-            }
-        });
-
-        let result: Result<PyResult, _> = serde_json::from_value(sample_json);
-        assert!(result.is_ok());
-
-        let python = result.unwrap();
-        assert_eq!(python.ping, 15.5);
-        assert_eq!(python.download, 125000000.0);
-        assert_eq!(python.upload, 25000000.0);
-        assert_eq!(python.client.unwrap().isp.unwrap(), "Example ISP");
-        assert_eq!(python.server.unwrap().name.unwrap(), "TestServer");
-    }
-
     #[test]
     fn test_bandwidth_to_mbps() {
         // Ookla returns bandwidth in bytes per second
@@ -133,4 +59,38 @@ mod tests {
 
         assert!(!degraded, "Should not detect degradation when thresholds not set");
     }
+
+    #[test]
+    fn test_local_server_list_is_plain_local_run() {
+        use crate::remote_servers;
+
+        assert!(remote_servers("", false).unwrap().is_empty());
+        assert!(remote_servers("local", false).unwrap().is_empty());
+        assert!(remote_servers("docker-vm:local", true).unwrap().is_empty());
+
+        let servers = remote_servers("hq:admin@10.0.0.5,local", true).unwrap();
+        let names: Vec<_> = servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["hq", "localhost"]);
+
+        let servers = remote_servers("hq:admin@10.0.0.5", true).unwrap();
+        assert_eq!(servers.len(), 2);
+        assert!(servers[1].is_local());
+    }
+
+    #[test]
+    fn test_update_servers_fallback_keeps_local_run() {
+        use crate::server_list;
+
+        let env = Some("hq:admin@10.0.0.5".to_string());
+        assert_eq!(
+            server_list(None, env.clone()),
+            ("hq:admin@10.0.0.5".to_string(), true)
+        );
+        assert_eq!(
+            server_list(Some("branch:admin@10.1.0.5"), env),
+            ("branch:admin@10.1.0.5".to_string(), false)
+        );
+        assert_eq!(server_list(None, Some(" ".to_string())), (String::new(), false));
+        assert_eq!(server_list(None, None), (String::new(), false));
+    }
 }
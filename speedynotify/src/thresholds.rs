@@ -0,0 +1,300 @@
+//! Degraded-connection thresholds
+//!
+//! A run is degraded when any configured threshold fails. Latency-quality
+//! limits (ping, jitter, packet loss) only apply when the backend measured
+//! them; python speedtest-cli, for example, reports no jitter or loss.
+//! The baseline drop applies once enough history exists for a baseline, and
+//! the bufferbloat grade once the backend reports latency under load.
+
+use std::env;
+
+use crate::bufferbloat::{Bufferbloat, Grade};
+use crate::history::{drop_pct, Baseline};
+use crate::model::SpeedResult;
+
+/// Limits from `--min-*` / `--max-*` or their `SPEEDTEST_*` env fallbacks
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Thresholds {
+    /// Mbps
+    pub min_down: Option<f64>,
+    /// Mbps
+    pub min_up: Option<f64>,
+    /// ms
+    pub max_ping: Option<f64>,
+    /// ms
+    pub max_jitter: Option<f64>,
+    /// Percent
+    pub max_loss: Option<f64>,
+    /// Percent download/upload may fall below the rolling baseline
+    pub baseline_drop: Option<f64>,
+    /// Worst acceptable bufferbloat grade
+    pub min_grade: Option<Grade>,
+}
+
+/// One failed threshold
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    /// Metric label: "download", "upload", "ping", "jitter", "packet_loss",
+    /// "download_baseline", "upload_baseline" or "bufferbloat"
+    pub threshold: &'static str,
+    /// "Ping 45.1 ms > 30 ms"
+    pub text: String,
+}
+
+impl Thresholds {
+    /// Limits for one WAN of a multi-WAN run, or one host of a remote run
+    ///
+    /// `SPEEDTEST_MIN_DOWN_ETH1`, `SPEEDTEST_MAX_PING_192_0_2_10`, ... override
+    /// the shared value; the suffix is the interface, source IP or host name
+    /// in upper case with anything but letters and digits replaced by `_`.
+    pub fn for_label(&self, label: &str) -> Self {
+        let suffix: String = label
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.with_overrides(|name| env::var(format!("{name}_{suffix}")).ok())
+    }
+
+    fn with_overrides(&self, lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let num =
+            |name: &str, shared: Option<f64>| lookup(name).and_then(|v| v.parse().ok()).or(shared);
+        Thresholds {
+            min_down: num("SPEEDTEST_MIN_DOWN", self.min_down),
+            min_up: num("SPEEDTEST_MIN_UP", self.min_up),
+            max_ping: num("SPEEDTEST_MAX_PING", self.max_ping),
+            max_jitter: num("SPEEDTEST_MAX_JITTER", self.max_jitter),
+            max_loss: num("SPEEDTEST_MAX_LOSS", self.max_loss),
+            baseline_drop: num("SPEEDTEST_BASELINE_DROP", self.baseline_drop),
+            min_grade: lookup("SPEEDTEST_MIN_BUFFERBLOAT_GRADE")
+                .and_then(|v| v.parse().ok())
+                .or(self.min_grade),
+        }
+    }
+
+    /// Every threshold `result` fails, in a fixed order
+    pub fn check(&self, result: &SpeedResult, baseline: Option<&Baseline>) -> Vec<Failure> {
+        let mut failures = Vec::new();
+        if let Some(min) = self.min_down {
+            if result.download_mbps < min {
+                failures.push(Failure {
+                    threshold: "download",
+                    text: format!("Download {:.2} Mbps < {} Mbps", result.download_mbps, min),
+                });
+            }
+        }
+        if let Some(min) = self.min_up {
+            if result.upload_mbps < min {
+                failures.push(Failure {
+                    threshold: "upload",
+                    text: format!("Upload {:.2} Mbps < {} Mbps", result.upload_mbps, min),
+                });
+            }
+        }
+        if let (Some(max), Some(ping)) = (self.max_ping, result.ping_ms) {
+            if ping > max {
+                failures.push(Failure {
+                    threshold: "ping",
+                    text: format!("Ping {:.1} ms > {} ms", ping, max),
+                });
+            }
+        }
+        if let (Some(max), Some(jitter)) = (self.max_jitter, result.jitter_ms) {
+            if jitter > max {
+                failures.push(Failure {
+                    threshold: "jitter",
+                    text: format!("Jitter {:.1} ms > {} ms", jitter, max),
+                });
+            }
+        }
+        if let (Some(max), Some(loss)) = (self.max_loss, result.packet_loss) {
+            if loss > max {
+                failures.push(Failure {
+                    threshold: "packet_loss",
+                    text: format!("Packet loss {:.1}% > {}%", loss, max),
+                });
+            }
+        }
+        if let (Some(max), Some(b)) = (self.baseline_drop, baseline) {
+            if drop_pct(result.download_mbps, b.download_mbps) > max {
+                failures.push(Failure {
+                    threshold: "download_baseline",
+                    text: b.compare("Down", result.download_mbps, b.download_mbps, "Mbps"),
+                });
+            }
+            if drop_pct(result.upload_mbps, b.upload_mbps) > max {
+                failures.push(Failure {
+                    threshold: "upload_baseline",
+                    text: b.compare("Up", result.upload_mbps, b.upload_mbps, "Mbps"),
+                });
+            }
+        }
+        if let (Some(min), Some(b)) = (self.min_grade, Bufferbloat::from_result(result)) {
+            if b.grade > min {
+                failures.push(Failure {
+                    threshold: "bufferbloat",
+                    text: format!(
+                        "Bufferbloat {} (+{:.0} ms under load) worse than {}",
+                        b.grade, b.increase_ms, min
+                    ),
+                });
+            }
+        }
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result() -> SpeedResult {
+        SpeedResult {
+            jitter_ms: Some(12.5),
+            packet_loss: Some(0.0),
+            ..SpeedResult::new("ookla", 250.0, 25.0, 45.12)
+        }
+    }
+
+    #[test]
+    fn test_failed_thresholds_listed() {
+        let t = Thresholds {
+            min_down: Some(300.0),
+            min_up: Some(20.0),
+            max_ping: Some(30.0),
+            max_jitter: Some(10.0),
+            max_loss: Some(1.0),
+            baseline_drop: None,
+            min_grade: None,
+        };
+        let failures = t.check(&result(), None);
+        let names: Vec<_> = failures.iter().map(|f| f.threshold).collect();
+        assert_eq!(names, vec!["download", "ping", "jitter"]);
+        assert_eq!(failures[0].text, "Download 250.00 Mbps < 300 Mbps");
+        assert_eq!(failures[1].text, "Ping 45.1 ms > 30 ms");
+        assert_eq!(failures[2].text, "Jitter 12.5 ms > 10 ms");
+
+        let mut lossy = result();
+        lossy.packet_loss = Some(2.5);
+        let failures = Thresholds {
+            max_loss: Some(1.0),
+            ..Default::default()
+        }
+        .check(&lossy, None);
+        assert_eq!(failures[0].text, "Packet loss 2.5% > 1%");
+    }
+
+    #[test]
+    fn test_unset_and_unmeasured_pass() {
+        assert!(Thresholds::default().check(&result(), None).is_empty());
+        // python speedtest-cli measures neither jitter nor loss
+        let t = Thresholds {
+            max_jitter: Some(1.0),
+            max_loss: Some(0.0),
+            ..Default::default()
+        };
+        assert!(t
+            .check(&SpeedResult::new("python", 100.0, 10.0, 9.0), None)
+            .is_empty());
+        // iperf3 UDP runs measure no ping
+        let t = Thresholds {
+            max_ping: Some(1.0),
+            ..Default::default()
+        };
+        let udp = SpeedResult {
+            ping_ms: None,
+            ..SpeedResult::new("iperf3", 100.0, 10.0, 0.0)
+        };
+        assert!(t.check(&udp, None).is_empty());
+    }
+
+    fn down(mbps: f64) -> SpeedResult {
+        SpeedResult {
+            download_mbps: mbps,
+            ..result()
+        }
+    }
+
+    #[test]
+    fn test_baseline_drop() {
+        let baseline = Baseline {
+            days: 7,
+            samples: 7,
+            download_mbps: 342.0,
+            upload_mbps: 25.0,
+            ping_ms: Some(10.0),
+        };
+        let t = Thresholds {
+            min_down: Some(100.0),
+            baseline_drop: Some(25.0),
+            ..Default::default()
+        };
+        // Above the absolute minimum but 38% under the baseline
+        let failures = t.check(&down(212.0), Some(&baseline));
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].threshold, "download_baseline");
+        assert_eq!(
+            failures[0].text,
+            "Down 212 Mbps (\u{2212}38% vs 7-day median 342)"
+        );
+        // The absolute minimum still applies
+        let names: Vec<_> = t
+            .check(&down(90.0), Some(&baseline))
+            .iter()
+            .map(|f| f.threshold)
+            .collect();
+        assert_eq!(names, vec!["download", "download_baseline"]);
+        // Within the allowed drop, or no baseline yet
+        assert!(t.check(&down(300.0), Some(&baseline)).is_empty());
+        assert!(t.check(&down(212.0), None).is_empty());
+    }
+
+    #[test]
+    fn test_per_wan_overrides() {
+        let shared = Thresholds {
+            min_down: Some(500.0),
+            min_up: Some(50.0),
+            ..Default::default()
+        };
+        let lte = shared.with_overrides(|name| match name {
+            "SPEEDTEST_MIN_DOWN" => Some("40".to_string()),
+            "SPEEDTEST_MAX_PING" => Some("60".to_string()),
+            _ => None,
+        });
+        assert_eq!(lte.min_down, Some(40.0));
+        assert_eq!(lte.min_up, Some(50.0));
+        assert_eq!(lte.max_ping, Some(60.0));
+        assert_eq!(shared.with_overrides(|_| None), shared);
+    }
+
+    #[test]
+    fn test_bufferbloat_grade() {
+        let t = Thresholds {
+            min_grade: Some(Grade::B),
+            ..Default::default()
+        };
+        let bloated = SpeedResult {
+            download_latency_ms: Some(130.0),
+            upload_latency_ms: Some(60.0),
+            ..result()
+        };
+        let failures = t.check(&bloated, None);
+        assert_eq!(failures[0].threshold, "bufferbloat");
+        assert_eq!(
+            failures[0].text,
+            "Bufferbloat C (+85 ms under load) worse than B"
+        );
+        let sqm = SpeedResult {
+            download_latency_ms: Some(60.0),
+            ..result()
+        };
+        assert!(t.check(&sqm, None).is_empty());
+        // Not measured
+        assert!(t.check(&result(), None).is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! Severe weather alerts: formatting and already-sent tracking
+
+use std::collections::BTreeMap;
+
+use chrono::{FixedOffset, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::model::WeatherAlert;
+
+/// State file holding alerts that were already pushed
+const SENT_ALERTS_FILE: &str = "weatherust-alerts.json";
+
+/// Alerts already sent, keyed by location key (`geocache::cache_key`) +
+/// event + start time.
+///
+/// The value is the alert's end time so expired entries can be pruned.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SentAlerts {
+    #[serde(default)]
+    sent: BTreeMap<String, i64>,
+}
+
+impl SentAlerts {
+    pub fn load() -> Self {
+        common::state::load_json(crate::STATE_APP, SENT_ALERTS_FILE)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        common::state::save_json(crate::STATE_APP, SENT_ALERTS_FILE, self)
+    }
+
+    fn key(location_key: &str, alert: &WeatherAlert) -> String {
+        format!("{}|{}@{}", location_key, alert.event, alert.start)
+    }
+
+    pub fn is_new(&self, location_key: &str, alert: &WeatherAlert) -> bool {
+        !self.sent.contains_key(&Self::key(location_key, alert))
+    }
+
+    pub fn mark_sent(&mut self, location_key: &str, alert: &WeatherAlert) {
+        self.sent.insert(Self::key(location_key, alert), alert.end);
+    }
+
+    /// Forget alerts that ended before `now` so the file does not grow forever
+    pub fn prune(&mut self, now: i64) {
+        self.sent.retain(|_, end| *end >= now);
+    }
+}
+
+/// Build the notification title and body for a single alert
+pub fn format_alert(
+    alert: &WeatherAlert,
+    offset: &FixedOffset,
+    location: &str,
+) -> (String, String) {
+    let title = format!("⚠️ {} - {}", alert.event, location);
+
+    let mut lines = Vec::new();
+    lines.push(alert.event.clone());
+    if !alert.sender.is_empty() {
+        lines.push(format!("From: {}", alert.sender));
+    }
+    lines.push(format!("Start: {}", format_local(alert.start, offset)));
+    lines.push(format!("End: {}", format_local(alert.end, offset)));
+    if !alert.description.trim().is_empty() {
+        lines.push(String::new());
+        lines.push(alert.description.trim().to_string());
+    }
+
+    (title, lines.join("\n"))
+}
+
+/// One-line form used in the daily summary
+pub fn format_alert_line(alert: &WeatherAlert, offset: &FixedOffset) -> String {
+    format!(
+        "  {} (until {})",
+        alert.event,
+        format_local(alert.end, offset)
+    )
+}
+
+fn format_local(ts: i64, offset: &FixedOffset) -> String {
+    offset
+        .timestamp_opt(ts, 0)
+        .single()
+        .map(|t| t.format("%a %b %-d %H:%M").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOME: &str = "Davenport, IA, US";
+
+    fn alert(event: &str, start: i64, end: i64) -> WeatherAlert {
+        WeatherAlert {
+            sender: "NWS Quad Cities".to_string(),
+            event: event.to_string(),
+            start,
+            end,
+            description: "...TORNADO WARNING IN EFFECT...".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sent_alerts_dedupe_and_prune() {
+        let mut sent = SentAlerts::default();
+        let tornado = alert("Tornado Warning", 1000, 2000);
+        let flood = alert("Flood Watch", 1000, 5000);
+
+        assert!(sent.is_new(HOME, &tornado));
+        sent.mark_sent(HOME, &tornado);
+        assert!(!sent.is_new(HOME, &tornado));
+        assert!(sent.is_new(HOME, &flood));
+
+        // Same event re-issued with a new start time is a new alert
+        assert!(sent.is_new(HOME, &alert("Tornado Warning", 1500, 2500)));
+
+        // The same alert at another location is tracked separately
+        assert!(sent.is_new("Moline, IL, US", &tornado));
+
+        sent.mark_sent(HOME, &flood);
+        sent.prune(3000);
+        assert!(sent.is_new(HOME, &tornado));
+        assert!(!sent.is_new(HOME, &flood));
+    }
+
+    #[test]
+    fn test_format_alert_local_time() {
+        // UTC-5 (CDT)
+        let offset = FixedOffset::east_opt(-5 * 3600).unwrap();
+        // 2024-06-20 21:00:00 UTC -> 16:00 local
+        let a = alert("Tornado Warning", 1718917200, 1718920800);
+        let (title, body) = format_alert(&a, &offset, "Davenport, IA, US");
+
+        assert_eq!(title, "⚠️ Tornado Warning - Davenport, IA, US");
+        assert!(body.contains("From: NWS Quad Cities"));
+        assert!(body.contains("Start: Thu Jun 20 16:00"));
+        assert!(body.contains("End: Thu Jun 20 17:00"));
+        assert!(body.ends_with("...TORNADO WARNING IN EFFECT..."));
+    }
+}
@@ -0,0 +1,407 @@
+//! Forecast change detection between runs
+//!
+//! Each location query keeps a compact snapshot of the daily forecast as last
+//! notified. Every run compares against it and reports only meaningful
+//! changes to future days: a high/low shift beyond the threshold, a
+//! precipitation category flip (dry/rain/snow/storms), or a newly issued
+//! alert. A day's snapshot only moves on when a change to that day is
+//! notified, so a slow drift over several runs is still reported once it
+//! adds up, even when other days were notified in between.
+
+use std::collections::BTreeMap;
+
+use chrono::TimeZone;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Condition, DailyForecast, Forecast};
+
+/// State file holding the last forecast snapshot, per location
+const SNAPSHOT_STATE_FILE: &str = "weatherust-forecast.json";
+
+/// Daily probability of precipitation at or above which a day counts as wet
+const WET_DAILY_POP: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrecipCategory {
+    Dry,
+    Rain,
+    Snow,
+    Storms,
+}
+
+impl PrecipCategory {
+    /// From the condition code, so translated descriptions classify the same
+    pub fn of(day: &DailyForecast) -> Self {
+        match day.condition {
+            Condition::Thunderstorm => PrecipCategory::Storms,
+            Condition::Snow => PrecipCategory::Snow,
+            Condition::Rain | Condition::Drizzle => PrecipCategory::Rain,
+            _ if day.pop >= WET_DAILY_POP => PrecipCategory::Rain,
+            _ => PrecipCategory::Dry,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaySnapshot {
+    /// Local date, "YYYY-MM-DD"
+    pub date: String,
+    /// Local weekday, e.g. "Saturday"
+    pub weekday: String,
+    pub high: f64,
+    pub low: f64,
+    pub description: String,
+    pub category: PrecipCategory,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub units: String,
+    /// daily[0] is today
+    pub days: Vec<DaySnapshot>,
+    /// Active alerts as "event@start"
+    pub alerts: Vec<String>,
+}
+
+impl Snapshot {
+    pub fn from_forecast(forecast: &Forecast, units: &str) -> Self {
+        let offset = forecast.offset();
+        let days = forecast
+            .daily
+            .iter()
+            .filter_map(|d| {
+                let local = offset.timestamp_opt(d.dt, 0).single()?;
+                Some(DaySnapshot {
+                    date: local.format("%Y-%m-%d").to_string(),
+                    weekday: local.format("%A").to_string(),
+                    high: d.temp_max,
+                    low: d.temp_min,
+                    description: d.description.clone(),
+                    category: PrecipCategory::of(d),
+                })
+            })
+            .collect();
+        let alerts = forecast
+            .alerts
+            .iter()
+            .map(|a| format!("{}@{}", a.event, a.start))
+            .collect();
+
+        Snapshot {
+            units: units.to_string(),
+            days,
+            alerts,
+        }
+    }
+
+    /// Human-readable changes since `previous`, one line each
+    ///
+    /// Only days after today are compared; `threshold` is in report degrees.
+    pub fn changes_since(&self, previous: &Snapshot, threshold: f64) -> Vec<String> {
+        // Different unit systems cannot be compared meaningfully
+        if self.units != previous.units {
+            return Vec::new();
+        }
+
+        let mut lines = Vec::new();
+        let today = self.days.first().map(|d| d.date.as_str()).unwrap_or("");
+
+        for day in self.days.iter().filter(|d| d.date.as_str() > today) {
+            let Some(old) = previous.days.iter().find(|d| d.date == day.date) else {
+                continue;
+            };
+            let parts = day_changes(old, day, threshold);
+            if !parts.is_empty() {
+                lines.push(format!("{} changed: {}", day.weekday, parts.join(", ")));
+            }
+        }
+
+        for alert in self.alerts.iter().filter(|a| !previous.alerts.contains(a)) {
+            let event = alert.rsplit_once('@').map(|(e, _)| e).unwrap_or(alert);
+            lines.push(format!("New alert: {}", event));
+        }
+
+        lines
+    }
+
+    /// Snapshot to keep after this run: current values for days that
+    /// `changes_since` reported and for days new to the forecast, the stored
+    /// values for every other day so their drift keeps adding up
+    pub fn carry_forward(&self, previous: &Snapshot, threshold: f64) -> Snapshot {
+        if self.units != previous.units {
+            return self.clone();
+        }
+        let today = self.days.first().map(|d| d.date.as_str()).unwrap_or("");
+        let days = self
+            .days
+            .iter()
+            .map(
+                |day| match previous.days.iter().find(|d| d.date == day.date) {
+                    Some(old)
+                        if day.date.as_str() <= today
+                            || day_changes(old, day, threshold).is_empty() =>
+                    {
+                        old.clone()
+                    }
+                    _ => day.clone(),
+                },
+            )
+            .collect();
+        Snapshot {
+            units: self.units.clone(),
+            days,
+            alerts: self.alerts.clone(),
+        }
+    }
+}
+
+/// What moved between the stored and current forecast for one day
+fn day_changes(old: &DaySnapshot, day: &DaySnapshot, threshold: f64) -> Vec<String> {
+    let mut parts = Vec::new();
+    if day.category != old.category {
+        parts.push(format!("{} → {}", old.description, day.description));
+    }
+    if (day.high - old.high).abs() > threshold {
+        parts.push(format!("high {:.0}→{:.0}", old.high, day.high));
+    }
+    if (day.low - old.low).abs() > threshold {
+        parts.push(format!("low {:.0}→{:.0}", old.low, day.low));
+    }
+    parts
+}
+
+/// Snapshot stored for a location key (`geocache::cache_key`)
+pub fn load_previous(location_key: &str) -> Option<Snapshot> {
+    let mut all: BTreeMap<String, Snapshot> =
+        common::state::load_json(crate::STATE_APP, SNAPSHOT_STATE_FILE);
+    all.remove(location_key)
+}
+
+pub fn save(location_key: &str, snapshot: &Snapshot) -> anyhow::Result<()> {
+    let mut all: BTreeMap<String, Snapshot> =
+        common::state::load_json(crate::STATE_APP, SNAPSHOT_STATE_FILE);
+    all.insert(location_key.to_string(), snapshot.clone());
+    common::state::save_json(crate::STATE_APP, SNAPSHOT_STATE_FILE, &all)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CurrentConditions, WeatherAlert};
+
+    // 2024-06-20 00:00 UTC (Thursday)
+    const DAY0: i64 = 1_718_841_600;
+
+    fn day(i: i64, high: f64, low: f64, desc: &str, condition: Condition) -> DailyForecast {
+        DailyForecast {
+            dt: DAY0 + i * 86_400 + 12 * 3600,
+            temp_min: low,
+            temp_max: high,
+            pop: 0.1,
+            rain: 0.0,
+            snow: 0.0,
+            wind_speed: 5.0,
+            wind_gust: None,
+            uvi: None,
+            description: desc.to_string(),
+            condition,
+            ..Default::default()
+        }
+    }
+
+    fn forecast(daily: Vec<DailyForecast>, alerts: Vec<WeatherAlert>) -> Forecast {
+        Forecast {
+            timezone: "UTC".to_string(),
+            timezone_offset: 0,
+            current: CurrentConditions {
+                dt: DAY0,
+                temp: 70.0,
+                humidity: 50,
+                description: "clear sky".to_string(),
+                ..Default::default()
+            },
+            minutely: Vec::new(),
+            hourly: Vec::new(),
+            daily,
+            alerts,
+            air_quality: None,
+            solar: None,
+        }
+    }
+
+    fn week(saturday: DailyForecast) -> Vec<DailyForecast> {
+        vec![
+            day(0, 80.0, 60.0, "clear sky", Condition::Clear),
+            day(1, 82.0, 62.0, "few clouds", Condition::PartlyCloudy),
+            saturday,
+        ]
+    }
+
+    #[test]
+    fn test_category() {
+        assert_eq!(
+            PrecipCategory::of(&day(0, 0.0, 0.0, "clear sky", Condition::Clear)),
+            PrecipCategory::Dry
+        );
+        assert_eq!(
+            PrecipCategory::of(&day(
+                0,
+                0.0,
+                0.0,
+                "thunderstorm with rain",
+                Condition::Thunderstorm
+            )),
+            PrecipCategory::Storms
+        );
+        assert_eq!(
+            PrecipCategory::of(&day(0, 0.0, 0.0, "light snow", Condition::Snow)),
+            PrecipCategory::Snow
+        );
+        // The description may be translated (--lang); only the condition counts
+        assert_eq!(
+            PrecipCategory::of(&day(0, 0.0, 0.0, "neige légère", Condition::Snow)),
+            PrecipCategory::Snow
+        );
+        assert_eq!(
+            PrecipCategory::of(&day(0, 0.0, 0.0, "Gewitter", Condition::Thunderstorm)),
+            PrecipCategory::Storms
+        );
+        let mut cloudy = day(0, 0.0, 0.0, "overcast clouds", Condition::Cloudy);
+        cloudy.pop = 0.7;
+        assert_eq!(PrecipCategory::of(&cloudy), PrecipCategory::Rain);
+    }
+
+    #[test]
+    fn test_significant_change_reported() {
+        let before = Snapshot::from_forecast(
+            &forecast(
+                week(day(2, 84.0, 65.0, "sunny", Condition::Clear)),
+                Vec::new(),
+            ),
+            "imperial",
+        );
+        let after = Snapshot::from_forecast(
+            &forecast(
+                week(day(2, 76.0, 63.0, "thunderstorms", Condition::Thunderstorm)),
+                Vec::new(),
+            ),
+            "imperial",
+        );
+
+        assert_eq!(
+            after.changes_since(&before, 5.0),
+            vec!["Saturday changed: sunny → thunderstorms, high 84→76".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_small_changes_and_today_ignored() {
+        let before = Snapshot::from_forecast(
+            &forecast(
+                week(day(2, 84.0, 65.0, "sunny", Condition::Clear)),
+                Vec::new(),
+            ),
+            "imperial",
+        );
+        // Same category, temperatures within threshold, and today changed a lot
+        let mut daily = week(day(2, 86.0, 62.0, "few clouds", Condition::PartlyCloudy));
+        daily[0] = day(0, 60.0, 40.0, "heavy rain", Condition::Rain);
+        let after = Snapshot::from_forecast(&forecast(daily, Vec::new()), "imperial");
+
+        assert!(after.changes_since(&before, 5.0).is_empty());
+        // Switching units is not a change
+        let metric = Snapshot::from_forecast(
+            &forecast(
+                week(day(2, 20.0, 10.0, "rain", Condition::Rain)),
+                Vec::new(),
+            ),
+            "metric",
+        );
+        assert!(metric.changes_since(&before, 5.0).is_empty());
+    }
+
+    #[test]
+    fn test_new_alert_reported() {
+        let alert = WeatherAlert {
+            sender: "NWS".to_string(),
+            event: "Heat Advisory".to_string(),
+            start: DAY0,
+            end: DAY0 + 3600,
+            description: String::new(),
+        };
+        let before = Snapshot::from_forecast(
+            &forecast(
+                week(day(2, 84.0, 65.0, "sunny", Condition::Clear)),
+                Vec::new(),
+            ),
+            "imperial",
+        );
+        let after = Snapshot::from_forecast(
+            &forecast(
+                week(day(2, 84.0, 65.0, "sunny", Condition::Clear)),
+                vec![alert],
+            ),
+            "imperial",
+        );
+        assert_eq!(
+            after.changes_since(&before, 5.0),
+            vec!["New alert: Heat Advisory".to_string()]
+        );
+        assert!(after.changes_since(&after, 5.0).is_empty());
+    }
+
+    #[test]
+    fn test_slow_drift_accumulates() {
+        let run = |high: f64| {
+            Snapshot::from_forecast(
+                &forecast(
+                    week(day(2, high, 65.0, "sunny", Condition::Clear)),
+                    Vec::new(),
+                ),
+                "imperial",
+            )
+        };
+        let mut stored = run(84.0);
+        // Three runs of -2°: no single step crosses 5°, the total does
+        let mut reported = Vec::new();
+        for high in [82.0, 80.0, 78.0] {
+            let current = run(high);
+            reported.extend(current.changes_since(&stored, 5.0));
+            stored = current.carry_forward(&stored, 5.0);
+        }
+        assert_eq!(reported, vec!["Saturday changed: high 84→78".to_string()]);
+
+        // Days that roll into the forecast are picked up
+        let mut daily = week(day(2, 84.0, 65.0, "sunny", Condition::Clear));
+        daily.push(day(3, 70.0, 50.0, "rain", Condition::Rain));
+        let kept = Snapshot::from_forecast(&forecast(daily, Vec::new()), "imperial")
+            .carry_forward(&run(84.0), 5.0);
+        assert_eq!(kept.days.len(), 4);
+        assert_eq!(kept.days[3].high, 70.0);
+    }
+
+    #[test]
+    fn test_unreported_days_keep_drifting_after_a_notification() {
+        let run = |saturday: f64, sunday: f64| {
+            let mut daily = week(day(2, saturday, 65.0, "sunny", Condition::Clear));
+            daily.push(day(3, sunday, 60.0, "sunny", Condition::Clear));
+            Snapshot::from_forecast(&forecast(daily, Vec::new()), "imperial")
+        };
+        let mut stored = run(84.0, 80.0);
+        let mut reported = Vec::new();
+        // Saturday jumps and is notified while Sunday drifts 3° per run
+        for (saturday, sunday) in [(76.0, 77.0), (76.0, 74.0)] {
+            let current = run(saturday, sunday);
+            reported.extend(current.changes_since(&stored, 5.0));
+            stored = current.carry_forward(&stored, 5.0);
+        }
+        assert_eq!(
+            reported,
+            vec![
+                "Saturday changed: high 84→76".to_string(),
+                "Sunday changed: high 80→74".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! On-disk geocoding cache
+//!
+//! Locations practically never move, so resolved coordinates and display
+//! names are kept in the state directory and reused until they expire
+//! (`WEATHER_GEOCODE_TTL_DAYS`, default 30). Entries are keyed on the
+//! normalized query so "davenport, ia" and "Davenport,IA,US" share one entry.
+
+use std::collections::BTreeMap;
+use std::env;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::locations::LocationQuery;
+use crate::model::Location;
+use crate::{normalize_city_query, split_zip_and_cc};
+
+/// State file holding resolved locations
+const GEOCODE_CACHE_FILE: &str = "weatherust-geocode.json";
+
+/// Default lifetime of a cache entry
+const DEFAULT_TTL_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedLocation {
+    location: Location,
+    /// Unix seconds when the entry was resolved
+    fetched_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GeoCache {
+    #[serde(default)]
+    entries: BTreeMap<String, CachedLocation>,
+}
+
+/// Cache lifetime in seconds from `WEATHER_GEOCODE_TTL_DAYS`
+pub fn ttl_secs() -> i64 {
+    let days = match env::var("WEATHER_GEOCODE_TTL_DAYS") {
+        Ok(v) if !v.trim().is_empty() => v.trim().parse().unwrap_or_else(|_| {
+            warn!(value = %v, "Invalid WEATHER_GEOCODE_TTL_DAYS; using default");
+            DEFAULT_TTL_DAYS
+        }),
+        _ => DEFAULT_TTL_DAYS,
+    };
+    days * 86_400
+}
+
+/// Normalized cache key for a query
+pub fn cache_key(query: &LocationQuery) -> String {
+    match query {
+        LocationQuery::Zip(zip) => {
+            let (zip, cc) = split_zip_and_cc(zip);
+            format!("zip:{},{}", zip, cc.to_uppercase())
+        }
+        LocationQuery::Place(place) => {
+            format!("place:{}", normalize_city_query(place).to_lowercase())
+        }
+        // ~11 m precision; anything finer is the same place for weather purposes
+        LocationQuery::Coords { lat, lon } => format!("coords:{:.4},{:.4}", lat, lon),
+    }
+}
+
+impl GeoCache {
+    pub fn load() -> Self {
+        common::state::load_json(crate::STATE_APP, GEOCODE_CACHE_FILE)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        common::state::save_json(crate::STATE_APP, GEOCODE_CACHE_FILE, self)
+    }
+
+    /// Cached location if present and younger than `ttl` seconds
+    pub fn get(&self, query: &LocationQuery, now: i64, ttl: i64) -> Option<Location> {
+        self.entries
+            .get(&cache_key(query))
+            .filter(|e| now - e.fetched_at < ttl)
+            .map(|e| e.location.clone())
+    }
+
+    pub fn insert(&mut self, query: &LocationQuery, location: &Location, now: i64) {
+        self.entries.insert(
+            cache_key(query),
+            CachedLocation {
+                location: location.clone(),
+                fetched_at: now,
+            },
+        );
+    }
+
+    /// Drop entries older than `ttl` so the file does not grow forever
+    pub fn prune(&mut self, now: i64, ttl: i64) {
+        self.entries.retain(|_, e| now - e.fetched_at < ttl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn davenport() -> Location {
+        Location {
+            lat: 41.5236,
+            lon: -90.5776,
+            name: "Davenport, IA, US".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cache_key_normalizes_query() {
+        let a = LocationQuery::Place("Davenport,IA".to_string());
+        let b = LocationQuery::Place("davenport, ia, us".to_string());
+        assert_eq!(cache_key(&a), cache_key(&b));
+
+        let z1 = LocationQuery::Zip("52726".to_string());
+        let z2 = LocationQuery::Zip("52726,us".to_string());
+        assert_eq!(cache_key(&z1), "zip:52726,US");
+        assert_eq!(cache_key(&z1), cache_key(&z2));
+
+        // A ZIP and a place with the same text are different entries
+        assert_ne!(
+            cache_key(&z1),
+            cache_key(&LocationQuery::Place("52726".to_string()))
+        );
+
+        let c = LocationQuery::Coords {
+            lat: 41.523_61,
+            lon: -90.577_64,
+        };
+        assert_eq!(cache_key(&c), "coords:41.5236,-90.5776");
+    }
+
+    #[test]
+    fn test_cache_hit_expiry_and_prune() {
+        let q = LocationQuery::Zip("52726".to_string());
+        let mut cache = GeoCache::default();
+        assert!(cache.get(&q, 1000, 500).is_none());
+
+        cache.insert(&q, &davenport(), 1000);
+        let hit = cache.get(&q, 1400, 500).unwrap();
+        assert_eq!(hit.name, "Davenport, IA, US");
+        assert!(cache.get(&q, 1500, 500).is_none());
+
+        cache.prune(1500, 500);
+        assert!(cache.entries.is_empty());
+    }
+}
@@ -0,0 +1,535 @@
+//! Local observation history (`weatherust history`)
+//!
+//! Every forecast report adds one line to `weatherust-history.jsonl` in the
+//! state directory: the current conditions plus today's reported high/low
+//! and precipitation. Temperatures are stored in °C so runs with different
+//! `--units` can be mixed; statistics are converted on output. Observations
+//! older than `WEATHER_HISTORY_DAYS` are dropped as new ones are recorded.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, FixedOffset, NaiveDate, TimeZone};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::model::{from_celsius, temp_unit, to_celsius, Forecast};
+use crate::report::precip_amount;
+
+/// History file (JSON lines)
+const HISTORY_FILE: &str = "weatherust-history.jsonl";
+
+/// A year plus a month, so a monthly view still covers the same month last year
+const DEFAULT_KEEP_DAYS: i64 = 400;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    /// Location key (`geocache::cache_key`)
+    pub key: String,
+    /// Display name at the time of the observation
+    pub location: String,
+    pub dt: i64, // unix seconds
+    pub timezone_offset: i32,
+    /// °C
+    pub temp: f64,
+    pub humidity: u8,
+    /// Today's high/low as reported by the provider, °C
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    /// Today's rain + snow as reported by the provider, mm
+    pub precip_mm: Option<f64>,
+    pub description: String,
+}
+
+impl Observation {
+    pub fn from_forecast(key: &str, location: &str, data: &Forecast, units: &str) -> Self {
+        let today = data.daily.first();
+        Observation {
+            key: key.to_string(),
+            location: location.to_string(),
+            dt: data.current.dt,
+            timezone_offset: data.timezone_offset,
+            temp: to_celsius(data.current.temp, units),
+            humidity: data.current.humidity,
+            high: today.map(|d| to_celsius(d.temp_max, units)),
+            low: today.map(|d| to_celsius(d.temp_min, units)),
+            precip_mm: today.map(|d| d.rain + d.snow),
+            description: data.current.description.clone(),
+        }
+    }
+
+    fn local_date(&self) -> Option<NaiveDate> {
+        let offset = FixedOffset::east_opt(self.timezone_offset)?;
+        Some(offset.timestamp_opt(self.dt, 0).single()?.date_naive())
+    }
+}
+
+/// Days of observations to keep from `WEATHER_HISTORY_DAYS`
+pub fn keep_days() -> i64 {
+    match env::var("WEATHER_HISTORY_DAYS") {
+        Ok(v) if !v.trim().is_empty() => v.trim().parse().unwrap_or_else(|_| {
+            warn!(value = %v, "Invalid WEATHER_HISTORY_DAYS; using default");
+            DEFAULT_KEEP_DAYS
+        }),
+        _ => DEFAULT_KEEP_DAYS,
+    }
+}
+
+/// Add one observation to the history file, dropping those more than
+/// `keep_days` days older than it
+pub fn record(obs: &Observation, keep_days: i64) -> Result<()> {
+    let path = common::state::state_path(crate::STATE_APP, HISTORY_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create state directory {}", parent.display()))?;
+    }
+    let mut kept = prune(load(), obs.dt, keep_days);
+    kept.push(obs.clone());
+    let mut text = String::new();
+    for o in &kept {
+        text.push_str(&serde_json::to_string(o)?);
+        text.push('\n');
+    }
+    // Replace atomically so a crash mid-write keeps the old history
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, text).with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// Observations from the last `days` days before `now` (unix seconds)
+fn prune(observations: Vec<Observation>, now: i64, days: i64) -> Vec<Observation> {
+    let since = now - days * 86_400;
+    observations.into_iter().filter(|o| o.dt > since).collect()
+}
+
+/// Every stored observation; unreadable lines are skipped
+pub fn load() -> Vec<Observation> {
+    let path = common::state::state_path(crate::STATE_APP, HISTORY_FILE);
+    let Ok(text) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    parse_lines(&text)
+}
+
+fn parse_lines(text: &str) -> Vec<Observation> {
+    let mut skipped = 0;
+    let list = text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| {
+            let parsed = serde_json::from_str(l).ok();
+            if parsed.is_none() {
+                skipped += 1;
+            }
+            parsed
+        })
+        .collect();
+    if skipped > 0 {
+        warn!(skipped, "Skipped unreadable history lines");
+    }
+    list
+}
+
+/// Grouping for `weatherust history --period`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    /// "2024-06-20", "2024-W25", "2024-06"
+    fn key(self, date: NaiveDate) -> String {
+        match self {
+            Period::Day => date.format("%Y-%m-%d").to_string(),
+            Period::Week => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Period::Month => date.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// One local day at one location
+#[derive(Debug, Clone, PartialEq)]
+struct DayStats {
+    date: NaiveDate,
+    min: f64,
+    max: f64,
+    /// Observed temperatures only, without the provider's daily high/low
+    observed_min: f64,
+    observed_max: f64,
+    sum: f64,
+    count: usize,
+    precip_mm: Option<f64>,
+}
+
+/// Statistics for one period, °C and mm
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodStats {
+    pub label: String,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub precip_mm: Option<f64>,
+    pub observations: usize,
+}
+
+/// Highest / lowest temperature observed, with the day
+#[derive(Debug, Clone, PartialEq)]
+pub struct Records {
+    pub high: (f64, NaiveDate),
+    pub low: (f64, NaiveDate),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationHistory {
+    pub location: String,
+    /// Oldest first
+    pub periods: Vec<PeriodStats>,
+    pub records: Records,
+    /// Sum over the shown periods, when any reported precipitation
+    pub precip_total_mm: Option<f64>,
+}
+
+/// Per-day statistics for each location, keyed by location key, with the
+/// most recent display name
+///
+/// Highs and lows combine the observed temperatures with the provider's
+/// daily high/low for that day, so a job that runs once in the morning
+/// still sees the afternoon high. Averages and records use
+/// observations only. Repeated observations (same location and time) count once.
+fn daily_stats(observations: &[Observation]) -> BTreeMap<String, (String, Vec<DayStats>)> {
+    let mut seen = std::collections::HashSet::new();
+    let mut names: BTreeMap<String, (String, i64)> = BTreeMap::new();
+    let mut days: BTreeMap<String, BTreeMap<NaiveDate, (DayStats, i64)>> = BTreeMap::new();
+
+    for obs in observations {
+        let key = obs.key.clone();
+        if !seen.insert((key.clone(), obs.dt)) {
+            continue;
+        }
+        let Some(date) = obs.local_date() else {
+            continue;
+        };
+        let name = names
+            .entry(key.clone())
+            .or_insert_with(|| (obs.location.clone(), obs.dt));
+        if obs.dt >= name.1 {
+            *name = (obs.location.clone(), obs.dt);
+        }
+        let entry = days
+            .entry(key)
+            .or_default()
+            .entry(date)
+            .or_insert_with(|| {
+                (
+                    DayStats {
+                        date,
+                        min: f64::INFINITY,
+                        max: f64::NEG_INFINITY,
+                        observed_min: f64::INFINITY,
+                        observed_max: f64::NEG_INFINITY,
+                        sum: 0.0,
+                        count: 0,
+                        precip_mm: None,
+                    },
+                    i64::MIN,
+                )
+            });
+        let (day, latest) = entry;
+        day.min = day.min.min(obs.temp).min(obs.low.unwrap_or(f64::INFINITY));
+        day.max = day
+            .max
+            .max(obs.temp)
+            .max(obs.high.unwrap_or(f64::NEG_INFINITY));
+        day.observed_min = day.observed_min.min(obs.temp);
+        day.observed_max = day.observed_max.max(obs.temp);
+        day.sum += obs.temp;
+        day.count += 1;
+        // The latest report of the day has the most complete total
+        if obs.dt > *latest {
+            *latest = obs.dt;
+            if obs.precip_mm.is_some() {
+                day.precip_mm = obs.precip_mm;
+            }
+        }
+    }
+
+    days.into_iter()
+        .map(|(key, by_date)| {
+            let name = names.remove(&key).map(|(n, _)| n).unwrap_or_default();
+            (key, (name, by_date.into_values().map(|(d, _)| d).collect()))
+        })
+        .collect()
+}
+
+/// Summaries of the last `last` periods per location, optionally filtered
+/// by a case-insensitive substring of the location name
+pub fn summarize(
+    observations: &[Observation],
+    period: Period,
+    last: usize,
+    location_filter: Option<&str>,
+) -> Vec<LocationHistory> {
+    let filter = location_filter.map(|f| f.to_lowercase());
+    let mut summaries: Vec<LocationHistory> = daily_stats(observations)
+        .into_values()
+        .filter(|(name, _)| {
+            filter
+                .as_ref()
+                .is_none_or(|f| name.to_lowercase().contains(f.as_str()))
+        })
+        .filter_map(|(location, days)| {
+            let high =
+                days.iter()
+                    .map(|d| (d.observed_max, d.date))
+                    .reduce(|a, b| if b.0 > a.0 { b } else { a })?;
+            let low = days
+                .iter()
+                .map(|d| (d.observed_min, d.date))
+                .reduce(|a, b| if b.0 < a.0 { b } else { a })?;
+
+            let mut grouped: BTreeMap<String, Vec<&DayStats>> = BTreeMap::new();
+            for day in &days {
+                grouped.entry(period.key(day.date)).or_default().push(day);
+            }
+            let skip = grouped.len().saturating_sub(last);
+            let periods: Vec<PeriodStats> = grouped
+                .into_iter()
+                .skip(skip)
+                .map(|(label, days)| {
+                    let count: usize = days.iter().map(|d| d.count).sum();
+                    let reported: Vec<f64> = days.iter().filter_map(|d| d.precip_mm).collect();
+                    PeriodStats {
+                        label,
+                        min: days.iter().map(|d| d.min).fold(f64::INFINITY, f64::min),
+                        max: days.iter().map(|d| d.max).fold(f64::NEG_INFINITY, f64::max),
+                        avg: days.iter().map(|d| d.sum).sum::<f64>() / count as f64,
+                        precip_mm: (!reported.is_empty()).then(|| reported.iter().sum()),
+                        observations: count,
+                    }
+                })
+                .collect();
+            let reported: Vec<f64> = periods.iter().filter_map(|p| p.precip_mm).collect();
+
+            Some(LocationHistory {
+                location,
+                precip_total_mm: (!reported.is_empty()).then(|| reported.iter().sum()),
+                periods,
+                records: Records { high, low },
+            })
+        })
+        .collect();
+    summaries.sort_by(|a, b| a.location.cmp(&b.location));
+    summaries
+}
+
+/// Text table for one location in the requested units
+pub fn render(history: &LocationHistory, units: &str) -> String {
+    let deg = temp_unit(units);
+    let t = |c: f64| from_celsius(c, units);
+    let mut lines = vec![
+        format!("History for {} ({})", history.location, deg),
+        format!(
+            "{:<10} {:>7} {:>7} {:>7} {:>10} {:>5}",
+            "Period", "Low", "High", "Avg", "Precip", "Obs"
+        ),
+    ];
+    for p in &history.periods {
+        lines.push(format!(
+            "{:<10} {:>7.1} {:>7.1} {:>7.1} {:>10} {:>5}",
+            p.label,
+            t(p.min),
+            t(p.max),
+            t(p.avg),
+            p.precip_mm
+                .map(|mm| precip_amount(mm, units))
+                .unwrap_or_else(|| "-".to_string()),
+            p.observations
+        ));
+    }
+    let (high, high_date) = history.records.high;
+    let (low, low_date) = history.records.low;
+    lines.push(format!(
+        "Record high: {:.1}{} on {}",
+        t(high),
+        deg,
+        high_date
+    ));
+    lines.push(format!("Record low: {:.1}{} on {}", t(low), deg, low_date));
+    if let Some(total) = history.precip_total_mm {
+        lines.push(format!(
+            "Precipitation total: {}",
+            precip_amount(total, units)
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-06-17 12:00 UTC (Monday)
+    const MON_NOON: i64 = 1_718_625_600;
+
+    fn obs(location: &str, dt: i64, temp: f64, high: f64, low: f64, precip: f64) -> Observation {
+        Observation {
+            key: format!("place:{}", location.to_lowercase()),
+            location: location.to_string(),
+            dt,
+            timezone_offset: 0,
+            temp,
+            humidity: 50,
+            high: Some(high),
+            low: Some(low),
+            precip_mm: Some(precip),
+            description: "clear sky".to_string(),
+        }
+    }
+
+    fn sample() -> Vec<Observation> {
+        let day = 86_400;
+        vec![
+            obs(
+                "Davenport, IA, US",
+                MON_NOON - 4 * 3600,
+                18.0,
+                27.0,
+                15.0,
+                1.0,
+            ),
+            obs(
+                "Davenport, IA, US",
+                MON_NOON + 4 * 3600,
+                26.0,
+                28.0,
+                15.0,
+                2.5,
+            ),
+            // Duplicate of the previous observation
+            obs(
+                "Davenport, IA, US",
+                MON_NOON + 4 * 3600,
+                26.0,
+                28.0,
+                15.0,
+                2.5,
+            ),
+            obs("Davenport, IA, US", MON_NOON + day, 30.0, 33.0, 20.0, 0.0),
+            // Following Monday: a new ISO week
+            obs(
+                "Davenport, IA, US",
+                MON_NOON + 7 * day,
+                10.0,
+                12.0,
+                4.0,
+                5.0,
+            ),
+            obs("London, GB", MON_NOON, 16.0, 19.0, 11.0, 0.2),
+        ]
+    }
+
+    #[test]
+    fn test_daily_summary_dedupes_and_uses_reported_extremes() {
+        let h = summarize(&sample(), Period::Day, 7, Some("davenport"));
+        assert_eq!(h.len(), 1);
+        let p = &h[0].periods;
+        assert_eq!(p.len(), 3);
+        assert_eq!(p[0].label, "2024-06-17");
+        assert_eq!(p[0].observations, 2);
+        assert_eq!(p[0].min, 15.0);
+        assert_eq!(p[0].max, 28.0);
+        assert_eq!(p[0].avg, 22.0);
+        // Latest report of the day wins
+        assert_eq!(p[0].precip_mm, Some(2.5));
+        // Records come from observed temperatures, not reported highs/lows
+        assert_eq!(h[0].records.high, (30.0, p[1].label.parse().unwrap()));
+        assert_eq!(h[0].records.low.0, 10.0);
+        assert_eq!(h[0].precip_total_mm, Some(7.5));
+
+        // Only the most recent periods are shown
+        let h = summarize(&sample(), Period::Day, 1, Some("davenport"));
+        assert_eq!(h[0].periods.len(), 1);
+        assert_eq!(h[0].periods[0].label, "2024-06-24");
+    }
+
+    #[test]
+    fn test_grouped_by_key_across_name_changes() {
+        let keyed = |name: &str, dt: i64, temp: f64| Observation {
+            key: "zip:52801,US".to_string(),
+            ..obs(name, dt, temp, temp, temp, 0.0)
+        };
+        let observations = vec![
+            keyed("Davenport", MON_NOON, 20.0),
+            // Same query, new name after a provider fallback
+            keyed("Davenport, IA, US", MON_NOON + 86_400, 22.0),
+        ];
+        let h = summarize(&observations, Period::Day, 7, None);
+        assert_eq!(h.len(), 1);
+        assert_eq!(h[0].location, "Davenport, IA, US");
+        assert_eq!(h[0].periods.len(), 2);
+        assert_eq!(h[0].records.low.0, 20.0);
+    }
+
+    #[test]
+    fn test_weekly_and_monthly_grouping() {
+        let h = summarize(&sample(), Period::Week, 10, None);
+        assert_eq!(h.len(), 2);
+        let weeks: Vec<_> = h[0].periods.iter().map(|p| p.label.as_str()).collect();
+        assert_eq!(weeks, vec!["2024-W25", "2024-W26"]);
+        assert_eq!(h[0].periods[0].max, 33.0);
+        assert_eq!(h[0].periods[0].precip_mm, Some(2.5));
+        assert_eq!(h[0].periods[0].observations, 3);
+
+        let h = summarize(&sample(), Period::Month, 10, Some("LONDON"));
+        assert_eq!(h[0].location, "London, GB");
+        assert_eq!(h[0].periods[0].label, "2024-06");
+    }
+
+    #[test]
+    fn test_units_round_trip_and_render() {
+        let mut data_obs = obs("X", MON_NOON, 0.0, 0.0, 0.0, 0.0);
+        data_obs.temp = to_celsius(77.0, "imperial");
+        assert!((data_obs.temp - 25.0).abs() < 1e-9);
+        assert!((from_celsius(25.0, "standard") - 298.15).abs() < 1e-9);
+
+        let h = summarize(&sample(), Period::Week, 1, Some("davenport"));
+        let text = render(&h[0], "imperial");
+        assert!(text.starts_with("History for Davenport, IA, US (°F)"));
+        assert!(text.contains("2024-W26      39.2    53.6    50.0    0.20 in     1"));
+        assert!(text.contains("Record high: 86.0°F on 2024-06-18"));
+        assert!(text.contains("Record low: 50.0°F on 2024-06-24"));
+        assert!(text.contains("Precipitation total: 0.20 in"));
+    }
+
+    #[test]
+    fn test_prune_by_age() {
+        let day = 86_400;
+        let kept = prune(sample(), MON_NOON + 7 * day, 7);
+        let dts: Vec<i64> = kept.iter().map(|o| o.dt).collect();
+        // The morning run and London (both at or before Monday noon) are a week old
+        assert_eq!(
+            dts,
+            [
+                MON_NOON + 4 * 3600,
+                MON_NOON + 4 * 3600,
+                MON_NOON + day,
+                MON_NOON + 7 * day
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unreadable_lines_skipped() {
+        let good = serde_json::to_string(&sample()[0]).unwrap();
+        let text = format!("{good}\n{{not json\n\n{good}\n");
+        assert_eq!(parse_lines(&text).len(), 2);
+    }
+}
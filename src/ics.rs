@@ -0,0 +1,379 @@
+//! iCalendar export of the daily forecast (`--ics <path>`, `--ics -` for stdout)
+//!
+//! Each forecast day becomes an all-day VEVENT. UIDs are derived from the
+//! normalized location query (the geocoding cache key) and date, so a
+//! calendar subscribed to the file updates the existing events on every
+//! refresh instead of piling up duplicates, even when the display name
+//! changes after a provider fallback or geocode refresh.
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::geocache::cache_key;
+use crate::locations::LocationQuery;
+use crate::model::{temp_unit, Condition, DailyForecast, Forecast, Location};
+use crate::narrative::capitalize;
+use crate::report::{clock, precip_amount, wind_text};
+
+/// Days exported per location (today + 6)
+const DAYS: usize = 7;
+
+/// One day at one location
+struct DayEvent {
+    /// Stable part of the UID, from the location query
+    uid_key: String,
+    location: String,
+    /// Local date as YYYYMMDD
+    date: String,
+    /// Local date of the following day (DTEND is exclusive)
+    next_date: String,
+    summary: String,
+    description: String,
+}
+
+/// Forecast events for every location in the run
+#[derive(Default)]
+pub struct Calendar {
+    events: Vec<DayEvent>,
+}
+
+impl Calendar {
+    /// Add the next `DAYS` days of `data` for `location`, resolved from `query`
+    pub fn add(
+        &mut self,
+        query: &LocationQuery,
+        location: &Location,
+        data: &Forecast,
+        units: &str,
+    ) {
+        let uid_key = slug(&cache_key(query));
+        let offset = data.offset();
+        for day in data.daily.iter().take(DAYS) {
+            let Some(local) = offset.timestamp_opt(day.dt, 0).single() else {
+                continue;
+            };
+            let date = local.date_naive();
+            let Some(next) = date.succ_opt() else {
+                continue;
+            };
+            self.events.push(DayEvent {
+                uid_key: uid_key.clone(),
+                location: location.name.clone(),
+                date: date.format("%Y%m%d").to_string(),
+                next_date: next.format("%Y%m%d").to_string(),
+                summary: format!(
+                    "{} {:.0}/{:.0} {}",
+                    icon(day),
+                    day.temp_max,
+                    day.temp_min,
+                    day.description
+                ),
+                description: describe(day, &location.name, &offset, units),
+            });
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Serialize as an RFC 5545 calendar stamped with `now`
+    pub fn render(&self, now: DateTime<Utc>) -> String {
+        let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        // Name the place in the title only when several share the calendar
+        let several = self
+            .events
+            .iter()
+            .any(|e| e.location != self.events[0].location);
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//weatherust//forecast//EN".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+            "METHOD:PUBLISH".to_string(),
+            "X-WR-CALNAME:Weather forecast".to_string(),
+        ];
+        for e in &self.events {
+            let summary = if several {
+                format!("{}: {}", e.location, e.summary)
+            } else {
+                e.summary.clone()
+            };
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{}-{}@weatherust", e.date, e.uid_key),
+                format!("DTSTAMP:{stamp}"),
+                format!("LAST-MODIFIED:{stamp}"),
+                format!("DTSTART;VALUE=DATE:{}", e.date),
+                format!("DTEND;VALUE=DATE:{}", e.next_date),
+                format!("SUMMARY:{}", escape(&summary)),
+                format!("DESCRIPTION:{}", escape(&e.description)),
+                format!("LOCATION:{}", escape(&e.location)),
+                // Forecasts should not block time in free/busy
+                "TRANSP:TRANSPARENT".to_string(),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+        lines.push("END:VCALENDAR".to_string());
+
+        lines
+            .iter()
+            .map(|l| fold(l))
+            .collect::<Vec<_>>()
+            .join("\r\n")
+            + "\r\n"
+    }
+}
+
+/// Write the calendar to `path` ("-" for stdout)
+///
+/// Files are replaced atomically so a subscriber never reads half a calendar.
+pub fn write(calendar: &Calendar, path: &str, now: DateTime<Utc>) -> anyhow::Result<()> {
+    let text = calendar.render(now);
+    if path == "-" {
+        print!("{}", text);
+        return Ok(());
+    }
+    let tmp = format!("{path}.tmp");
+    std::fs::write(&tmp, text)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn describe(
+    day: &DailyForecast,
+    location: &str,
+    offset: &chrono::FixedOffset,
+    units: &str,
+) -> String {
+    let degree = temp_unit(units);
+    let mut lines = vec![
+        format!("{} in {}", capitalize(&day.description), location),
+        format!(
+            "High {:.1}{deg}, low {:.1}{deg}",
+            day.temp_max,
+            day.temp_min,
+            deg = degree
+        ),
+        format!(
+            "Precipitation: {:.0}% ({})",
+            day.pop * 100.0,
+            precip_amount(day.rain + day.snow, units)
+        ),
+        format!(
+            "Wind: {}",
+            wind_text(day.wind_speed, day.wind_deg, day.wind_gust, units)
+        ),
+    ];
+    if let Some(uv) = day.uvi {
+        lines.push(format!("UV index: {:.1}", uv));
+    }
+    if let (Some(rise), Some(set)) = (day.sunrise, day.sunset) {
+        lines.push(format!(
+            "Sunrise {}, sunset {}",
+            clock(rise, offset),
+            clock(set, offset)
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Weather symbol for a day, from its condition code so it does not depend
+/// on the description's language; falls back to the precipitation amounts
+fn icon(day: &DailyForecast) -> &'static str {
+    match day.condition {
+        Condition::Thunderstorm => "⛈",
+        Condition::Snow => "❄",
+        Condition::Rain | Condition::Drizzle => "🌧",
+        Condition::Fog => "🌫",
+        Condition::Cloudy => "☁",
+        Condition::PartlyCloudy => "⛅",
+        Condition::Clear => "☀",
+        Condition::Unknown if day.snow > 0.0 => "❄",
+        Condition::Unknown if day.rain > 0.0 => "🌧",
+        Condition::Unknown => "☀",
+    }
+}
+
+/// "Davenport, IA, US" -> "davenport-ia-us"
+fn slug(name: &str) -> String {
+    // Keep '.' and '-' so coordinates ("41.5236,-90.5776") stay distinct
+    name.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '.' || c == '-'))
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// TEXT value escaping (RFC 5545 3.3.11)
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold lines longer than 75 octets, never splitting a UTF-8 character
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            // The leading space counts toward the next line
+            len = 1;
+        }
+        out.push(c);
+        len += c.len_utf8();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CurrentConditions;
+
+    // 2024-06-20 00:00 UTC (Thursday)
+    const DAY0: i64 = 1_718_841_600;
+
+    fn location(name: &str) -> Location {
+        Location {
+            lat: 41.5236,
+            lon: -90.5776,
+            name: name.to_string(),
+        }
+    }
+
+    fn zip() -> LocationQuery {
+        LocationQuery::Zip("52726".to_string())
+    }
+
+    fn forecast(days: i64) -> Forecast {
+        Forecast {
+            timezone: "America/Chicago".to_string(),
+            timezone_offset: -5 * 3600,
+            current: CurrentConditions::default(),
+            minutely: Vec::new(),
+            hourly: Vec::new(),
+            daily: (0..days)
+                .map(|i| DailyForecast {
+                    dt: DAY0 + i * 86_400 + 17 * 3600,
+                    temp_min: 61.2,
+                    temp_max: 78.4,
+                    pop: 0.2,
+                    rain: 1.0,
+                    wind_speed: 8.0,
+                    wind_deg: Some(225.0),
+                    uvi: Some(7.0),
+                    description: if i == 1 { "light rain" } else { "clear sky" }.to_string(),
+                    condition: if i == 1 {
+                        Condition::Rain
+                    } else {
+                        Condition::Clear
+                    },
+                    ..Default::default()
+                })
+                .collect(),
+            alerts: Vec::new(),
+            air_quality: None,
+            solar: None,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.timestamp_opt(DAY0 + 10 * 3600, 0).unwrap()
+    }
+
+    #[test]
+    fn test_all_day_events_with_stable_uids() {
+        let mut cal = Calendar::default();
+        cal.add(&zip(), &location("Davenport, IA, US"), &forecast(8), "imperial");
+        let ics = cal.render(now());
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 7);
+        assert!(ics.contains("UID:20240620-zip-52726-us@weatherust\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20240620\r\nDTEND;VALUE=DATE:20240621\r\n"));
+        assert!(ics.contains("SUMMARY:☀ 78/61 clear sky\r\n"));
+        assert!(ics.contains("SUMMARY:🌧 78/61 light rain\r\n"));
+        assert!(ics.contains("LOCATION:Davenport\\, IA\\, US\r\n"));
+        assert!(ics.contains("DTSTAMP:20240620T100000Z\r\n"));
+
+        // A later run produces the same UIDs
+        let later = cal.render(now() + chrono::Duration::hours(6));
+        let uids = |s: &str| -> Vec<String> {
+            s.lines()
+                .filter(|l| l.starts_with("UID:"))
+                .map(str::to_string)
+                .collect()
+        };
+        assert_eq!(uids(&ics), uids(&later));
+
+        // So does the same place under another display name (provider fallback)
+        let mut renamed = Calendar::default();
+        renamed.add(&zip(), &location("Davenport"), &forecast(8), "imperial");
+        assert_eq!(uids(&ics), uids(&renamed.render(now())));
+
+        // Coordinates keep their sign
+        let mut coords = Calendar::default();
+        let query = LocationQuery::Coords {
+            lat: 41.5236,
+            lon: -90.5776,
+        };
+        coords.add(&query, &location("Davenport"), &forecast(1), "imperial");
+        assert!(coords
+            .render(now())
+            .contains("UID:20240620-coords-41.5236--90.5776@weatherust\r\n"));
+    }
+
+    #[test]
+    fn test_description_escaped_and_folded() {
+        let mut cal = Calendar::default();
+        cal.add(&zip(), &location("Davenport, IA, US"), &forecast(1), "imperial");
+        let ics = cal.render(now());
+        // Unfold before looking at the content
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(
+            "DESCRIPTION:Clear sky in Davenport\\, IA\\, US\\nHigh 78.4°F\\, low 61.2°F\\n\
+             Precipitation: 20% (0.04 in)\\nWind: 8.0 mph SW\\nUV index: 7.0\r\n"
+        ));
+        assert!(ics.split("\r\n").all(|l| l.len() <= 75));
+    }
+
+    #[test]
+    fn test_several_locations_named_in_summary() {
+        let mut cal = Calendar::default();
+        assert!(cal.is_empty());
+        cal.add(&zip(), &location("Davenport, IA, US"), &forecast(2), "imperial");
+        let london = LocationQuery::Place("London".to_string());
+        cal.add(&london, &location("London, GB"), &forecast(2), "imperial");
+        let ics = cal.render(now());
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 4);
+        assert!(ics.contains("SUMMARY:London\\, GB: ☀ 78/61 clear sky\r\n"));
+        assert!(ics.contains("UID:20240621-place-london@weatherust\r\n"));
+    }
+
+    #[test]
+    fn test_icon_ignores_description_language() {
+        let day = |description: &str, condition, snow| DailyForecast {
+            description: description.to_string(),
+            condition,
+            snow,
+            ..Default::default()
+        };
+        assert_eq!(icon(&day("neige légère", Condition::Snow, 0.0)), "❄");
+        assert_eq!(icon(&day("light snow", Condition::Cloudy, 0.0)), "☁");
+        assert_eq!(icon(&day("", Condition::Unknown, 2.0)), "❄");
+        assert_eq!(icon(&day("", Condition::Unknown, 0.0)), "☀");
+    }
+
+    #[test]
+    fn test_fold_keeps_characters_whole() {
+        let line = format!("SUMMARY:{}", "☀".repeat(40));
+        let folded = fold(&line);
+        assert!(folded.split("\r\n").all(|l| l.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
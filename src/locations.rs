@@ -0,0 +1,223 @@
+//! Location targets for multi-location runs and per-location notification routing
+//!
+//! A target is a location query plus optional routing overrides, written as
+//!
+//! ```text
+//! <zip, place or lat,lon>[|ntfy=<topic>][|gotify=<app token>]
+//! ```
+//!
+//! `WEATHER_LOCATIONS` holds several targets separated by `;`. Targets without
+//! overrides notify through the usual WEATHERUST_NTFY_TOPIC / WEATHERUST_GOTIFY_KEY.
+//! A target with any override replaces that pair: it only notifies through the
+//! channels it names.
+
+use anyhow::{bail, Result};
+use common::{send_gotify_weatherust_to, send_ntfy_weatherust_to, Priority};
+use reqwest::Client;
+use tracing::warn;
+
+use crate::looks_like_zip;
+
+/// What the user asked for, before any provider geocodes it
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocationQuery {
+    Zip(String),
+    Place(String),
+    /// Explicit coordinates; only the display name is looked up (reverse geocoding)
+    Coords { lat: f64, lon: f64 },
+}
+
+/// Where a location's notifications go
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Route {
+    pub gotify_key: Option<String>,
+    pub ntfy_topic: Option<String>,
+}
+
+impl Route {
+    /// No overrides: notify through the WEATHERUST_* defaults
+    fn is_default(&self) -> bool {
+        self.gotify_key.is_none() && self.ntfy_topic.is_none()
+    }
+
+    fn sends_gotify(&self) -> bool {
+        self.is_default() || self.gotify_key.is_some()
+    }
+
+    fn sends_ntfy(&self) -> bool {
+        self.is_default() || self.ntfy_topic.is_some()
+    }
+
+    /// Send to Gotify and ntfy, logging (not failing on) delivery errors
+    pub async fn notify(&self, client: &Client, title: &str, body: &str, priority: Priority) {
+        if self.sends_gotify() {
+            if let Err(e) = send_gotify_weatherust_to(
+                client,
+                title,
+                body,
+                self.gotify_key.as_deref(),
+                priority,
+            )
+            .await
+            {
+                warn!(error = %e, "Gotify send error");
+            }
+        }
+        if self.sends_ntfy() {
+            if let Err(e) = send_ntfy_weatherust_to(
+                client,
+                title,
+                body,
+                self.ntfy_topic.as_deref(),
+                None,
+                priority,
+            )
+            .await
+            {
+                warn!(error = %e, "ntfy send error");
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationTarget {
+    pub query: LocationQuery,
+    pub route: Route,
+}
+
+impl LocationTarget {
+    /// Key for run-to-run state (alerts, nowcast, changes, history)
+    ///
+    /// Derived from the query, not the display name, which changes with a
+    /// provider fallback or a refreshed geocode.
+    pub fn state_key(&self) -> String {
+        crate::geocache::cache_key(&self.query)
+    }
+}
+
+/// How to interpret the query part of a target
+#[derive(Debug, Clone, Copy)]
+pub enum QueryKind {
+    Zip,
+    Place,
+    /// Guess from the shape of the query (ZIP if it looks like one)
+    Auto,
+}
+
+/// Parse one target, e.g. `52726|ntfy=home-weather` or `Davenport,IA,US`
+pub fn parse_target(spec: &str, kind: QueryKind) -> Result<LocationTarget> {
+    let mut parts = spec.split('|').map(str::trim);
+    let query = parts.next().unwrap_or_default();
+    if query.is_empty() {
+        bail!("empty location in '{spec}'");
+    }
+
+    let mut route = Route::default();
+    for part in parts.filter(|p| !p.is_empty()) {
+        match part.split_once('=') {
+            Some(("ntfy", v)) if !v.trim().is_empty() => {
+                route.ntfy_topic = Some(v.trim().to_string())
+            }
+            Some(("gotify", v)) if !v.trim().is_empty() => {
+                route.gotify_key = Some(v.trim().to_string())
+            }
+            _ => bail!(
+                "invalid routing '{part}' in '{spec}' (expected ntfy=<topic> or gotify=<token>)"
+            ),
+        }
+    }
+
+    let query = match (kind, parse_coords(query)) {
+        (QueryKind::Zip, _) => LocationQuery::Zip(query.to_string()),
+        (_, Some((lat, lon))) => LocationQuery::Coords { lat, lon },
+        (QueryKind::Auto, None) if looks_like_zip(query) => LocationQuery::Zip(query.to_string()),
+        _ => LocationQuery::Place(query.to_string()),
+    };
+
+    Ok(LocationTarget { query, route })
+}
+
+/// "41.52,-90.58" -> (41.52, -90.58) when both parts are valid coordinates
+fn parse_coords(s: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = s.split_once(',')?;
+    let lat: f64 = lat.trim().parse().ok()?;
+    let lon: f64 = lon.trim().parse().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// Parse a `;`-separated list of targets (WEATHER_LOCATIONS)
+pub fn parse_target_list(list: &str) -> Result<Vec<LocationTarget>> {
+    list.split(';')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| parse_target(s, QueryKind::Auto))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target_plain() {
+        let t = parse_target("52726", QueryKind::Auto).unwrap();
+        assert_eq!(t.query, LocationQuery::Zip("52726".to_string()));
+        assert_eq!(t.route, Route::default());
+
+        let t = parse_target("Davenport,IA,US", QueryKind::Auto).unwrap();
+        assert_eq!(t.query, LocationQuery::Place("Davenport,IA,US".to_string()));
+
+        // Explicit kind wins over the guess
+        let t = parse_target("52726", QueryKind::Place).unwrap();
+        assert_eq!(t.query, LocationQuery::Place("52726".to_string()));
+
+        let t = parse_target("41.5236, -90.5776|ntfy=home", QueryKind::Auto).unwrap();
+        assert_eq!(
+            t.query,
+            LocationQuery::Coords {
+                lat: 41.5236,
+                lon: -90.5776
+            }
+        );
+        // Not coordinates: out of range
+        let t = parse_target("123,456", QueryKind::Place).unwrap();
+        assert_eq!(t.query, LocationQuery::Place("123,456".to_string()));
+    }
+
+    #[test]
+    fn test_parse_target_routing() {
+        let t = parse_target(
+            "London,GB | ntfy=uk-weather | gotify=AbC123",
+            QueryKind::Auto,
+        )
+        .unwrap();
+        assert_eq!(t.query, LocationQuery::Place("London,GB".to_string()));
+        assert_eq!(t.route.ntfy_topic.as_deref(), Some("uk-weather"));
+        assert_eq!(t.route.gotify_key.as_deref(), Some("AbC123"));
+
+        assert!(parse_target("52726|slack=#weather", QueryKind::Auto).is_err());
+        assert!(parse_target("52726|ntfy=", QueryKind::Auto).is_err());
+        assert!(parse_target("|ntfy=x", QueryKind::Auto).is_err());
+    }
+
+    #[test]
+    fn test_route_channels() {
+        let route = |spec: &str| parse_target(spec, QueryKind::Auto).unwrap().route;
+        let plain = route("52726");
+        assert!(plain.sends_gotify() && plain.sends_ntfy());
+        // An override replaces the WEATHERUST_* pair, not just its own channel
+        let ntfy_only = route("52726|ntfy=home");
+        assert!(ntfy_only.sends_ntfy() && !ntfy_only.sends_gotify());
+        let gotify_only = route("52726|gotify=AbC123");
+        assert!(gotify_only.sends_gotify() && !gotify_only.sends_ntfy());
+    }
+
+    #[test]
+    fn test_parse_target_list() {
+        let list = parse_target_list("52726|ntfy=home; Davenport,IA,US ;").unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].route.ntfy_topic.as_deref(), Some("home"));
+        assert_eq!(list[1].route, Route::default());
+    }
+}
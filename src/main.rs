@@ -1,86 +1,172 @@
 use std::env;
 use std::io::{self, Write};
 
-use chrono::{FixedOffset, TimeZone};
-use clap::Parser;
-use common::{dotenv_init, send_gotify_weatherust, send_ntfy_weatherust};
+use chrono::FixedOffset;
+use clap::{Parser, Subcommand, ValueEnum};
+use common::{dotenv_init, Priority};
 use reqwest::Client;
-use serde::Deserialize;
-use std::time::Instant;
-use tracing::warn;
+use tracing::{error, info, warn};
+
+mod alerts;
+mod changes;
+mod geocache;
+mod history;
+mod ics;
+mod locations;
+mod metar;
+mod model;
+mod narrative;
+mod nowcast;
+mod provider;
+mod report;
+mod rules;
+mod solar;
+
+use alerts::{format_alert, SentAlerts};
+use geocache::GeoCache;
+use locations::{
+    parse_target, parse_target_list, LocationQuery, LocationTarget, QueryKind, Route,
+};
+use model::{Forecast, Location};
+use provider::{fetch_with_fallback, format_coords, ProviderKind, WeatherProvider};
+use report::OutputFormat;
+
+/// Name of weatherust's state directory (override: `WEATHERUST_STATE_DIR`)
+const STATE_APP: &str = "weatherust";
 
 /// CLI flags for non-interactive runs (systemd, cron, n8n)
 #[derive(Parser, Debug)]
 #[command(name = "weatherust")]
 #[command(about = "Weather -> Gotify (current + next 6 days)")]
 struct Args {
-    /// ZIP code (e.g., 52726). If present, skips prompt.
-    #[arg(long)]
-    zip: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Free-form location (e.g., "Davenport,IA,US"). Used if --zip is not given.
+    /// ZIP code (e.g., 52726). Repeatable. If present, skips prompt.
+    /// Append "|ntfy=<topic>" or "|gotify=<token>" to route this location elsewhere.
     #[arg(long)]
-    location: Option<String>,
+    zip: Vec<String>,
 
-    /// Units: "imperial" (°F) or "metric" (°C). If omitted, uses DEFAULT_UNITS env or falls back to "imperial".
+    /// Free-form location (e.g., "Davenport,IA,US"). Repeatable, same routing suffixes as --zip.
     #[arg(long)]
+    location: Vec<String>,
+
+    /// Latitude for an explicit coordinate location (requires --lon); skips forward geocoding
+    #[arg(long, requires = "lon", allow_hyphen_values = true)]
+    lat: Option<f64>,
+
+    /// Longitude for an explicit coordinate location (requires --lat)
+    #[arg(long, requires = "lat", allow_hyphen_values = true)]
+    lon: Option<f64>,
+
+    /// Ignore cached geocoding results and look every location up again
+    #[arg(long, default_value_t = false)]
+    refresh_geocode: bool,
+
+    /// Units: "imperial" (°F, mph), "metric" (°C, m/s) or "standard" (K, m/s).
+    /// If omitted, uses DEFAULT_UNITS env or falls back to "imperial".
+    #[arg(long, global = true)]
     units: Option<String>,
 
+    /// Language for weather descriptions (OWM codes, e.g. "de", "fr", "pt_br").
+    /// If omitted, uses DEFAULT_LANG env or the provider default (English).
+    #[arg(long)]
+    lang: Option<String>,
+
     /// If set, don't print to stdout; only send Gotify
     #[arg(long, default_value_t = false)]
     quiet: bool,
-}
 
-#[derive(Debug, Deserialize)]
-struct GeoResult {
-    name: String,
-    lat: f64,
-    lon: f64,
-    country: String,
-    state: Option<String>,
-}
+    /// Summary output format. Markdown is also used as the notification body
+    /// (ntfy renders it); json is printed only, notifications get the text body.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
 
-// For ZIP geocoding (returns a single object)
-#[derive(Debug, Deserialize)]
-struct ZipGeoResult {
-    name: String,
-    lat: f64,
-    lon: f64,
-    country: String,
-}
+    /// Alerts mode: push each new severe weather alert as its own high-priority
+    /// notification instead of sending the daily summary
+    #[arg(long, default_value_t = false)]
+    alerts: bool,
 
-#[derive(Debug, Deserialize)]
-struct OneCall {
-    timezone: String,
-    timezone_offset: i32, // seconds
-    current: Current,
-    daily: Vec<Daily>,
-}
+    /// Nowcast mode: report when precipitation starts or stops in the next
+    /// --nowcast-horizon minutes, notifying only when the answer changes
+    #[arg(long, default_value_t = false)]
+    nowcast: bool,
 
-#[derive(Debug, Deserialize)]
-struct Current {
-    dt: i64, // unix seconds
-    temp: f64,
-    humidity: u8,
-    weather: Vec<Weather>,
-}
+    /// Nowcast look-ahead in minutes (60-120)
+    #[arg(long, default_value_t = 120, value_parser = clap::value_parser!(u32).range(60..=120))]
+    nowcast_horizon: u32,
 
-#[derive(Debug, Deserialize)]
-struct Daily {
-    dt: i64,
-    temp: DailyTemp,
-    weather: Vec<Weather>,
-}
+    /// Changes mode: compare with the previous run's forecast and notify only
+    /// when a future day changes significantly or a new alert appears
+    #[arg(long, default_value_t = false)]
+    changes: bool,
+
+    /// Degrees a high/low must move before --changes reports it
+    #[arg(long, default_value_t = 5.0)]
+    change_threshold: f64,
+
+    /// Weather provider to try first; the other one is used as fallback.
+    /// If omitted, uses WEATHER_PROVIDER env or falls back to "owm".
+    #[arg(long, value_enum)]
+    provider: Option<ProviderKind>,
+
+    /// Rules mode: evaluate WEATHER_RULES / --rules-file against the forecast
+    /// and notify only when a rule fires
+    #[arg(long, default_value_t = false)]
+    rules: bool,
+
+    /// File with one rule per line (e.g. "Frost: low < 32 tomorrow").
+    /// If omitted, uses WEATHER_RULES_FILE env.
+    #[arg(long)]
+    rules_file: Option<String>,
 
-#[derive(Debug, Deserialize)]
-struct DailyTemp {
-    min: f64,
-    max: f64,
+    /// Aviation mode: fetch and decode the METAR/TAF for an ICAO station
+    /// (e.g. KDVN) instead of the forecast. Repeatable.
+    #[arg(long)]
+    station: Vec<String>,
+
+    /// Decode raw METARs (one per line) from a file, or "-" for stdin
+    #[arg(long, conflicts_with = "station")]
+    metar_file: Option<String>,
+
+    /// Calendar mode: write the 7-day forecast as all-day iCalendar events
+    /// to this file ("-" for stdout) instead of notifying
+    #[arg(long)]
+    ics: Option<String>,
+
+    /// Solar outlook: PV array size in kWp. Adds the expected production for
+    /// the next 7 days to the report. If omitted, uses SOLAR_KWP env.
+    #[arg(long)]
+    solar_kwp: Option<f64>,
+
+    /// Panel tilt from horizontal in degrees.
+    /// If omitted, uses SOLAR_TILT env or falls back to 30.
+    #[arg(long)]
+    solar_tilt: Option<f64>,
+
+    /// Direction the panels face in degrees (90 = E, 180 = S, 270 = W).
+    /// If omitted, uses SOLAR_AZIMUTH env or falls back to 180.
+    #[arg(long)]
+    solar_azimuth: Option<f64>,
 }
 
-#[derive(Debug, Deserialize)]
-struct Weather {
-    description: String,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show low/high/average temperatures, records and precipitation from
+    /// the locally recorded observations
+    History {
+        /// Group observations by day, week or month
+        #[arg(long, value_enum, default_value_t = history::Period::Day)]
+        period: history::Period,
+
+        /// Number of most recent periods to show
+        #[arg(long, default_value_t = 7)]
+        last: usize,
+
+        /// Only locations whose name contains this text (case-insensitive)
+        #[arg(long)]
+        location: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -97,8 +183,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let args = Args::parse();
 
-    let api_key = env::var("OWM_API_KEY").expect("Missing OWM_API_KEY in environment or .env file");
-
     // Units: CLI flag -> DEFAULT_UNITS env -> "imperial"
     let units = args
         .units
@@ -106,144 +190,427 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .or_else(|| env::var("DEFAULT_UNITS").ok())
         .unwrap_or_else(|| "imperial".to_string())
         .to_lowercase();
+    if !matches!(units.as_str(), "imperial" | "metric" | "standard") {
+        return Err(format!("invalid units '{units}' (expected imperial, metric or standard)").into());
+    }
+
+    if let Some(Command::History {
+        period,
+        last,
+        location,
+    }) = &args.command
+    {
+        return show_history(*period, *last, location.as_deref(), &units);
+    }
+
+    // Language: CLI flag -> DEFAULT_LANG env -> provider default
+    let lang = args
+        .lang
+        .clone()
+        .or_else(|| env::var("DEFAULT_LANG").ok())
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty());
+
+    // Provider: CLI flag -> WEATHER_PROVIDER env -> OpenWeatherMap
+    let provider = match args.provider {
+        Some(p) => p,
+        None => match env::var("WEATHER_PROVIDER") {
+            Ok(v) if !v.trim().is_empty() => ProviderKind::from_str(v.trim(), true)
+                .map_err(|e| format!("invalid WEATHER_PROVIDER: {e}"))?,
+            _ => ProviderKind::Owm,
+        },
+    };
 
     // Create one HTTP client for all requests
     let client = Client::new();
 
-    // Resolve location to lat/lon and a pretty display name
-    let (lat, lon, pretty_location) = resolve_location(&client, &api_key, &args).await?;
+    // Aviation mode replaces the forecast entirely
+    if !args.station.is_empty() || args.metar_file.is_some() {
+        return send_station_reports(&client, &args).await;
+    }
 
-    // ---- One Call daily forecast + current ----
-    // If your account lacks One Call 3.0, change the path to /data/2.5/onecall
-    let onecall_url = format!(
-        "https://api.openweathermap.org/data/3.0/onecall?lat={lat}&lon={lon}&exclude=minutely,hourly,alerts&units={units}&appid={api_key}"
-    );
+    let targets = location_targets(&args)?;
+    let rules = if args.rules {
+        Some(load_rules(args.rules_file.as_deref())?)
+    } else {
+        None
+    };
+    let solar_array = solar_array(&args)?;
+
+    // Reuse cached geocoding results unless --refresh-geocode
+    let now = chrono::Utc::now().timestamp();
+    let ttl = geocache::ttl_secs();
+    let mut geo_cache = GeoCache::load();
+    let mut geo_cache_dirty = false;
+
+    // Resolve and fetch every location concurrently, falling back to the other provider on error
+    let order = provider.with_fallback();
+    let mut tasks = Vec::new();
+    for target in &targets {
+        let client = client.clone();
+        let query = target.query.clone();
+        let units = units.clone();
+        let lang = lang.clone();
+        let cached = if args.refresh_geocode {
+            None
+        } else {
+            geo_cache.get(&query, now, ttl)
+        };
+        let was_cached = cached.is_some();
+        tasks.push((
+            was_cached,
+            tokio::spawn(async move {
+                fetch_with_fallback(&client, &order, &query, cached.as_ref(), &units, lang.as_deref())
+                    .await
+            }),
+        ));
+    }
 
-    // Track API call timing
-    let start = Instant::now();
-    let oc_result = client.get(&onecall_url).send().await?.error_for_status();
-    let elapsed = start.elapsed().as_secs_f64();
+    let mut calendar = args.ics.as_ref().map(|_| ics::Calendar::default());
+    let mut failed = 0;
+    let mut last_err: Option<Box<dyn std::error::Error>> = None;
+    for (target, (was_cached, task)) in targets.iter().zip(tasks) {
+        let result = match task.await {
+            Ok(r) => r,
+            Err(e) => Err(e.into()),
+        };
+        let (used, location, mut data) = match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!(location = ?target.query, error = %e, "Weather fetch failed");
+                failed += 1;
+                last_err = Some(e.into());
+                continue;
+            }
+        };
+        if used != provider {
+            info!(provider = ?used, location = %location.name, "Using fallback weather provider");
+        }
 
-    let oc_resp = match oc_result {
-        Ok(resp) => {
-            common::metrics::record_weather_fetch(true, elapsed);
-            resp
+        // Coordinates without a reverse-geocoded name are not worth caching
+        let learned_name = !matches!(target.query, LocationQuery::Coords { .. })
+            || location.name != format_coords(location.lat, location.lon);
+        if !was_cached && learned_name {
+            geo_cache.insert(&target.query, &location, now);
+            geo_cache_dirty = true;
         }
-        Err(e) => {
-            common::metrics::record_weather_fetch(false, elapsed);
-            return Err(e.into());
+
+        if let Some(array) = &solar_array {
+            data.solar = Some(solar::outlook(array, &location, &data));
         }
-    };
 
-    let data: OneCall = oc_resp.json().await?;
+        if let Some(calendar) = calendar.as_mut() {
+            calendar.add(&target.query, &location, &data, &units);
+            continue;
+        }
 
-    // timezone-aware timestamp for "current"
-    let offset =
-        FixedOffset::east_opt(data.timezone_offset).expect("invalid timezone offset from API");
-    let current_time = offset.timestamp_opt(data.current.dt, 0).unwrap();
+        if let Err(e) =
+            report_location(&client, &args, &units, rules.as_deref(), target, &location, &data)
+                .await
+        {
+            error!(location = %location.name, error = %e, "Weather report failed");
+            failed += 1;
+            last_err = Some(e);
+        }
+    }
 
-    let current_desc = data
-        .current
-        .weather
-        .get(0)
-        .map(|w| w.description.as_str())
-        .unwrap_or("no description");
+    if geo_cache_dirty {
+        geo_cache.prune(now, ttl);
+        if let Err(e) = geo_cache.save() {
+            warn!(error = %e, "Failed to save geocoding cache");
+        }
+    }
 
-    // Build human-readable output and a concise Gotify message
-    let (unit_label, degree) = if units == "metric" {
-        ("°C", "°C")
-    } else {
-        ("°F", "°F")
-    };
+    if let (Some(calendar), Some(path)) = (&calendar, &args.ics) {
+        if !calendar.is_empty() {
+            ics::write(calendar, path, chrono::Utc::now())
+                .map_err(|e| format!("failed to write calendar {path}: {e}"))?;
+            if path != "-" && !args.quiet {
+                println!("Wrote forecast calendar to {}", path);
+            }
+        }
+    }
 
-    // Today is daily[0]
-    let today_high = data.daily.get(0).map(|d| d.temp.max);
-    let today_low = data.daily.get(0).map(|d| d.temp.min);
-
-    // Detailed multi-line body
-    let mut lines = Vec::new();
-    lines.push(format!(
-        "Location: {}\nTimezone: {}\nNow: {} | {} | Temp: {:.1} {} | Humidity: {}%",
-        pretty_location,
-        data.timezone,
-        current_time,
-        current_desc,
-        data.current.temp,
-        unit_label,
-        data.current.humidity
-    ));
-    lines.push("\nNext 7 days (high/low):".to_string());
-
-    for day in data.daily.iter().skip(1).take(7) {
-        let dt = offset.timestamp_opt(day.dt, 0).unwrap();
-        let label = dt.format("%a %d").to_string();
-        let desc = day
-            .weather
-            .get(0)
-            .map(|w| w.description.as_str())
-            .unwrap_or("n/a");
-        lines.push(format!(
-            "  {label}: {:>5.1}{deg}/{:>5.1}{deg}  ({desc})",
-            day.temp.max,
-            day.temp.min,
-            deg = degree
-        ));
+    // A single failing site should not hide the others, but fail the run if nothing worked
+    match last_err {
+        Some(e) if failed == targets.len() => Err(e),
+        _ => Ok(()),
     }
+}
 
-    let human_output = lines.join("\n");
+/// Run the selected mode for one location and notify through its route
+async fn report_location(
+    client: &Client,
+    args: &Args,
+    units: &str,
+    rules: Option<&[rules::Rule]>,
+    target: &LocationTarget,
+    location: &Location,
+    data: &Forecast,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let route = &target.route;
+    let pretty_location = location.name.as_str();
+
+    let offset = data.offset();
+
+    if args.alerts {
+        return send_new_alerts(client, target, data, &offset, pretty_location, args.quiet).await;
+    }
 
-    // Concise single-line summary for Gotify title/message
-    let summary = match (today_high, today_low) {
-        (Some(h), Some(l)) => format!(
-            "Now: {:.1}{} ({}) | Today H/L: {:.1}{}/ {:.1}{}",
-            data.current.temp, degree, current_desc, h, degree, l, degree
-        ),
-        _ => format!("Now: {:.1}{} ({})", data.current.temp, degree, current_desc),
-    };
+    if args.nowcast {
+        return send_nowcast(
+            client,
+            target,
+            data,
+            &offset,
+            pretty_location,
+            args.nowcast_horizon,
+            args.quiet,
+        )
+        .await;
+    }
+
+    if args.changes {
+        return send_changes(
+            client,
+            target,
+            data,
+            units,
+            pretty_location,
+            args.change_threshold,
+            args.quiet,
+        )
+        .await;
+    }
+
+    if let Some(rules) = rules {
+        return send_rule_hits(
+            client,
+            route,
+            data,
+            rules,
+            units,
+            pretty_location,
+            args.quiet,
+        )
+        .await;
+    }
+
+    // Only forecast reports feed the history; the alert, nowcast, change and
+    // rule modes run far more often
+    let observation =
+        history::Observation::from_forecast(&target.state_key(), pretty_location, data, units);
+    if let Err(e) = history::record(&observation, history::keep_days()) {
+        warn!(error = %e, "Failed to record observation history");
+    }
+
+    // Concise single-line summary for the notification title
+    let summary = report::summary_line(data, units);
+    let output = report::render(args.format, location, data, units)?;
 
     // Print unless --quiet
     if !args.quiet {
-        println!("{}", human_output);
+        println!("{}", output);
     }
 
-    // Send to Gotify (if configured)
-    if let Err(e) = send_gotify_weatherust(&client, &summary, &human_output).await {
-        warn!(error = %e, "Gotify send error");
+    let body = match args.format {
+        OutputFormat::Json => report::render_text(location, data, units),
+        _ => output,
+    };
+
+    // Poor air or strong UV: say so in the title and raise the priority
+    let warnings = report::exposure_warnings(data, env_threshold("AQI_WARN"), env_threshold("UV_WARN"));
+    let (title, priority) = if warnings.is_empty() {
+        (summary, Priority::Normal)
+    } else {
+        (format!("⚠️ {} | {}", warnings.join(", "), summary), Priority::High)
+    };
+
+    // Send to Gotify / ntfy.sh (if configured)
+    route.notify(client, &title, &body, priority).await;
+
+    Ok(())
+}
+
+
+// ----------------- helpers -----------------
+
+/// Push every alert that has not been sent before, then remember it
+async fn send_new_alerts(
+    client: &Client,
+    target: &LocationTarget,
+    data: &Forecast,
+    offset: &FixedOffset,
+    location: &str,
+    quiet: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let key = target.state_key();
+    let mut sent = SentAlerts::load();
+    sent.prune(data.current.dt);
+
+    let new_alerts: Vec<_> = data
+        .alerts
+        .iter()
+        .filter(|a| sent.is_new(&key, a))
+        .collect();
+    if new_alerts.is_empty() && !quiet {
+        println!(
+            "No new alerts for {} ({} active)",
+            location,
+            data.alerts.len()
+        );
     }
 
-    // Send to ntfy.sh (if configured)
-    if let Err(e) = send_ntfy_weatherust(&client, &summary, &human_output, None).await {
-        warn!(error = %e, "ntfy send error");
+    for alert in new_alerts {
+        let (title, body) = format_alert(alert, offset, location);
+        if !quiet {
+            println!("{}\n{}\n", title, body);
+        }
+
+        target.route.notify(client, &title, &body, Priority::High).await;
+
+        sent.mark_sent(&key, alert);
+    }
+
+    if let Err(e) = sent.save() {
+        warn!(error = %e, "Failed to save sent alerts state");
     }
 
     Ok(())
 }
 
-// ----------------- helpers -----------------
+/// Print history statistics per location (`weatherust history`)
+fn show_history(
+    period: history::Period,
+    last: usize,
+    location: Option<&str>,
+    units: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let observations = history::load();
+    let summaries = history::summarize(&observations, period, last, location);
+    if summaries.is_empty() {
+        println!("No recorded observations yet; history is collected on every weatherust run.");
+        return Ok(());
+    }
+    let blocks: Vec<String> = summaries
+        .iter()
+        .map(|h| history::render(h, units))
+        .collect();
+    println!("{}", blocks.join("\n\n"));
+    Ok(())
+}
 
-async fn resolve_location(
+/// Decode METAR/TAF reports and notify one per station
+async fn send_station_reports(
     client: &Client,
-    api_key: &str,
     args: &Args,
-) -> Result<(f64, f64, String), Box<dyn std::error::Error>> {
-    // Highest priority: explicit CLI flags
-    if let Some(zip) = args.zip.as_deref() {
-        return geocode_zip(client, api_key, zip).await;
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reports = Vec::new();
+    if let Some(path) = &args.metar_file {
+        let text = if path == "-" {
+            io::read_to_string(io::stdin())?
+        } else {
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read METAR file {path}: {e}"))?
+        };
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let metar = metar::parse_metar(line).map_err(|e| format!("{e}: {line}"))?;
+            reports.push(metar::StationReport { metar, taf: None });
+        }
+        if reports.is_empty() {
+            return Err(format!("no METARs found in {path}").into());
+        }
     }
 
-    if let Some(loc) = args.location.as_deref() {
-        return geocode_location(client, api_key, loc).await;
+    let mut last_err: Option<Box<dyn std::error::Error>> = None;
+    for station in &args.station {
+        match fetch_station_report(client, station).await {
+            Ok(report) => reports.push(report),
+            Err(e) => {
+                error!(station = %station, error = %e, "METAR fetch failed");
+                last_err = Some(e.into());
+            }
+        }
+    }
+
+    for report in &reports {
+        let output = report.render(args.format)?;
+        if !args.quiet {
+            println!("{}", output);
+        }
+
+        let body = match args.format {
+            OutputFormat::Json => report.render_text(),
+            _ => output,
+        };
+        let priority = if report.is_low_ifr() {
+            Priority::High
+        } else {
+            Priority::Normal
+        };
+        Route::default()
+            .notify(client, &report.metar.summary_line(), &body, priority)
+            .await;
+    }
+
+    match last_err {
+        Some(e) if reports.is_empty() => Err(e),
+        _ => Ok(()),
+    }
+}
+
+async fn fetch_station_report(client: &Client, station: &str) -> anyhow::Result<metar::StationReport> {
+    let (raw_metar, raw_taf) = metar::fetch_station(client, station).await?;
+    let metar = metar::parse_metar(&raw_metar)?;
+    let taf = raw_taf.and_then(|raw| match metar::parse_taf(&raw) {
+        Ok(taf) => Some(taf),
+        Err(e) => {
+            warn!(station = %station, error = %e, "Could not decode TAF");
+            None
+        }
+    });
+    Ok(metar::StationReport { metar, taf })
+}
+
+/// Pick the locations from CLI flags, then env defaults, then an interactive prompt
+fn location_targets(args: &Args) -> Result<Vec<LocationTarget>, Box<dyn std::error::Error>> {
+    // Highest priority: explicit CLI flags (repeatable)
+    let mut targets = Vec::new();
+    for zip in &args.zip {
+        targets.push(parse_target(zip, QueryKind::Zip)?);
+    }
+    for loc in &args.location {
+        targets.push(parse_target(loc, QueryKind::Place)?);
+    }
+    if let (Some(lat), Some(lon)) = (args.lat, args.lon) {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(format!("coordinates out of range: {lat},{lon}").into());
+        }
+        targets.push(LocationTarget {
+            query: LocationQuery::Coords { lat, lon },
+            route: Route::default(),
+        });
+    }
+    if !targets.is_empty() {
+        return Ok(targets);
     }
 
     // Next: environment-provided defaults
+    if let Ok(list) = env::var("WEATHER_LOCATIONS") {
+        if !list.trim().is_empty() {
+            let targets =
+                parse_target_list(&list).map_err(|e| format!("invalid WEATHER_LOCATIONS: {e}"))?;
+            return Ok(targets);
+        }
+    }
     if let Ok(zip) = env::var("DEFAULT_ZIP") {
         if !zip.trim().is_empty() {
-            return geocode_zip(client, api_key, zip.trim()).await;
+            return Ok(vec![parse_target(zip.trim(), QueryKind::Zip)?]);
         }
     }
     if let Ok(loc) = env::var("DEFAULT_LOCATION") {
         if !loc.trim().is_empty() {
-            return geocode_location(client, api_key, loc.trim()).await;
+            return Ok(vec![parse_target(loc.trim(), QueryKind::Place)?]);
         }
     }
 
@@ -257,51 +624,222 @@ async fn resolve_location(
         return Err("no input provided".into());
     }
 
-    if looks_like_zip(input) {
-        geocode_zip(client, api_key, input).await
+    Ok(vec![parse_target(input, QueryKind::Auto)?])
+}
+
+/// Work out the precipitation nowcast and notify only when it changed meaningfully
+async fn send_nowcast(
+    client: &Client,
+    target: &LocationTarget,
+    data: &Forecast,
+    offset: &FixedOffset,
+    location: &str,
+    horizon_minutes: u32,
+    quiet: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(current) = nowcast::compute(data, data.current.dt, horizon_minutes) else {
+        warn!("Provider returned no minutely/hourly data; cannot compute nowcast");
+        return Ok(());
+    };
+
+    let body = current.body(offset, location);
+    if !quiet {
+        println!("{}", body);
+    }
+
+    let key = target.state_key();
+    let previous = nowcast::load_last(&key);
+    let notify = current.differs_from(previous.as_ref());
+
+    if notify {
+        let title = format!("{} - {}", current.headline(), location);
+        target.route.notify(client, &title, &body, Priority::Normal).await;
+    } else if !quiet {
+        println!("(unchanged since last notification; not sending)");
+    }
+
+    // Keep the last *notified* prediction so small drifts cannot add up unnoticed,
+    // but follow kind changes so the next comparison starts from the right state
+    let kind_changed = previous.as_ref().map(|p| p.kind) != Some(current.kind);
+    if notify || kind_changed {
+        if let Err(e) = nowcast::save_last(&key, &current) {
+            warn!(error = %e, "Failed to save nowcast state");
+        }
+    }
+
+    Ok(())
+}
+
+/// Compare with the previous run's snapshot and notify only about significant changes
+async fn send_changes(
+    client: &Client,
+    target: &LocationTarget,
+    data: &Forecast,
+    units: &str,
+    location: &str,
+    threshold: f64,
+    quiet: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let current = changes::Snapshot::from_forecast(data, units);
+    let key = target.state_key();
+    let previous = changes::load_previous(&key);
+    let lines = match &previous {
+        Some(previous) => current.changes_since(previous, threshold),
+        None => {
+            if !quiet {
+                println!("No previous forecast for {}; saving a baseline", location);
+            }
+            Vec::new()
+        }
+    };
+
+    if lines.is_empty() {
+        if !quiet {
+            println!("No significant forecast changes for {}", location);
+        }
     } else {
-        geocode_location(client, api_key, input).await
+        let title = format!("Forecast changed - {}", location);
+        let body = lines.join("\n");
+        if !quiet {
+            println!("{}\n{}", title, body);
+        }
+        target.route.notify(client, &title, &body, Priority::Normal).await;
     }
+
+    // Keep comparing each day against its last notified values so slow drift adds up
+    let snapshot = match &previous {
+        Some(previous) => current.carry_forward(previous, threshold),
+        None => current,
+    };
+    if let Err(e) = changes::save(&key, &snapshot) {
+        warn!(error = %e, "Failed to save forecast snapshot");
+    }
+
+    Ok(())
 }
 
-async fn geocode_zip(
+/// Rules from WEATHER_RULES plus the rules file (--rules-file or WEATHER_RULES_FILE)
+fn load_rules(rules_file: Option<&str>) -> Result<Vec<rules::Rule>, Box<dyn std::error::Error>> {
+    let mut list = Vec::new();
+
+    if let Ok(inline) = env::var("WEATHER_RULES") {
+        let parsed = rules::parse_rules(&inline).map_err(|e| format!("invalid WEATHER_RULES: {e}"))?;
+        list.extend(parsed);
+    }
+
+    let path = rules_file
+        .map(str::to_string)
+        .or_else(|| env::var("WEATHER_RULES_FILE").ok())
+        .filter(|p| !p.trim().is_empty());
+    if let Some(path) = path {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read rules file {path}: {e}"))?;
+        let parsed = rules::parse_rules(&text).map_err(|e| format!("invalid rules in {path}: {e}"))?;
+        list.extend(parsed);
+    }
+
+    if list.is_empty() {
+        return Err("no rules found (set WEATHER_RULES or WEATHER_RULES_FILE)".into());
+    }
+    Ok(list)
+}
+
+/// PV array from --solar-* / SOLAR_* env; none unless a size is configured
+fn solar_array(args: &Args) -> Result<Option<solar::PvArray>, Box<dyn std::error::Error>> {
+    let Some(kwp) = args.solar_kwp.or_else(|| env_threshold("SOLAR_KWP")) else {
+        return Ok(None);
+    };
+    let tilt = args
+        .solar_tilt
+        .or_else(|| env_threshold("SOLAR_TILT"))
+        .unwrap_or(30.0);
+    let azimuth = args
+        .solar_azimuth
+        .or_else(|| env_threshold("SOLAR_AZIMUTH"))
+        .unwrap_or(180.0);
+    Ok(Some(solar::PvArray::new(kwp, tilt, azimuth)?))
+}
+
+/// Evaluate the rules and notify once with every hit; stay silent when none fire
+async fn send_rule_hits(
     client: &Client,
-    api_key: &str,
-    zip_in: &str,
-) -> Result<(f64, f64, String), Box<dyn std::error::Error>> {
-    let (zip, cc) = split_zip_and_cc(zip_in);
-    let url = format!("https://api.openweathermap.org/geo/1.0/zip?zip={zip},{cc}&appid={api_key}");
-    let resp = client.get(&url).send().await?.error_for_status()?;
-    let z: ZipGeoResult = resp.json().await?;
-    Ok((z.lat, z.lon, format!("{}, {}", z.name, z.country)))
+    route: &Route,
+    data: &Forecast,
+    rules: &[rules::Rule],
+    units: &str,
+    location: &str,
+    quiet: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let hits = rules::evaluate(rules, data, units);
+    if hits.is_empty() {
+        if !quiet {
+            println!("No rules fired for {} ({} checked)", location, rules.len());
+        }
+        return Ok(());
+    }
+
+    let mut names: Vec<&str> = Vec::new();
+    for hit in &hits {
+        if !names.contains(&hit.rule.as_str()) {
+            names.push(&hit.rule);
+        }
+    }
+    let title = format!("Weather rules: {} - {}", names.join(", "), location);
+    let body = hits
+        .iter()
+        .map(|h| h.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if !quiet {
+        println!("{}\n{}", title, body);
+    }
+
+    route.notify(client, &title, &body, Priority::Normal).await;
+
+    Ok(())
 }
 
-async fn geocode_location(
+/// Numeric threshold from the environment; unset or invalid means no threshold
+fn env_threshold<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let value = env::var(name).ok()?;
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    match value.parse() {
+        Ok(v) => Some(v),
+        Err(_) => {
+            warn!(name = name, value = value, "Ignoring invalid threshold");
+            None
+        }
+    }
+}
+
+/// Resolve a location query to lat/lon and a pretty display name
+async fn resolve_location<P: WeatherProvider>(
     client: &Client,
-    api_key: &str,
-    input: &str,
-) -> Result<(f64, f64, String), Box<dyn std::error::Error>> {
-    let q = normalize_city_query(input);
-    let url =
-        format!("https://api.openweathermap.org/geo/1.0/direct?q={q}&limit=1&appid={api_key}");
-    let resp = client.get(&url).send().await?.error_for_status()?;
-    let mut v: Vec<GeoResult> = resp.json().await?;
-    if v.is_empty() {
-        return Err(format!(
-            "Could not find coordinates for \"{input}\".\nHint: try \"City,STATE,US\" (e.g., Davenport,IA,US) or use a ZIP code."
-        ).into());
-    }
-    let loc = v.remove(0);
-    let pretty = format!(
-        "{}{}{}",
-        loc.name,
-        loc.state
-            .as_ref()
-            .map(|s| format!(", {}", s))
-            .unwrap_or_default(),
-        format!(", {}", loc.country)
-    );
-    Ok((loc.lat, loc.lon, pretty))
+    provider: &P,
+    query: &LocationQuery,
+) -> anyhow::Result<Location> {
+    match query {
+        LocationQuery::Zip(zip) => provider.geocode_zip(client, zip).await,
+        LocationQuery::Place(place) => provider.geocode_location(client, place).await,
+        LocationQuery::Coords { lat, lon } => {
+            let name = match provider.reverse_geocode(client, *lat, *lon).await {
+                Ok(name) => name,
+                Err(e) => {
+                    warn!(error = %e, "Reverse geocoding failed; using coordinates as the name");
+                    format_coords(*lat, *lon)
+                }
+            };
+            Ok(Location {
+                lat: *lat,
+                lon: *lon,
+                name,
+            })
+        }
+    }
 }
 
 fn looks_like_zip(s: &str) -> bool {
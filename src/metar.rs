@@ -0,0 +1,1084 @@
+//! METAR/TAF aviation weather (`--station`, `--metar-file`)
+//!
+//! Raw reports come from aviationweather.gov (or a file/stdin) and are
+//! decoded offline: wind, visibility, weather phenomena, sky cover and
+//! ceiling, temperature/dew point, altimeter and the flight category.
+//! Values stay in aviation units (knots, statute miles, feet, °C).
+//! TAF change groups are decoded with the same token rules.
+
+use std::fmt;
+
+use anyhow::{anyhow, bail, Result};
+use reqwest::Client;
+use serde::Serialize;
+
+use crate::report::OutputFormat;
+
+/// Meters per statute mile
+const METERS_PER_SM: f64 = 1609.344;
+
+/// hPa per inch of mercury
+const HPA_PER_INHG: f64 = 33.8639;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FlightCategory {
+    Lifr,
+    Ifr,
+    Mvfr,
+    Vfr,
+}
+
+impl FlightCategory {
+    /// FAA categories from ceiling (ft AGL) and visibility (SM); missing means unlimited
+    pub fn from_limits(ceiling_ft: Option<u32>, visibility_sm: Option<f64>) -> Self {
+        let ceiling = ceiling_ft.unwrap_or(u32::MAX);
+        let vis = visibility_sm.unwrap_or(f64::INFINITY);
+        if ceiling < 500 || vis < 1.0 {
+            FlightCategory::Lifr
+        } else if ceiling < 1000 || vis < 3.0 {
+            FlightCategory::Ifr
+        } else if ceiling <= 3000 || vis <= 5.0 {
+            FlightCategory::Mvfr
+        } else {
+            FlightCategory::Vfr
+        }
+    }
+}
+
+impl fmt::Display for FlightCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FlightCategory::Lifr => "LIFR",
+            FlightCategory::Ifr => "IFR",
+            FlightCategory::Mvfr => "MVFR",
+            FlightCategory::Vfr => "VFR",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Wind {
+    /// True direction in degrees; `None` for variable (VRB)
+    pub direction: Option<u16>,
+    pub speed_kt: u16,
+    pub gust_kt: Option<u16>,
+    /// Extremes of a variable direction, e.g. 240V300
+    pub variable_between: Option<(u16, u16)>,
+}
+
+impl fmt::Display for Wind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.speed_kt == 0 {
+            return f.write_str("calm");
+        }
+        match self.direction {
+            Some(dir) => write!(f, "{:03}° at {} kt", dir, self.speed_kt)?,
+            None => write!(f, "variable at {} kt", self.speed_kt)?,
+        }
+        if let Some(gust) = self.gust_kt {
+            write!(f, ", gusts {} kt", gust)?;
+        }
+        if let Some((from, to)) = self.variable_between {
+            write!(f, " (varying {:03}°-{:03}°)", from, to)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cover {
+    Few,
+    Scattered,
+    Broken,
+    Overcast,
+    /// Sky obscured; the height is the vertical visibility
+    VerticalVisibility,
+}
+
+impl Cover {
+    fn label(self) -> &'static str {
+        match self {
+            Cover::Few => "few",
+            Cover::Scattered => "scattered",
+            Cover::Broken => "broken",
+            Cover::Overcast => "overcast",
+            Cover::VerticalVisibility => "vertical visibility",
+        }
+    }
+
+    /// Broken, overcast and obscured layers form a ceiling
+    fn is_ceiling(self) -> bool {
+        matches!(
+            self,
+            Cover::Broken | Cover::Overcast | Cover::VerticalVisibility
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CloudLayer {
+    pub cover: Cover,
+    /// Base in feet AGL; `None` when not reported (///)
+    pub base_ft: Option<u32>,
+    /// "CB" or "TCU"
+    pub convective: Option<String>,
+}
+
+impl fmt::Display for CloudLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.cover.label())?;
+        if let Some(base) = self.base_ft {
+            write!(f, " {} ft", thousands(base))?;
+        }
+        match self.convective.as_deref() {
+            Some("CB") => f.write_str(" (cumulonimbus)"),
+            Some("TCU") => f.write_str(" (towering cumulus)"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Wind, visibility, weather and sky: the part shared by METARs and TAF groups
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Conditions {
+    pub wind: Option<Wind>,
+    /// Prevailing visibility in statute miles
+    pub visibility_sm: Option<f64>,
+    /// "P6SM" / "9999": at least this far
+    pub visibility_plus: bool,
+    /// Decoded phenomena, e.g. "light rain", "mist"
+    pub weather: Vec<String>,
+    pub clouds: Vec<CloudLayer>,
+    /// SKC/CLR/NSC/CAVOK
+    pub sky_clear: bool,
+}
+
+impl Conditions {
+    /// Lowest broken/overcast/obscured layer
+    pub fn ceiling_ft(&self) -> Option<u32> {
+        self.clouds
+            .iter()
+            .filter(|c| c.cover.is_ceiling())
+            .filter_map(|c| c.base_ft)
+            .min()
+    }
+
+    pub fn flight_category(&self) -> FlightCategory {
+        FlightCategory::from_limits(self.ceiling_ft(), self.visibility_sm)
+    }
+
+    fn visibility_text(&self) -> Option<String> {
+        self.visibility_sm.map(|v| {
+            if self.visibility_plus {
+                // "P6SM", or 9999 m which is just over 6 SM
+                format!("{}+ SM", v.floor())
+            } else {
+                format!("{} SM", (v * 100.0).round() / 100.0)
+            }
+        })
+    }
+
+    fn sky_text(&self) -> Option<String> {
+        if self.clouds.is_empty() {
+            return self.sky_clear.then(|| "clear".to_string());
+        }
+        Some(
+            self.clouds
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+
+    /// "wind 280° at 10 kt, vis 5 SM, light rain, broken 2,500 ft"
+    fn brief(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(wind) = &self.wind {
+            parts.push(format!("wind {}", wind));
+        }
+        if let Some(vis) = self.visibility_text() {
+            parts.push(format!("vis {}", vis));
+        }
+        parts.extend(self.weather.iter().cloned());
+        if let Some(sky) = self.sky_text() {
+            parts.push(sky);
+        }
+        parts.join(", ")
+    }
+
+    /// Decode `tokens[i]` (and, for split visibilities, the next one) into self
+    ///
+    /// Returns how many tokens were consumed, 0 if the token is not a condition.
+    fn decode(&mut self, tokens: &[&str], i: usize) -> usize {
+        let tok = tokens[i];
+        if let Some(wind) = parse_wind(tok) {
+            self.wind = Some(wind);
+            return 1;
+        }
+        if let Some(range) = parse_variable_direction(tok) {
+            if let Some(wind) = &mut self.wind {
+                wind.variable_between = Some(range);
+            }
+            return 1;
+        }
+        // "1 1/2SM"
+        if let (Ok(whole), Some(next)) = (tok.parse::<u8>(), tokens.get(i + 1)) {
+            if let Some((frac, _)) = next.strip_suffix("SM").and_then(parse_sm) {
+                if next.contains('/') {
+                    self.visibility_sm = Some(whole as f64 + frac);
+                    self.visibility_plus = false;
+                    return 2;
+                }
+            }
+        }
+        if let Some((sm, plus)) = tok.strip_suffix("SM").and_then(parse_sm) {
+            self.visibility_sm = Some(sm);
+            self.visibility_plus = plus;
+            return 1;
+        }
+        if tok.len() == 4 && tok.chars().all(|c| c.is_ascii_digit()) {
+            let meters: f64 = tok.parse().unwrap_or(0.0);
+            self.visibility_sm = Some(meters / METERS_PER_SM);
+            self.visibility_plus = tok == "9999";
+            return 1;
+        }
+        match tok {
+            "CAVOK" => {
+                self.visibility_sm = Some(10_000.0 / METERS_PER_SM);
+                self.visibility_plus = true;
+                self.clouds.clear();
+                self.sky_clear = true;
+                self.weather.clear();
+                return 1;
+            }
+            "SKC" | "CLR" | "NSC" | "NCD" => {
+                self.clouds.clear();
+                self.sky_clear = true;
+                return 1;
+            }
+            "NSW" => {
+                self.weather.clear();
+                return 1;
+            }
+            _ => {}
+        }
+        if let Some(layer) = parse_cloud(tok) {
+            self.clouds.push(layer);
+            self.sky_clear = false;
+            return 1;
+        }
+        if let Some(phrase) = decode_weather(tok) {
+            self.weather.push(phrase);
+            return 1;
+        }
+        0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metar {
+    pub raw: String,
+    pub station: String,
+    /// Observation time as reported, e.g. "201651Z"
+    pub time: Option<String>,
+    /// Automated station without human augmentation
+    pub auto: bool,
+    #[serde(flatten)]
+    pub conditions: Conditions,
+    pub temperature_c: Option<f64>,
+    pub dew_point_c: Option<f64>,
+    pub altimeter_hpa: Option<f64>,
+    pub altimeter_inhg: Option<f64>,
+    pub flight_category: FlightCategory,
+}
+
+/// Decode one METAR/SPECI, e.g. "KDVN 201651Z 27012G20KT 10SM FEW035 22/14 A3001"
+pub fn parse_metar(raw: &str) -> Result<Metar> {
+    let raw = raw.trim().trim_end_matches('=').trim();
+    let tokens: Vec<&str> = raw.split_whitespace().collect();
+    let mut i = 0;
+    while matches!(tokens.get(i), Some(&("METAR" | "SPECI"))) {
+        i += 1;
+    }
+
+    let station = match tokens.get(i) {
+        Some(s) if is_station(s) => s.to_string(),
+        Some(s) => bail!("not a METAR: expected an ICAO station, found '{s}'"),
+        None => bail!("empty METAR"),
+    };
+    i += 1;
+
+    let mut metar = Metar {
+        raw: raw.to_string(),
+        station,
+        time: None,
+        auto: false,
+        conditions: Conditions::default(),
+        temperature_c: None,
+        dew_point_c: None,
+        altimeter_hpa: None,
+        altimeter_inhg: None,
+        flight_category: FlightCategory::Vfr,
+    };
+
+    while i < tokens.len() {
+        let tok = tokens[i];
+        if tok == "RMK" || tok == "TEMPO" || tok == "BECMG" || tok == "NOSIG" {
+            break;
+        }
+        if is_day_time(tok) {
+            metar.time = Some(tok.to_string());
+        } else if tok == "AUTO" {
+            metar.auto = true;
+        } else if let Some((t, d)) = parse_temperatures(tok) {
+            metar.temperature_c = Some(t);
+            metar.dew_point_c = d;
+        } else if let Some((hpa, inhg)) = parse_altimeter(tok) {
+            metar.altimeter_hpa = Some(hpa);
+            metar.altimeter_inhg = Some(inhg);
+        } else {
+            let used = metar.conditions.decode(&tokens, i);
+            if used > 1 {
+                i += used - 1;
+            }
+        }
+        i += 1;
+    }
+
+    metar.flight_category = metar.conditions.flight_category();
+    Ok(metar)
+}
+
+impl Metar {
+    /// Relative humidity from temperature and dew point (Magnus formula)
+    pub fn humidity(&self) -> Option<f64> {
+        let (t, d) = (self.temperature_c?, self.dew_point_c?);
+        let es = |x: f64| (17.625 * x / (243.04 + x)).exp();
+        Some((100.0 * es(d) / es(t)).clamp(0.0, 100.0))
+    }
+
+    /// Notification title, e.g. "KDVN VFR | 270° at 12 kt, gusts 20 kt | 10 SM | 22°C"
+    pub fn summary_line(&self) -> String {
+        let c = &self.conditions;
+        let mut parts = vec![format!("{} {}", self.station, self.flight_category)];
+        if let Some(wind) = &c.wind {
+            parts.push(wind.to_string());
+        }
+        if let Some(vis) = c.visibility_text() {
+            parts.push(vis);
+        }
+        if let Some(ceiling) = c.ceiling_ft() {
+            parts.push(format!("ceiling {} ft", thousands(ceiling)));
+        }
+        if let Some(t) = self.temperature_c {
+            parts.push(format!("{:.0}°C", t));
+        }
+        parts.join(" | ")
+    }
+
+    /// Decoded multi-line report
+    pub fn render_text(&self) -> String {
+        let c = &self.conditions;
+        let mut lines = vec![format!(
+            "Station: {}{}",
+            self.station,
+            self.time
+                .as_deref()
+                .map(|t| format!(" (observed {})", format_day_time(t)))
+                .unwrap_or_default()
+        )];
+        lines.push(format!("Flight category: {}", self.flight_category));
+        if let Some(wind) = &c.wind {
+            lines.push(format!("Wind: {}", wind));
+        }
+        if let Some(vis) = c.visibility_text() {
+            lines.push(format!("Visibility: {}", vis));
+        }
+        if !c.weather.is_empty() {
+            lines.push(format!("Weather: {}", c.weather.join(", ")));
+        }
+        if let Some(sky) = c.sky_text() {
+            lines.push(format!("Sky: {}", sky));
+        }
+        if let Some(ceiling) = c.ceiling_ft() {
+            lines.push(format!("Ceiling: {} ft", thousands(ceiling)));
+        }
+        if let Some(t) = self.temperature_c {
+            let mut line = format!("Temperature: {:.0}°C", t);
+            if let Some(d) = self.dew_point_c {
+                line.push_str(&format!(", dew point {:.0}°C", d));
+            }
+            if let Some(rh) = self.humidity() {
+                line.push_str(&format!(" (humidity {:.0}%)", rh));
+            }
+            lines.push(line);
+        }
+        if let (Some(inhg), Some(hpa)) = (self.altimeter_inhg, self.altimeter_hpa) {
+            lines.push(format!("Altimeter: {:.2} inHg ({:.0} hPa)", inhg, hpa));
+        }
+        lines.push(format!("Raw: {}", self.raw));
+        lines.join("\n")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TafPeriod {
+    /// "Initial", "From 20 21:00Z", "Temporarily 2020/2024", ...
+    pub label: String,
+    #[serde(flatten)]
+    pub conditions: Conditions,
+    pub flight_category: FlightCategory,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Taf {
+    pub raw: String,
+    pub station: String,
+    pub periods: Vec<TafPeriod>,
+}
+
+/// Decode a TAF into its base forecast and change groups
+///
+/// FM groups replace the prevailing conditions; TEMPO/BECMG/PROB groups
+/// only override what they mention.
+pub fn parse_taf(raw: &str) -> Result<Taf> {
+    let raw = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    let raw = raw.trim_end_matches('=').trim().to_string();
+    let tokens: Vec<&str> = raw.split_whitespace().collect();
+    let mut i = 0;
+    while matches!(tokens.get(i), Some(&("TAF" | "AMD" | "COR"))) {
+        i += 1;
+    }
+    let station = match tokens.get(i) {
+        Some(s) if is_station(s) => s.to_string(),
+        _ => bail!("not a TAF: missing ICAO station"),
+    };
+    i += 1;
+    // Issue time and validity
+    while tokens
+        .get(i)
+        .is_some_and(|t| is_day_time(t) || is_period(t))
+    {
+        i += 1;
+    }
+
+    let mut periods = Vec::new();
+    let mut prevailing = Conditions::default();
+    let mut label = "Initial".to_string();
+    let mut current = Conditions::default();
+    let mut replaces = true;
+
+    let mut finish =
+        |label: &str, current: &Conditions, replaces: bool, prevailing: &mut Conditions| {
+            let merged = if replaces {
+                current.clone()
+            } else {
+                overlay(prevailing, current)
+            };
+            if replaces {
+                *prevailing = merged.clone();
+            }
+            periods.push(TafPeriod {
+                label: label.to_string(),
+                flight_category: merged.flight_category(),
+                conditions: merged,
+            });
+        };
+
+    while i < tokens.len() {
+        let tok = tokens[i];
+        let group = if let Some(ts) = tok
+            .strip_prefix("FM")
+            .filter(|t| t.len() == 6 && t.chars().all(|c| c.is_ascii_digit()))
+        {
+            Some((
+                format!("From {}", format_day_time(&format!("{ts}Z"))),
+                true,
+                1,
+            ))
+        } else if tok == "TEMPO" || tok == "BECMG" {
+            let word = if tok == "TEMPO" {
+                "Temporarily"
+            } else {
+                "Becoming"
+            };
+            let (period, used) = period_after(&tokens, i + 1);
+            Some((format!("{word}{period}"), false, 1 + used))
+        } else if let Some(pct) = tok.strip_prefix("PROB") {
+            let mut used = 1;
+            let mut word = format!("{pct}% chance");
+            if tokens.get(i + 1) == Some(&"TEMPO") {
+                word.push_str(" temporarily");
+                used += 1;
+            }
+            let (period, n) = period_after(&tokens, i + used);
+            Some((format!("{word}{period}"), false, used + n))
+        } else {
+            None
+        };
+
+        match group {
+            Some((next_label, next_replaces, used)) => {
+                finish(&label, &current, replaces, &mut prevailing);
+                label = next_label;
+                replaces = next_replaces;
+                current = Conditions::default();
+                i += used;
+            }
+            None => {
+                let used = current.decode(&tokens, i);
+                i += used.max(1);
+            }
+        }
+    }
+    finish(&label, &current, replaces, &mut prevailing);
+
+    Ok(Taf {
+        raw,
+        station,
+        periods,
+    })
+}
+
+impl Taf {
+    pub fn render_text(&self) -> String {
+        let mut lines = vec![format!("TAF {}:", self.station)];
+        for p in &self.periods {
+            lines.push(format!(
+                "  {}: {}, {}",
+                p.label,
+                p.flight_category,
+                p.conditions.brief()
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Values from `group` over `base`
+fn overlay(base: &Conditions, group: &Conditions) -> Conditions {
+    let mut merged = base.clone();
+    if group.wind.is_some() {
+        merged.wind = group.wind.clone();
+    }
+    if group.visibility_sm.is_some() {
+        merged.visibility_sm = group.visibility_sm;
+        merged.visibility_plus = group.visibility_plus;
+    }
+    if !group.weather.is_empty() {
+        merged.weather = group.weather.clone();
+    }
+    if !group.clouds.is_empty() || group.sky_clear {
+        merged.clouds = group.clouds.clone();
+        merged.sky_clear = group.sky_clear;
+    }
+    merged
+}
+
+/// " 2020/2024" for a validity period at `tokens[i]`, and tokens used
+fn period_after(tokens: &[&str], i: usize) -> (String, usize) {
+    match tokens.get(i) {
+        Some(t) if is_period(t) => (format!(" {t}"), 1),
+        _ => (String::new(), 0),
+    }
+}
+
+/// A complete report for the notification pipeline
+#[derive(Debug, Clone, Serialize)]
+pub struct StationReport {
+    pub metar: Metar,
+    pub taf: Option<Taf>,
+}
+
+impl StationReport {
+    /// IFR and LIFR conditions are worth a louder notification
+    pub fn is_low_ifr(&self) -> bool {
+        self.metar.flight_category <= FlightCategory::Ifr
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut text = self.metar.render_text();
+        if let Some(taf) = &self.taf {
+            text.push_str("\n\n");
+            text.push_str(&taf.render_text());
+        }
+        text
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "**{}** — **{}**",
+                self.metar.station, self.metar.flight_category
+            ),
+            String::new(),
+        ];
+        for line in self.metar.render_text().lines().skip(2) {
+            lines.push(format!("- {}", line));
+        }
+        if let Some(taf) = &self.taf {
+            lines.push(String::new());
+            lines.push("**TAF**".to_string());
+            for p in &taf.periods {
+                lines.push(format!(
+                    "- {}: **{}**, {}",
+                    p.label,
+                    p.flight_category,
+                    p.conditions.brief()
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Render for stdout in the requested format
+    pub fn render(&self, format: OutputFormat) -> Result<String> {
+        Ok(match format {
+            OutputFormat::Text => self.render_text(),
+            OutputFormat::Json => serde_json::to_string(self)?,
+            OutputFormat::Markdown => self.render_markdown(),
+            OutputFormat::Compact => self.metar.summary_line(),
+            // Stations have no daily forecast to narrate
+            OutputFormat::Narrative => self.render_text(),
+        })
+    }
+}
+
+/// Fetch the latest raw METAR and TAF from aviationweather.gov
+///
+/// A missing TAF is not an error: many small fields only report METARs.
+pub async fn fetch_station(client: &Client, station: &str) -> Result<(String, Option<String>)> {
+    let station = station.trim().to_uppercase();
+    if !is_station(&station) {
+        bail!("invalid ICAO station '{station}' (expected 4 letters/digits, e.g. KDVN)");
+    }
+    let metar = fetch_raw(client, "metar", &station).await?;
+    let metar = metar
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("no METAR available for {station}"))?;
+    let taf = fetch_raw(client, "taf", &station)
+        .await
+        .ok()
+        .filter(|t| !t.trim().is_empty());
+    Ok((metar, taf))
+}
+
+async fn fetch_raw(client: &Client, product: &str, station: &str) -> Result<String> {
+    let url = format!("https://aviationweather.gov/api/data/{product}?ids={station}&format=raw");
+    let resp = client.get(&url).send().await?.error_for_status()?;
+    Ok(resp.text().await?)
+}
+
+fn is_station(s: &str) -> bool {
+    s.len() == 4
+        && s.chars().all(|c| c.is_ascii_alphanumeric())
+        && s.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+/// "201651Z"
+fn is_day_time(s: &str) -> bool {
+    s.len() == 7 && s.ends_with('Z') && s[..6].chars().all(|c| c.is_ascii_digit())
+}
+
+/// "2018/2124"
+fn is_period(s: &str) -> bool {
+    s.len() == 9 && s.as_bytes()[4] == b'/' && s.chars().filter(|c| c.is_ascii_digit()).count() == 8
+}
+
+/// "201651Z" -> "20 16:51Z"
+fn format_day_time(s: &str) -> String {
+    if s.len() >= 6 && s.is_ascii() {
+        format!("{} {}:{}Z", &s[..2], &s[2..4], &s[4..6])
+    } else {
+        s.to_string()
+    }
+}
+
+/// 25000 -> "25,000"
+fn thousands(n: u32) -> String {
+    if n >= 1000 {
+        format!("{},{:03}", n / 1000, n % 1000)
+    } else {
+        n.to_string()
+    }
+}
+
+/// "27012G20KT", "VRB03KT", "00000KT", "24008MPS"
+fn parse_wind(tok: &str) -> Option<Wind> {
+    let (body, to_kt) = if let Some(b) = tok.strip_suffix("KT") {
+        (b, 1.0)
+    } else if let Some(b) = tok.strip_suffix("MPS") {
+        (b, 1.943_844)
+    } else {
+        return None;
+    };
+    if body.len() < 5 || !body.is_ascii() {
+        return None;
+    }
+    let (dir, rest) = body.split_at(3);
+    let direction = match dir {
+        "VRB" => None,
+        d => Some(d.parse::<u16>().ok()?),
+    };
+    let (speed, gust) = match rest.split_once('G') {
+        Some((s, g)) => (s, Some(g)),
+        None => (rest, None),
+    };
+    let knots = |v: &str| -> Option<u16> { Some((v.parse::<f64>().ok()? * to_kt).round() as u16) };
+    Some(Wind {
+        direction,
+        speed_kt: knots(speed)?,
+        gust_kt: match gust {
+            Some(g) => Some(knots(g)?),
+            None => None,
+        },
+        variable_between: None,
+    })
+}
+
+/// "240V300"
+fn parse_variable_direction(tok: &str) -> Option<(u16, u16)> {
+    let (a, b) = tok.split_once('V')?;
+    if a.len() != 3 || b.len() != 3 {
+        return None;
+    }
+    Some((a.parse().ok()?, b.parse().ok()?))
+}
+
+/// Statute miles without the "SM" suffix: "10", "1/2", "M1/4", "P6"; bool = "at least"
+fn parse_sm(s: &str) -> Option<(f64, bool)> {
+    let (s, plus) = match s.strip_prefix('P') {
+        Some(rest) => (rest, true),
+        None => (s.strip_prefix('M').unwrap_or(s), false),
+    };
+    let value = match s.split_once('/') {
+        Some((n, d)) => n.parse::<f64>().ok()? / d.parse::<f64>().ok()?,
+        None => s.parse().ok()?,
+    };
+    Some((value, plus))
+}
+
+/// "BKN025", "OVC008CB", "VV002", "FEW///"
+fn parse_cloud(tok: &str) -> Option<CloudLayer> {
+    let (cover, rest) = [
+        ("FEW", Cover::Few),
+        ("SCT", Cover::Scattered),
+        ("BKN", Cover::Broken),
+        ("OVC", Cover::Overcast),
+        ("VV", Cover::VerticalVisibility),
+    ]
+    .into_iter()
+    .find_map(|(p, c)| tok.strip_prefix(p).map(|rest| (c, rest)))?;
+    if rest.len() < 3 || !rest.is_ascii() {
+        return None;
+    }
+    let (height, convective) = rest.split_at(3);
+    let base_ft = match height {
+        "///" => None,
+        h => Some(h.parse::<u32>().ok()? * 100),
+    };
+    let convective = match convective {
+        "" | "///" => None,
+        "CB" | "TCU" => Some(convective.to_string()),
+        _ => return None,
+    };
+    Some(CloudLayer {
+        cover,
+        base_ft,
+        convective,
+    })
+}
+
+/// "M05/M10" -> (-5, Some(-10)); dew point may be missing ("22/")
+fn parse_temperatures(tok: &str) -> Option<(f64, Option<f64>)> {
+    let (t, d) = tok.split_once('/')?;
+    let value = |s: &str| -> Option<f64> {
+        let (neg, digits) = match s.strip_prefix('M') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        if digits.len() != 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let v: f64 = digits.parse().ok()?;
+        Some(if neg { -v } else { v })
+    };
+    let temp = value(t)?;
+    let dew = if d.is_empty() { None } else { Some(value(d)?) };
+    Some((temp, dew))
+}
+
+/// "A2992" (inHg) or "Q1013" (hPa) -> (hPa, inHg)
+fn parse_altimeter(tok: &str) -> Option<(f64, f64)> {
+    if tok.len() != 5 || !tok.is_ascii() {
+        return None;
+    }
+    let value: f64 = tok[1..].parse().ok()?;
+    match &tok[..1] {
+        "A" => Some((value / 100.0 * HPA_PER_INHG, value / 100.0)),
+        "Q" => Some((value, value / HPA_PER_INHG)),
+        _ => None,
+    }
+}
+
+/// Present weather group -> words, e.g. "-TSRA" -> "light thunderstorm with rain"
+fn decode_weather(tok: &str) -> Option<String> {
+    let (intensity, mut rest) = if let Some(r) = tok.strip_prefix('-') {
+        (Some("light"), r)
+    } else if let Some(r) = tok.strip_prefix('+') {
+        (Some("heavy"), r)
+    } else {
+        (None, tok)
+    };
+    let vicinity = if let Some(r) = rest.strip_prefix("VC") {
+        rest = r;
+        true
+    } else {
+        false
+    };
+    if rest.is_empty() || rest.len() % 2 != 0 || !rest.is_ascii() {
+        return None;
+    }
+
+    let mut descriptor = None;
+    let mut phenomena = Vec::new();
+    for code in (0..rest.len()).step_by(2).map(|i| &rest[i..i + 2]) {
+        let word = match code {
+            "MI" => Err("shallow"),
+            "PR" => Err("partial"),
+            "BC" => Err("patches of"),
+            "DR" => Err("low drifting"),
+            "BL" => Err("blowing"),
+            "SH" => Err("showers"),
+            "TS" => Err("thunderstorm"),
+            "FZ" => Err("freezing"),
+            "DZ" => Ok("drizzle"),
+            "RA" => Ok("rain"),
+            "SN" => Ok("snow"),
+            "SG" => Ok("snow grains"),
+            "IC" => Ok("ice crystals"),
+            "PL" => Ok("ice pellets"),
+            "GR" => Ok("hail"),
+            "GS" => Ok("small hail"),
+            "UP" => Ok("unknown precipitation"),
+            "BR" => Ok("mist"),
+            "FG" => Ok("fog"),
+            "FU" => Ok("smoke"),
+            "VA" => Ok("volcanic ash"),
+            "DU" => Ok("dust"),
+            "SA" => Ok("sand"),
+            "HZ" => Ok("haze"),
+            "PY" => Ok("spray"),
+            "PO" => Ok("dust whirls"),
+            "SQ" => Ok("squalls"),
+            "FC" if intensity == Some("heavy") => Ok("tornado"),
+            "FC" => Ok("funnel cloud"),
+            "SS" => Ok("sandstorm"),
+            "DS" => Ok("duststorm"),
+            _ => return None,
+        };
+        match word {
+            // Descriptors only come first
+            Err(d) if descriptor.is_none() && phenomena.is_empty() => descriptor = Some(d),
+            Err(_) => return None,
+            Ok(p) => phenomena.push(p),
+        }
+    }
+
+    let what = phenomena.join(" and ");
+    let mut phrase = match (descriptor, what.is_empty()) {
+        (Some("thunderstorm"), true) => "thunderstorm".to_string(),
+        (Some("thunderstorm"), false) => format!("thunderstorm with {}", what),
+        (Some("showers"), true) => "showers".to_string(),
+        (Some("showers"), false) => format!("{} showers", what),
+        (Some(d), false) => format!("{} {}", d, what),
+        (Some(_), true) => return None,
+        (None, _) => what,
+    };
+    if let Some(i) = intensity.filter(|_| phrase != "tornado") {
+        phrase = format!("{} {}", i, phrase);
+    }
+    if vicinity {
+        phrase.push_str(" in the vicinity");
+    }
+    Some(phrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KDVN_VFR: &str =
+        "METAR KDVN 201651Z 27012G20KT 240V300 10SM FEW035 SCT250 22/14 A3001 RMK AO2 SLP161";
+    const KORD_IFR: &str = "KORD 201651Z AUTO 09008KT 2 1/2SM -TSRA BR BKN008 OVC015CB 18/17 A2992";
+    const EGLL_CAVOK: &str = "EGLL 201650Z VRB03KT CAVOK M02/M05 Q1013";
+    const KSFO_LIFR: &str = "SPECI KSFO 201656Z 00000KT M1/4SM FG VV002 12/12 A3002=";
+
+    #[test]
+    fn test_decode_vfr_metar() {
+        let m = parse_metar(KDVN_VFR).unwrap();
+        assert_eq!(m.station, "KDVN");
+        assert_eq!(m.time.as_deref(), Some("201651Z"));
+        let wind = m.conditions.wind.as_ref().unwrap();
+        assert_eq!(wind.direction, Some(270));
+        assert_eq!(wind.speed_kt, 12);
+        assert_eq!(wind.gust_kt, Some(20));
+        assert_eq!(wind.variable_between, Some((240, 300)));
+        assert_eq!(m.conditions.visibility_sm, Some(10.0));
+        assert_eq!(m.conditions.clouds.len(), 2);
+        assert_eq!(m.conditions.ceiling_ft(), None);
+        assert_eq!(m.temperature_c, Some(22.0));
+        assert_eq!(m.dew_point_c, Some(14.0));
+        assert_eq!(m.altimeter_inhg, Some(30.01));
+        assert_eq!(m.flight_category, FlightCategory::Vfr);
+        assert_eq!(
+            m.summary_line(),
+            "KDVN VFR | 270° at 12 kt, gusts 20 kt (varying 240°-300°) | 10 SM | 22°C"
+        );
+    }
+
+    #[test]
+    fn test_decode_ifr_metar_with_weather() {
+        let m = parse_metar(KORD_IFR).unwrap();
+        assert!(m.auto);
+        assert_eq!(m.conditions.visibility_sm, Some(2.5));
+        assert_eq!(
+            m.conditions.weather,
+            vec!["light thunderstorm with rain", "mist"]
+        );
+        assert_eq!(m.conditions.ceiling_ft(), Some(800));
+        assert_eq!(m.conditions.clouds[1].convective.as_deref(), Some("CB"));
+        assert_eq!(m.flight_category, FlightCategory::Ifr);
+        let text = m.render_text();
+        assert!(text.contains("Sky: broken 800 ft, overcast 1,500 ft (cumulonimbus)"));
+        assert!(text.contains("Visibility: 2.5 SM"));
+        assert!(text.contains("Ceiling: 800 ft"));
+        assert!(text.contains("Temperature: 18°C, dew point 17°C (humidity 94%)"));
+        assert!(text.contains("Altimeter: 29.92 inHg (1013 hPa)"));
+    }
+
+    #[test]
+    fn test_decode_metric_and_obscured_reports() {
+        let m = parse_metar(EGLL_CAVOK).unwrap();
+        assert_eq!(m.conditions.wind.as_ref().unwrap().direction, None);
+        assert!(m.conditions.sky_clear);
+        assert!(m.conditions.visibility_plus);
+        assert_eq!(m.temperature_c, Some(-2.0));
+        assert_eq!(m.dew_point_c, Some(-5.0));
+        assert_eq!(m.altimeter_hpa, Some(1013.0));
+        assert_eq!(m.flight_category, FlightCategory::Vfr);
+        assert_eq!(m.summary_line(), "EGLL VFR | variable at 3 kt | 6+ SM | -2°C");
+
+        let m = parse_metar(KSFO_LIFR).unwrap();
+        assert_eq!(m.conditions.wind.as_ref().unwrap().to_string(), "calm");
+        assert_eq!(m.conditions.visibility_sm, Some(0.25));
+        assert_eq!(m.conditions.weather, vec!["fog"]);
+        assert_eq!(m.conditions.ceiling_ft(), Some(200));
+        assert_eq!(m.flight_category, FlightCategory::Lifr);
+
+        let m = parse_metar("LFPG 201700Z 24008MPS 4000 -SHRA BKN030 15/10 Q1008").unwrap();
+        assert_eq!(m.conditions.wind.as_ref().unwrap().speed_kt, 16);
+        assert_eq!(m.conditions.weather, vec!["light rain showers"]);
+        // 4000 m is ~2.5 SM
+        assert_eq!(m.flight_category, FlightCategory::Ifr);
+
+        assert!(parse_metar("").is_err());
+        assert!(parse_metar("hello world").is_err());
+    }
+
+    #[test]
+    fn test_flight_category_limits() {
+        use FlightCategory::*;
+        assert_eq!(FlightCategory::from_limits(None, None), Vfr);
+        assert_eq!(FlightCategory::from_limits(Some(3000), Some(10.0)), Mvfr);
+        assert_eq!(FlightCategory::from_limits(Some(3100), Some(5.0)), Mvfr);
+        assert_eq!(FlightCategory::from_limits(Some(900), Some(10.0)), Ifr);
+        assert_eq!(FlightCategory::from_limits(None, Some(0.5)), Lifr);
+        assert_eq!(FlightCategory::from_limits(Some(400), Some(3.0)), Lifr);
+    }
+
+    #[test]
+    fn test_weather_phrases() {
+        assert_eq!(decode_weather("+FC").as_deref(), Some("tornado"));
+        assert_eq!(decode_weather("FZDZ").as_deref(), Some("freezing drizzle"));
+        assert_eq!(
+            decode_weather("VCSH").as_deref(),
+            Some("showers in the vicinity")
+        );
+        assert_eq!(
+            decode_weather("+SNPL").as_deref(),
+            Some("heavy snow and ice pellets")
+        );
+        assert_eq!(decode_weather("RMK"), None);
+        assert_eq!(decode_weather("A3001"), None);
+    }
+
+    #[test]
+    fn test_non_ascii_tokens() {
+        assert!(parse_wind("1€KT").is_none());
+        assert!(parse_cloud("FEWa€").is_none());
+        assert!(parse_altimeter("€AB").is_none());
+        assert!(decode_weather("-€RA").is_none());
+        assert_eq!(format_day_time("2€€Z"), "2€€Z");
+        // Undecodable tokens are skipped rather than panicking
+        let metar = parse_metar("KORD 201651Z 1€KT 10SM FEWa€ 22/12 A2992").unwrap();
+        assert!(metar.conditions.wind.is_none());
+        let taf = parse_taf("TAF KDVN 201720Z 2018/2118 27012KT P6SM FM€€ SCT040").unwrap();
+        assert_eq!(taf.periods.len(), 1);
+    }
+
+    #[test]
+    fn test_decode_taf_groups() {
+        let raw = "TAF KDVN 201720Z 2018/2118 27012KT P6SM SCT040
+              TEMPO 2020/2022 3SM TSRA BKN025CB
+              FM210200 31008KT 5SM BR OVC012
+              PROB30 2106/2109 1/2SM FG VV003
+              FM211200 VRB03KT P6SM SKC";
+        let taf = parse_taf(raw).unwrap();
+        assert_eq!(taf.station, "KDVN");
+        let labels: Vec<_> = taf.periods.iter().map(|p| p.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "Initial",
+                "Temporarily 2020/2022",
+                "From 21 02:00Z",
+                "30% chance 2106/2109",
+                "From 21 12:00Z"
+            ]
+        );
+        let cats: Vec<_> = taf.periods.iter().map(|p| p.flight_category).collect();
+        use FlightCategory::*;
+        assert_eq!(cats, vec![Vfr, Mvfr, Mvfr, Lifr, Vfr]);
+        // TEMPO keeps the prevailing wind it does not mention
+        assert_eq!(
+            taf.periods[1].conditions.wind.as_ref().unwrap().speed_kt,
+            12
+        );
+        assert!(taf.render_text().contains(
+            "  From 21 02:00Z: MVFR, wind 310° at 8 kt, vis 5 SM, mist, overcast 1,200 ft"
+        ));
+    }
+
+    #[test]
+    fn test_station_report_formats() {
+        let report = StationReport {
+            metar: parse_metar(KORD_IFR).unwrap(),
+            taf: None,
+        };
+        assert!(report.is_low_ifr());
+        let md = report.render(OutputFormat::Markdown).unwrap();
+        assert!(md.starts_with("**KORD** — **IFR**"));
+        assert!(md.contains("- Ceiling: 800 ft"));
+        let json = report.render(OutputFormat::Json).unwrap();
+        let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(v["metar"]["flight_category"], "IFR");
+        assert_eq!(v["metar"]["visibility_sm"], 2.5);
+    }
+}
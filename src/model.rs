@@ -0,0 +1,235 @@
+//! Provider-neutral forecast model
+//!
+//! Every `WeatherProvider` converts its own API response into these types so
+//! report building, alerts and notifications never depend on a specific API.
+//!
+//! Temperatures and wind speeds are in the requested unit system
+//! (°F + mph for imperial, °C + m/s for metric, K + m/s for standard);
+//! precipitation is always mm and pressure always hPa.
+
+use chrono::FixedOffset;
+use serde::{Deserialize, Serialize};
+
+/// Temperature suffix for a unit system ("imperial", "metric", "standard")
+pub fn temp_unit(units: &str) -> &'static str {
+    match units {
+        "metric" => "°C",
+        "standard" => "K",
+        _ => "°F",
+    }
+}
+
+/// Wind speed unit for a unit system
+pub fn wind_unit(units: &str) -> &'static str {
+    if units == "imperial" {
+        "mph"
+    } else {
+        "m/s"
+    }
+}
+
+/// Temperature in a unit system converted to °C
+pub fn to_celsius(t: f64, units: &str) -> f64 {
+    match units {
+        "imperial" => (t - 32.0) * 5.0 / 9.0,
+        "standard" => t - 273.15,
+        _ => t,
+    }
+}
+
+/// °C converted to a unit system
+pub fn from_celsius(t: f64, units: &str) -> f64 {
+    match units {
+        "imperial" => t * 9.0 / 5.0 + 32.0,
+        "standard" => t + 273.15,
+        _ => t,
+    }
+}
+
+/// 16-point compass direction for a bearing in degrees ("N", "SSW", ...)
+pub fn compass(deg: f64) -> &'static str {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+    let i = (deg.rem_euclid(360.0) / 22.5).round() as usize % 16;
+    POINTS[i]
+}
+
+/// A resolved location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub lat: f64,
+    pub lon: f64,
+    /// Display name, e.g. "Davenport, IA, US"
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Forecast {
+    /// IANA timezone name of the location
+    pub timezone: String,
+    /// Offset from UTC in seconds
+    pub timezone_offset: i32,
+    pub current: CurrentConditions,
+    /// Short-range precipitation, oldest first (empty if the provider has none)
+    pub minutely: Vec<MinutelyPrecipitation>,
+    pub hourly: Vec<HourlyForecast>,
+    /// daily[0] is today
+    pub daily: Vec<DailyForecast>,
+    pub alerts: Vec<WeatherAlert>,
+    /// Current air quality, when the provider has it
+    pub air_quality: Option<AirQuality>,
+    /// Estimated PV production, when a solar array is configured
+    pub solar: Option<SolarOutlook>,
+}
+
+impl Forecast {
+    /// Fixed offset for converting unix timestamps to local time
+    pub fn offset(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.timezone_offset).expect("invalid timezone offset from provider")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CurrentConditions {
+    pub dt: i64, // unix seconds
+    pub temp: f64,
+    pub feels_like: f64,
+    pub humidity: u8,
+    pub dew_point: Option<f64>,
+    /// Sea-level pressure in hPa
+    pub pressure: Option<f64>,
+    /// Cloud cover, percent
+    pub clouds: Option<u8>,
+    pub wind_speed: f64,
+    /// Direction the wind blows from, degrees
+    pub wind_deg: Option<f64>,
+    pub wind_gust: Option<f64>,
+    pub sunrise: Option<i64>, // unix seconds
+    pub sunset: Option<i64>,  // unix seconds
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MinutelyPrecipitation {
+    pub dt: i64, // unix seconds, start of the interval
+    /// Precipitation intensity in mm/h
+    pub intensity: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HourlyForecast {
+    pub dt: i64, // unix seconds, start of the hour
+    pub temp: f64,
+    pub humidity: u8,
+    /// Probability of precipitation, 0.0-1.0
+    pub pop: f64,
+    /// Rain + snow in mm for the hour
+    pub precipitation: f64,
+    pub description: String,
+    pub condition: Condition,
+}
+
+/// Broad weather condition from the provider's condition code, for logic
+/// that must not depend on the (possibly translated) description
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    Drizzle,
+    Rain,
+    Snow,
+    Thunderstorm,
+    /// No code, or one the provider mapping does not know
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DailyForecast {
+    pub dt: i64, // unix seconds
+    pub temp_min: f64,
+    pub temp_max: f64,
+    /// Probability of precipitation, 0.0-1.0
+    pub pop: f64,
+    /// Rain total in mm
+    pub rain: f64,
+    /// Snow total in mm, liquid equivalent
+    pub snow: f64,
+    /// Fresh snowfall depth in mm
+    pub snow_depth: f64,
+    pub wind_speed: f64,
+    /// Dominant direction the wind blows from, degrees
+    pub wind_deg: Option<f64>,
+    pub wind_gust: Option<f64>,
+    /// Maximum UV index
+    pub uvi: Option<f64>,
+    /// hPa
+    pub pressure: Option<f64>,
+    pub dew_point: Option<f64>,
+    /// Cloud cover, percent
+    pub clouds: Option<u8>,
+    pub sunrise: Option<i64>, // unix seconds
+    pub sunset: Option<i64>,  // unix seconds
+    pub description: String,
+    pub condition: Condition,
+}
+
+/// Air pollution at the location; concentrations in µg/m³
+#[derive(Debug, Clone, Serialize)]
+pub struct AirQuality {
+    /// 1 (Good) to 5 (Very Poor), the band scale OWM uses
+    pub aqi: u8,
+    pub pm2_5: f64,
+    pub pm10: f64,
+    pub o3: f64,
+}
+
+impl AirQuality {
+    pub fn label(&self) -> &'static str {
+        match self.aqi {
+            0 | 1 => "Good",
+            2 => "Fair",
+            3 => "Moderate",
+            4 => "Poor",
+            _ => "Very Poor",
+        }
+    }
+}
+
+/// Expected production of the configured PV array
+#[derive(Debug, Clone, Serialize)]
+pub struct SolarOutlook {
+    /// Array size in kWp
+    pub kwp: f64,
+    /// Panel tilt from horizontal, degrees
+    pub tilt: f64,
+    /// Direction the panels face, degrees (180 = south)
+    pub azimuth: f64,
+    /// One entry per forecast day, today first
+    pub days: Vec<SolarDay>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SolarDay {
+    pub dt: i64, // unix seconds, same as the matching DailyForecast
+    /// Expected energy for the day's cloud cover, kWh
+    pub kwh: f64,
+    /// Energy under a cloudless sky, kWh
+    pub clear_sky_kwh: f64,
+    /// Cloud cover used for the estimate, percent
+    pub clouds: Option<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeatherAlert {
+    pub sender: String,
+    pub event: String,
+    pub start: i64, // unix seconds
+    pub end: i64,   // unix seconds
+    pub description: String,
+}
@@ -0,0 +1,324 @@
+//! Rule-based forecast narrative (`--format narrative`)
+//!
+//! Days are grouped into spells of the same temperature class and
+//! precipitation, and each spell becomes a short phrase:
+//!
+//! ```text
+//! Warm and dry through Wednesday, cooling sharply Thursday through Friday with rain
+//! likely, cooling sharply and drying out Saturday; frost possible Saturday night.
+//! Warmest Tuesday (84°F), coldest Saturday night (28°F).
+//! ```
+
+use chrono::TimeZone;
+
+use crate::changes::PrecipCategory;
+use crate::model::{temp_unit, to_celsius, DailyForecast, Forecast};
+
+/// Days covered (today + 7), matching the markdown table
+const DAYS: usize = 8;
+
+/// Day-to-day change in the high (°C) that counts as sharp
+const SHARP_SWING_C: f64 = 8.0;
+
+/// Lows at or below this (°C) mean frost is possible
+const FROST_C: f64 = 0.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum TempClass {
+    Cold,
+    Cool,
+    Mild,
+    Warm,
+    Hot,
+}
+
+impl TempClass {
+    /// Class of a daily high in °C
+    fn of(high_c: f64) -> Self {
+        match high_c {
+            h if h >= 30.0 => TempClass::Hot,
+            h if h >= 22.0 => TempClass::Warm,
+            h if h >= 14.0 => TempClass::Mild,
+            h if h >= 5.0 => TempClass::Cool,
+            _ => TempClass::Cold,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TempClass::Cold => "cold",
+            TempClass::Cool => "cool",
+            TempClass::Mild => "mild",
+            TempClass::Warm => "warm",
+            TempClass::Hot => "hot",
+        }
+    }
+}
+
+fn wet_label(category: PrecipCategory) -> Option<&'static str> {
+    match category {
+        PrecipCategory::Dry => None,
+        PrecipCategory::Rain => Some("rain"),
+        PrecipCategory::Snow => Some("snow"),
+        PrecipCategory::Storms => Some("thunderstorms"),
+    }
+}
+
+/// Consecutive days with the same class and precipitation
+struct Spell {
+    class: TempClass,
+    category: PrecipCategory,
+    first: usize,
+    last: usize,
+}
+
+/// Short weekly narrative for `data` in the report's `units`
+pub fn narrative(data: &Forecast, units: &str) -> String {
+    let offset = data.offset();
+    let days: Vec<&DailyForecast> = data.daily.iter().take(DAYS).collect();
+    if days.is_empty() {
+        return "No daily forecast available.".to_string();
+    }
+
+    let names: Vec<String> = days
+        .iter()
+        .enumerate()
+        .map(|(i, d)| match (i, offset.timestamp_opt(d.dt, 0).single()) {
+            (0, _) => "today".to_string(),
+            (_, Some(t)) => t.format("%A").to_string(),
+            (_, None) => format!("day {}", i + 1),
+        })
+        .collect();
+    let high_c: Vec<f64> = days.iter().map(|d| to_celsius(d.temp_max, units)).collect();
+
+    // Group into spells
+    let mut spells: Vec<Spell> = Vec::new();
+    for (i, day) in days.iter().enumerate() {
+        let class = TempClass::of(high_c[i]);
+        let category = PrecipCategory::of(day);
+        match spells.last_mut() {
+            Some(s) if s.class == class && s.category == category => s.last = i,
+            _ => spells.push(Spell {
+                class,
+                category,
+                first: i,
+                last: i,
+            }),
+        }
+    }
+
+    let span = |s: &Spell| {
+        if s.first == s.last {
+            names[s.first].clone()
+        } else {
+            format!("{} through {}", names[s.first], names[s.last])
+        }
+    };
+
+    let mut phrases = Vec::new();
+    for (n, spell) in spells.iter().enumerate() {
+        let wet = wet_label(spell.category);
+        if n == 0 {
+            let weather = match wet {
+                Some(w) => format!("with {} likely", w),
+                None => "and dry".to_string(),
+            };
+            let when = if spell.first == spell.last {
+                "today".to_string()
+            } else {
+                format!("through {}", names[spell.last])
+            };
+            phrases.push(format!(
+                "{} {} {}",
+                capitalize(spell.class.label()),
+                weather,
+                when
+            ));
+            continue;
+        }
+
+        let prev = &spells[n - 1];
+        let mut verbs = Vec::new();
+        if spell.class != prev.class {
+            let swing = high_c[spell.first] - high_c[prev.last];
+            let trend = if spell.class < prev.class {
+                "cooling"
+            } else {
+                "warming"
+            };
+            if swing.abs() >= SHARP_SWING_C {
+                verbs.push(format!("{} sharply", trend));
+            } else {
+                verbs.push(trend.to_string());
+            }
+        }
+        if wet.is_none() && wet_label(prev.category).is_some() {
+            verbs.push("drying out".to_string());
+        }
+
+        phrases.push(match (verbs.is_empty(), wet) {
+            (false, Some(w)) => {
+                format!("{} {} with {} likely", verbs.join(" and "), span(spell), w)
+            }
+            (false, None) => format!("{} {}", verbs.join(" and "), span(spell)),
+            (true, Some(w)) => format!("{} likely {}", w, span(spell)),
+            // Spells always differ in class or precipitation
+            (true, None) => format!("dry {}", span(spell)),
+        });
+    }
+
+    let frost: Vec<String> = days
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| to_celsius(d.temp_min, units) <= FROST_C)
+        .map(|(i, _)| night(&names, i))
+        .collect();
+
+    let mut text = phrases.join(", ");
+    if !frost.is_empty() {
+        text.push_str(&format!("; frost possible {}", join_and(&frost)));
+    }
+    text.push('.');
+
+    // Extremes
+    if days.len() > 1 {
+        let deg = temp_unit(units);
+        let warmest = (0..days.len())
+            .reduce(|a, b| {
+                if days[b].temp_max > days[a].temp_max {
+                    b
+                } else {
+                    a
+                }
+            })
+            .unwrap_or(0);
+        let coldest = (0..days.len())
+            .reduce(|a, b| {
+                if days[b].temp_min < days[a].temp_min {
+                    b
+                } else {
+                    a
+                }
+            })
+            .unwrap_or(0);
+        text.push_str(&format!(
+            " Warmest {} ({:.0}{deg}), coldest {} ({:.0}{deg}).",
+            names[warmest],
+            days[warmest].temp_max,
+            night(&names, coldest),
+            days[coldest].temp_min,
+        ));
+    }
+
+    text
+}
+
+/// "tonight" or "Saturday night"
+fn night(names: &[String], i: usize) -> String {
+    if i == 0 {
+        "tonight".to_string()
+    } else {
+        format!("{} night", names[i])
+    }
+}
+
+/// "light rain" -> "Light rain"
+pub fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// "a", "a and b", "a, b and c"
+fn join_and(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Condition, CurrentConditions};
+
+    // 2024-06-17 00:00 UTC (Monday)
+    const MONDAY: i64 = 1_718_582_400;
+
+    fn forecast(days: &[(f64, f64, f64, Condition)]) -> Forecast {
+        Forecast {
+            timezone: "UTC".to_string(),
+            timezone_offset: 0,
+            current: CurrentConditions::default(),
+            minutely: Vec::new(),
+            hourly: Vec::new(),
+            daily: days
+                .iter()
+                .enumerate()
+                .map(|(i, &(high, low, pop, condition))| DailyForecast {
+                    dt: MONDAY + i as i64 * 86_400 + 12 * 3600,
+                    temp_max: high,
+                    temp_min: low,
+                    pop,
+                    condition,
+                    ..Default::default()
+                })
+                .collect(),
+            alerts: Vec::new(),
+            air_quality: None,
+            solar: None,
+        }
+    }
+
+    #[test]
+    fn test_trends_wet_spell_and_frost() {
+        let f = forecast(&[
+            (80.0, 60.0, 0.0, Condition::Clear),
+            (84.0, 62.0, 0.0, Condition::Clear),
+            (81.0, 61.0, 0.1, Condition::PartlyCloudy),
+            (62.0, 50.0, 0.8, Condition::Rain),
+            (60.0, 40.0, 0.7, Condition::Rain),
+            (45.0, 28.0, 0.1, Condition::Clear),
+        ]);
+        assert_eq!(
+            narrative(&f, "imperial"),
+            "Warm and dry through Wednesday, cooling sharply Thursday through Friday with rain \
+             likely, cooling sharply and drying out Saturday; frost possible Saturday night. \
+             Warmest Tuesday (84°F), coldest Saturday night (28°F)."
+        );
+    }
+
+    #[test]
+    fn test_metric_storms_and_warming() {
+        let f = forecast(&[
+            (18.0, 9.0, 0.6, Condition::Rain),
+            (24.0, 14.0, 0.2, Condition::Clear),
+            (31.0, 20.0, 0.7, Condition::Thunderstorm),
+        ]);
+        assert_eq!(
+            narrative(&f, "metric"),
+            "Mild with rain likely today, warming and drying out Tuesday, warming Wednesday \
+             with thunderstorms likely. Warmest Wednesday (31°C), coldest tonight (9°C)."
+        );
+    }
+
+    #[test]
+    fn test_same_class_precip_change_and_empty() {
+        let f = forecast(&[
+            (70.0, 55.0, 0.0, Condition::Clear),
+            (71.0, 56.0, 0.9, Condition::Snow),
+        ]);
+        // 21°C is mild either way, so only the precipitation changes
+        assert_eq!(
+            narrative(&f, "imperial"),
+            "Mild and dry today, snow likely Tuesday. Warmest Tuesday (71°F), coldest tonight (55°F)."
+        );
+        assert_eq!(
+            narrative(&forecast(&[]), "imperial"),
+            "No daily forecast available."
+        );
+    }
+}
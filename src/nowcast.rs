@@ -0,0 +1,344 @@
+//! Precipitation nowcast: when does rain start or stop in the next hour or two?
+//!
+//! Minute-level data (OWM `minutely`, Open-Meteo `minutely_15`) covers the
+//! first hour; `hourly` fills in the rest of the horizon. The result is only
+//! pushed when it differs meaningfully from the previous run.
+
+use std::collections::BTreeMap;
+
+use chrono::{FixedOffset, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Condition, Forecast};
+
+/// State file holding the last nowcast that was pushed, per location
+const NOWCAST_STATE_FILE: &str = "weatherust-nowcast.json";
+
+/// Intensity (mm/h) at or above which a minute counts as wet
+const WET_INTENSITY_MM_H: f64 = 0.1;
+
+/// An hour with no minute data counts as wet when it is this likely...
+const WET_HOURLY_POP: f64 = 0.5;
+/// ...and expects at least this much (mm)
+const WET_HOURLY_MM: f64 = 0.1;
+
+/// A predicted start/stop time that moves by less than this is not news
+const MIN_SHIFT_SECS: i64 = 15 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NowcastKind {
+    /// Dry now and for the whole horizon
+    Dry,
+    /// Dry now, precipitation expected at `change_at`
+    Starts,
+    /// Wet now, expected to stop at `change_at`
+    Stops,
+    /// Wet now and for the whole horizon
+    Continues,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Nowcast {
+    pub kind: NowcastKind,
+    /// Unix seconds of the predicted start/stop (only for Starts/Stops)
+    pub change_at: Option<i64>,
+    /// Reference time the nowcast was computed for
+    pub now: i64,
+    pub horizon_minutes: u32,
+    /// "Rain" or "Snow"
+    pub precip: String,
+    /// Highest intensity seen in the horizon (mm/h)
+    pub peak_intensity: f64,
+}
+
+impl Nowcast {
+    pub fn minutes_until_change(&self) -> Option<i64> {
+        self.change_at
+            .map(|t| ((t - self.now) as f64 / 60.0).round() as i64)
+    }
+
+    /// One-line headline, e.g. "Rain starts in ~20 min"
+    pub fn headline(&self) -> String {
+        match (self.kind, self.minutes_until_change()) {
+            (NowcastKind::Starts, Some(m)) => format!("{} starts in ~{} min", self.precip, m),
+            (NowcastKind::Stops, Some(m)) => format!("{} stops in ~{} min", self.precip, m),
+            (NowcastKind::Continues, _) => format!(
+                "{} continuing for the next {} min",
+                self.precip, self.horizon_minutes
+            ),
+            _ => format!(
+                "No precipitation expected in the next {} min",
+                self.horizon_minutes
+            ),
+        }
+    }
+
+    /// Detailed body with local times
+    pub fn body(&self, offset: &FixedOffset, location: &str) -> String {
+        let mut lines = vec![format!("Location: {}", location), self.headline()];
+        if let Some(t) = self
+            .change_at
+            .and_then(|t| offset.timestamp_opt(t, 0).single())
+        {
+            let verb = if self.kind == NowcastKind::Starts {
+                "Expected start"
+            } else {
+                "Expected end"
+            };
+            lines.push(format!("{}: {}", verb, t.format("%H:%M")));
+        }
+        if self.peak_intensity >= WET_INTENSITY_MM_H {
+            lines.push(format!("Peak intensity: {:.1} mm/h", self.peak_intensity));
+        }
+        lines.join("\n")
+    }
+
+    /// Whether this nowcast is different enough from the last pushed one to notify
+    pub fn differs_from(&self, previous: Option<&Nowcast>) -> bool {
+        let Some(prev) = previous else {
+            // First run: only speak up if something is happening
+            return self.kind != NowcastKind::Dry;
+        };
+
+        if self.kind != prev.kind {
+            // The expected progression of an announced start/stop is not news
+            let expected = matches!(
+                (prev.kind, self.kind),
+                (NowcastKind::Starts, NowcastKind::Continues)
+                    | (NowcastKind::Stops, NowcastKind::Dry)
+            );
+            return !expected;
+        }
+        match (self.change_at, prev.change_at) {
+            (Some(a), Some(b)) => (a - b).abs() >= MIN_SHIFT_SECS,
+            _ => false,
+        }
+    }
+}
+
+/// Work out the nowcast for the next `horizon_minutes` starting at `now`
+///
+/// Returns `None` when the provider has no short-range data at all.
+pub fn compute(forecast: &Forecast, now: i64, horizon_minutes: u32) -> Option<Nowcast> {
+    let wet_at = |t: i64| -> Option<(bool, f64)> {
+        if let Some(m) = sample_at(forecast, t) {
+            return Some((m >= WET_INTENSITY_MM_H, m));
+        }
+        forecast
+            .hourly
+            .iter()
+            .find(|h| h.dt <= t && t < h.dt + 3600)
+            .map(|h| {
+                let wet = h.pop >= WET_HOURLY_POP && h.precipitation >= WET_HOURLY_MM;
+                (wet, h.precipitation)
+            })
+    };
+
+    let (wet_now, first) = wet_at(now)?;
+    let mut peak = first;
+    let mut change_at = None;
+
+    for minute in 1..=horizon_minutes as i64 {
+        let t = now + minute * 60;
+        let Some((wet, intensity)) = wet_at(t) else {
+            break;
+        };
+        peak = peak.max(intensity);
+        if wet != wet_now && change_at.is_none() {
+            change_at = Some(t);
+        }
+    }
+
+    let kind = match (wet_now, change_at.is_some()) {
+        (false, false) => NowcastKind::Dry,
+        (false, true) => NowcastKind::Starts,
+        (true, true) => NowcastKind::Stops,
+        (true, false) => NowcastKind::Continues,
+    };
+
+    Some(Nowcast {
+        kind,
+        change_at,
+        now,
+        horizon_minutes,
+        precip: precip_word(forecast, change_at.unwrap_or(now)),
+        peak_intensity: peak,
+    })
+}
+
+/// Minute-level intensity covering `t`, if any
+fn sample_at(forecast: &Forecast, t: i64) -> Option<f64> {
+    let m = &forecast.minutely;
+    // Sample spacing: 60s for OWM, 900s for Open-Meteo
+    let step = match m.as_slice() {
+        [a, b, ..] => (b.dt - a.dt).max(60),
+        _ => 60,
+    };
+    m.iter()
+        .find(|s| s.dt <= t && t < s.dt + step)
+        .map(|s| s.intensity)
+}
+
+/// "Snow" when the hour covering `t` has a snow condition code, else "Rain"
+fn precip_word(forecast: &Forecast, t: i64) -> String {
+    let snowy = forecast
+        .hourly
+        .iter()
+        .find(|h| h.dt <= t && t < h.dt + 3600)
+        .map(|h| h.condition == Condition::Snow)
+        .unwrap_or(false);
+    if snowy { "Snow" } else { "Rain" }.to_string()
+}
+
+/// Last pushed nowcast for a location key (`geocache::cache_key`)
+pub fn load_last(location_key: &str) -> Option<Nowcast> {
+    let mut all: BTreeMap<String, Nowcast> =
+        common::state::load_json(crate::STATE_APP, NOWCAST_STATE_FILE);
+    all.remove(location_key)
+}
+
+pub fn save_last(location_key: &str, nowcast: &Nowcast) -> anyhow::Result<()> {
+    let mut all: BTreeMap<String, Nowcast> =
+        common::state::load_json(crate::STATE_APP, NOWCAST_STATE_FILE);
+    all.insert(location_key.to_string(), nowcast.clone());
+    common::state::save_json(crate::STATE_APP, NOWCAST_STATE_FILE, &all)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CurrentConditions, HourlyForecast, MinutelyPrecipitation};
+
+    const NOW: i64 = 1_718_900_000;
+
+    /// One hour of OWM-style minutely data, wet from `wet_from` minutes onward
+    fn forecast(wet_from: Option<i64>, hourly_pop: f64) -> Forecast {
+        let minutely = (0..60)
+            .map(|m| MinutelyPrecipitation {
+                dt: NOW + m * 60,
+                intensity: match wet_from {
+                    Some(w) if m >= w => 1.5,
+                    _ => 0.0,
+                },
+            })
+            .collect();
+        let hourly = (0..3)
+            .map(|h| HourlyForecast {
+                dt: NOW + h * 3600,
+                temp: 60.0,
+                humidity: 80,
+                pop: hourly_pop,
+                precipitation: if hourly_pop > 0.5 { 2.0 } else { 0.0 },
+                description: "light rain".to_string(),
+                condition: Condition::Rain,
+            })
+            .collect();
+        Forecast {
+            timezone: "UTC".to_string(),
+            timezone_offset: 0,
+            current: CurrentConditions {
+                dt: NOW,
+                temp: 60.0,
+                humidity: 80,
+                description: "overcast clouds".to_string(),
+                ..Default::default()
+            },
+            minutely,
+            hourly,
+            daily: Vec::new(),
+            alerts: Vec::new(),
+            air_quality: None,
+            solar: None,
+        }
+    }
+
+    #[test]
+    fn test_rain_starts() {
+        let f = forecast(Some(20), 0.9);
+        let n = compute(&f, NOW, 120).unwrap();
+        assert_eq!(n.kind, NowcastKind::Starts);
+        assert_eq!(n.minutes_until_change(), Some(20));
+        assert_eq!(n.headline(), "Rain starts in ~20 min");
+    }
+
+    #[test]
+    fn test_snow_from_condition_not_description() {
+        let mut f = forecast(Some(20), 0.9);
+        for h in &mut f.hourly {
+            h.description = "chutes de neige".to_string();
+            h.condition = Condition::Snow;
+        }
+        let n = compute(&f, NOW, 120).unwrap();
+        assert_eq!(n.headline(), "Snow starts in ~20 min");
+    }
+
+    #[test]
+    fn test_rain_stops_after_minutely_window() {
+        // Wet for the whole minutely hour, hourly says dry from hour 1
+        let mut f = forecast(Some(0), 0.9);
+        f.hourly[1].pop = 0.1;
+        f.hourly[1].precipitation = 0.0;
+        let n = compute(&f, NOW, 120).unwrap();
+        assert_eq!(n.kind, NowcastKind::Stops);
+        assert_eq!(n.minutes_until_change(), Some(60));
+    }
+
+    #[test]
+    fn test_dry_through_horizon() {
+        let f = forecast(None, 0.1);
+        let n = compute(&f, NOW, 120).unwrap();
+        assert_eq!(n.kind, NowcastKind::Dry);
+        assert_eq!(n.change_at, None);
+    }
+
+    #[test]
+    fn test_no_short_range_data() {
+        let mut f = forecast(None, 0.1);
+        f.minutely.clear();
+        f.hourly.clear();
+        assert!(compute(&f, NOW, 120).is_none());
+    }
+
+    #[test]
+    fn test_fifteen_minute_samples() {
+        let mut f = forecast(None, 0.1);
+        f.minutely = (0..8)
+            .map(|q| MinutelyPrecipitation {
+                dt: NOW + q * 900,
+                intensity: if q >= 2 { 2.0 } else { 0.0 },
+            })
+            .collect();
+        let n = compute(&f, NOW, 120).unwrap();
+        assert_eq!(n.kind, NowcastKind::Starts);
+        assert_eq!(n.minutes_until_change(), Some(30));
+    }
+
+    #[test]
+    fn test_only_meaningful_changes_notify() {
+        let f = forecast(Some(20), 0.9);
+        let first = compute(&f, NOW, 120).unwrap();
+        assert!(first.differs_from(None));
+
+        // Ten minutes later the start time has only drifted by 5 minutes
+        let f = forecast(Some(15), 0.9);
+        let mut later = compute(&f, NOW, 120).unwrap();
+        later.change_at = Some(first.change_at.unwrap() + 5 * 60);
+        assert!(!later.differs_from(Some(&first)));
+
+        // Now it slips by 30 minutes
+        later.change_at = Some(first.change_at.unwrap() + 30 * 60);
+        assert!(later.differs_from(Some(&first)));
+
+        // Dry on the first run is not news
+        let dry = compute(&forecast(None, 0.1), NOW, 120).unwrap();
+        assert!(!dry.differs_from(None));
+        // But dry after rain was forecast is
+        assert!(dry.differs_from(Some(&first)));
+
+        // Dry after an announced stop is just the stop happening
+        let stops = compute(&forecast(Some(0), 0.1), NOW, 120).unwrap();
+        assert_eq!(stops.kind, NowcastKind::Stops);
+        assert!(!dry.differs_from(Some(&stops)));
+    }
+}
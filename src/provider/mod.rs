@@ -0,0 +1,160 @@
+//! Weather data providers
+//!
+//! Each provider knows how to geocode a location and fetch a forecast,
+//! returning the provider-neutral types from `crate::model`.
+
+mod open_meteo;
+mod owm;
+
+pub use open_meteo::OpenMeteo;
+pub use owm::OpenWeatherMap;
+
+use std::time::Instant;
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use reqwest::Client;
+use tracing::warn;
+
+use crate::locations::LocationQuery;
+use crate::model::{AirQuality, Forecast, Location};
+
+/// Interface implemented by every weather backend
+///
+/// Used generically (`P: WeatherProvider`) so the async methods need no boxing.
+pub trait WeatherProvider {
+    /// Short name used in logs and output
+    fn name(&self) -> &'static str;
+
+    /// Geocode a ZIP/postal code ("52726" or "52726,US")
+    async fn geocode_zip(&self, client: &Client, zip: &str) -> Result<Location>;
+
+    /// Geocode a free-form place ("Davenport,IA,US")
+    async fn geocode_location(&self, client: &Client, query: &str) -> Result<Location>;
+
+    /// Display name for explicit coordinates
+    ///
+    /// Providers without reverse geocoding just show the coordinates.
+    async fn reverse_geocode(&self, _client: &Client, lat: f64, lon: f64) -> Result<String> {
+        Ok(format_coords(lat, lon))
+    }
+
+    /// Fetch current conditions, the daily forecast and active alerts
+    ///
+    /// `lang` asks for localized descriptions where the provider supports it.
+    async fn fetch_forecast(
+        &self,
+        client: &Client,
+        location: &Location,
+        units: &str,
+        lang: Option<&str>,
+    ) -> Result<Forecast>;
+
+    /// Current air quality; `None` when the provider has no air quality data
+    async fn fetch_air_quality(
+        &self,
+        _client: &Client,
+        _location: &Location,
+    ) -> Result<Option<AirQuality>> {
+        Ok(None)
+    }
+}
+
+/// Selectable providers (`--provider` / `WEATHER_PROVIDER`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProviderKind {
+    /// OpenWeatherMap One Call (requires OWM_API_KEY)
+    Owm,
+    /// Open-Meteo (no API key needed)
+    OpenMeteo,
+}
+
+impl ProviderKind {
+    /// Preferred provider first, the other one as fallback
+    pub fn with_fallback(self) -> [ProviderKind; 2] {
+        match self {
+            ProviderKind::Owm => [ProviderKind::Owm, ProviderKind::OpenMeteo],
+            ProviderKind::OpenMeteo => [ProviderKind::OpenMeteo, ProviderKind::Owm],
+        }
+    }
+}
+
+/// "41.5236, -90.5776"
+pub fn format_coords(lat: f64, lon: f64) -> String {
+    format!("{:.4}, {:.4}", lat, lon)
+}
+
+/// Resolve the location and fetch the forecast, trying providers in order
+///
+/// `cached` skips geocoding entirely. A provider that is not configured
+/// (OWM without `OWM_API_KEY`) or that returns an error is skipped with a
+/// warning and the next one is tried.
+pub async fn fetch_with_fallback(
+    client: &Client,
+    order: &[ProviderKind],
+    query: &LocationQuery,
+    cached: Option<&Location>,
+    units: &str,
+    lang: Option<&str>,
+) -> Result<(ProviderKind, Location, Forecast)> {
+    let mut last_err = None;
+
+    for kind in order {
+        let result = match kind {
+            ProviderKind::Owm => match OpenWeatherMap::from_env() {
+                Some(p) => fetch_from(&p, client, query, cached, units, lang).await,
+                None => {
+                    warn!("OWM_API_KEY not set; skipping OpenWeatherMap");
+                    continue;
+                }
+            },
+            ProviderKind::OpenMeteo => {
+                fetch_from(&OpenMeteo, client, query, cached, units, lang).await
+            }
+        };
+
+        match result {
+            Ok((location, forecast)) => return Ok((*kind, location, forecast)),
+            Err(e) => {
+                warn!(provider = ?kind, error = %e, "Weather provider failed; trying next provider");
+                last_err = Some(e);
+            }
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| anyhow!("no weather provider available")))
+}
+
+async fn fetch_from<P: WeatherProvider>(
+    provider: &P,
+    client: &Client,
+    query: &LocationQuery,
+    cached: Option<&Location>,
+    units: &str,
+    lang: Option<&str>,
+) -> Result<(Location, Forecast)> {
+    let location = match cached {
+        Some(location) => location.clone(),
+        None => crate::resolve_location(client, provider, query)
+            .await
+            .with_context(|| format!("{} geocoding failed", provider.name()))?,
+    };
+
+    // Track API call timing
+    let start = Instant::now();
+    let result = provider
+        .fetch_forecast(client, &location, units, lang)
+        .await;
+    common::metrics::record_weather_fetch(result.is_ok(), start.elapsed().as_secs_f64());
+
+    let mut forecast =
+        result.with_context(|| format!("{} forecast request failed", provider.name()))?;
+
+    // Air quality is a bonus; never fail the report over it
+    match provider.fetch_air_quality(client, &location).await {
+        Ok(aq) => forecast.air_quality = aq,
+        Err(e) => warn!(provider = provider.name(), error = %e, "Air quality request failed"),
+    }
+
+    Ok((location, forecast))
+}
@@ -0,0 +1,616 @@
+//! Open-Meteo backend (no API key required)
+
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::WeatherProvider;
+use crate::model::{
+    AirQuality, Condition, CurrentConditions, DailyForecast, Forecast, HourlyForecast, Location,
+    MinutelyPrecipitation,
+};
+use crate::{normalize_city_query, split_zip_and_cc};
+
+pub struct OpenMeteo;
+
+#[derive(Debug, Deserialize)]
+struct GeoResponse {
+    #[serde(default)]
+    results: Vec<GeoResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeoResult {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country_code: Option<String>,
+    admin1: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastResponse {
+    timezone: String,
+    utc_offset_seconds: i32,
+    current: Current,
+    minutely_15: Option<Minutely15>,
+    hourly: Option<Hourly>,
+    daily: Daily,
+}
+
+#[derive(Debug, Deserialize)]
+struct Current {
+    time: i64, // unix seconds (timeformat=unixtime)
+    temperature_2m: f64,
+    apparent_temperature: Option<f64>,
+    relative_humidity_2m: f64,
+    dew_point_2m: Option<f64>,
+    pressure_msl: Option<f64>, // hPa
+    cloud_cover: Option<f64>,  // percent
+    wind_speed_10m: Option<f64>,
+    wind_direction_10m: Option<f64>,
+    wind_gusts_10m: Option<f64>,
+    weather_code: u8,
+}
+
+// Open-Meteo returns series as parallel arrays
+#[derive(Debug, Deserialize)]
+struct Minutely15 {
+    time: Vec<i64>,
+    precipitation: Vec<Option<f64>>, // mm per 15 minutes
+}
+
+#[derive(Debug, Deserialize)]
+struct Hourly {
+    time: Vec<i64>,
+    temperature_2m: Vec<Option<f64>>,
+    relative_humidity_2m: Vec<Option<f64>>,
+    precipitation_probability: Vec<Option<f64>>, // percent
+    precipitation: Vec<Option<f64>>,             // mm
+    weather_code: Vec<Option<u8>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Daily {
+    time: Vec<i64>,
+    weather_code: Vec<Option<u8>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    precipitation_probability_max: Vec<Option<f64>>, // percent
+    rain_sum: Vec<Option<f64>>,                      // mm
+    snowfall_sum: Vec<Option<f64>>,                  // cm of fresh snow (depth)
+    /// Rain + showers + snowfall as water, mm
+    #[serde(default)]
+    precipitation_sum: Vec<Option<f64>>,
+    /// Convective rain, mm; not included in `rain_sum`
+    #[serde(default)]
+    showers_sum: Vec<Option<f64>>,
+    wind_speed_10m_max: Vec<Option<f64>>,
+    wind_gusts_10m_max: Vec<Option<f64>>,
+    #[serde(default)]
+    uv_index_max: Vec<Option<f64>>,
+    #[serde(default)]
+    wind_direction_10m_dominant: Vec<Option<f64>>,
+    #[serde(default)]
+    sunrise: Vec<Option<i64>>,
+    #[serde(default)]
+    sunset: Vec<Option<i64>>,
+    #[serde(default)]
+    cloud_cover_mean: Vec<Option<f64>>, // percent
+}
+
+// Air quality API (air-quality-api.open-meteo.com)
+#[derive(Debug, Deserialize)]
+struct AirQualityResponse {
+    current: AirQualityCurrent,
+}
+
+#[derive(Debug, Deserialize)]
+struct AirQualityCurrent {
+    european_aqi: Option<f64>,
+    pm2_5: Option<f64>,
+    pm10: Option<f64>,
+    ozone: Option<f64>,
+}
+
+impl AirQualityCurrent {
+    fn into_air_quality(self) -> Option<AirQuality> {
+        // European AQI bands (0-20 good ... 80-100 very poor) map onto OWM's 1-5
+        let aqi = match self.european_aqi? {
+            v if v < 20.0 => 1,
+            v if v < 40.0 => 2,
+            v if v < 60.0 => 3,
+            v if v < 80.0 => 4,
+            _ => 5,
+        };
+        Some(AirQuality {
+            aqi,
+            pm2_5: self.pm2_5.unwrap_or(0.0),
+            pm10: self.pm10.unwrap_or(0.0),
+            o3: self.ozone.unwrap_or(0.0),
+        })
+    }
+}
+
+impl OpenMeteo {
+    async fn search(
+        &self,
+        client: &Client,
+        name: &str,
+        country_code: Option<&str>,
+        input: &str,
+    ) -> Result<Location> {
+        let mut req = client
+            .get("https://geocoding-api.open-meteo.com/v1/search")
+            .query(&[
+                ("name", name),
+                ("count", "1"),
+                ("language", "en"),
+                ("format", "json"),
+            ]);
+        if let Some(cc) = country_code {
+            req = req.query(&[("countryCode", cc)]);
+        }
+        let resp = req.send().await?.error_for_status()?;
+        let mut v: GeoResponse = resp.json().await?;
+        if v.results.is_empty() {
+            return Err(anyhow!(
+                "Could not find coordinates for \"{input}\" (Open-Meteo geocoding)."
+            ));
+        }
+        let loc = v.results.remove(0);
+        let name = format!(
+            "{}{}{}",
+            loc.name,
+            loc.admin1.map(|s| format!(", {}", s)).unwrap_or_default(),
+            loc.country_code
+                .map(|s| format!(", {}", s))
+                .unwrap_or_default()
+        );
+        Ok(Location {
+            lat: loc.latitude,
+            lon: loc.longitude,
+            name,
+        })
+    }
+}
+
+impl WeatherProvider for OpenMeteo {
+    fn name(&self) -> &'static str {
+        "Open-Meteo"
+    }
+
+    async fn geocode_zip(&self, client: &Client, zip_in: &str) -> Result<Location> {
+        let (zip, cc) = split_zip_and_cc(zip_in);
+        self.search(client, &zip, Some(&cc), zip_in).await
+    }
+
+    async fn geocode_location(&self, client: &Client, input: &str) -> Result<Location> {
+        // "City,ST,US" -> name "City", country "US"; the state cannot be
+        // filtered on because Open-Meteo only returns full admin names
+        let normalized = normalize_city_query(input);
+        let parts: Vec<&str> = normalized.split(',').map(|p| p.trim()).collect();
+        let country = parts
+            .last()
+            .filter(|cc| parts.len() > 1 && cc.len() == 2)
+            .copied();
+        self.search(client, parts[0], country, input).await
+    }
+
+    async fn fetch_forecast(
+        &self,
+        client: &Client,
+        location: &Location,
+        units: &str,
+        _lang: Option<&str>,
+    ) -> Result<Forecast> {
+        // Match OWM: °F + mph for imperial, °C + m/s for metric, and K + m/s
+        // for standard (converted below; Open-Meteo has no Kelvin option).
+        // Descriptions come from the WMO code table and are always English.
+        let (temperature_unit, wind_speed_unit) = if units == "imperial" {
+            ("fahrenheit", "mph")
+        } else {
+            ("celsius", "ms")
+        };
+        let resp = client
+            .get("https://api.open-meteo.com/v1/forecast")
+            .query(&[
+                ("latitude", location.lat.to_string()),
+                ("longitude", location.lon.to_string()),
+                (
+                    "current",
+                    "temperature_2m,apparent_temperature,relative_humidity_2m,dew_point_2m,\
+                     pressure_msl,cloud_cover,wind_speed_10m,wind_direction_10m,wind_gusts_10m,\
+                     weather_code"
+                        .to_string(),
+                ),
+                ("minutely_15", "precipitation".to_string()),
+                ("forecast_minutely_15", "8".to_string()),
+                (
+                    "hourly",
+                    "temperature_2m,relative_humidity_2m,precipitation_probability,precipitation,weather_code"
+                        .to_string(),
+                ),
+                (
+                    "daily",
+                    "weather_code,temperature_2m_max,temperature_2m_min,precipitation_probability_max,\
+                     precipitation_sum,rain_sum,showers_sum,snowfall_sum,wind_speed_10m_max,wind_gusts_10m_max,uv_index_max,\
+                     wind_direction_10m_dominant,sunrise,sunset,cloud_cover_mean"
+                        .to_string(),
+                ),
+                ("temperature_unit", temperature_unit.to_string()),
+                ("wind_speed_unit", wind_speed_unit.to_string()),
+                ("timezone", "auto".to_string()),
+                ("timeformat", "unixtime".to_string()),
+                ("forecast_days", "8".to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+        let data: ForecastResponse = resp.json().await?;
+        let mut forecast: Forecast = data.into();
+        if units == "standard" {
+            to_kelvin(&mut forecast);
+        }
+        Ok(forecast)
+    }
+
+    async fn fetch_air_quality(
+        &self,
+        client: &Client,
+        location: &Location,
+    ) -> Result<Option<AirQuality>> {
+        let resp = client
+            .get("https://air-quality-api.open-meteo.com/v1/air-quality")
+            .query(&[
+                ("latitude", location.lat.to_string()),
+                ("longitude", location.lon.to_string()),
+                ("current", "european_aqi,pm2_5,pm10,ozone".to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+        let data: AirQualityResponse = resp.json().await?;
+        Ok(data.current.into_air_quality())
+    }
+}
+
+/// Value `i` of a parallel series, treating gaps and nulls alike
+fn at<T: Copy>(series: &[Option<T>], i: usize) -> Option<T> {
+    series.get(i).copied().flatten()
+}
+
+/// Rain and showers for day `i`, mm
+fn daily_rain(d: &Daily, i: usize) -> f64 {
+    at(&d.rain_sum, i).unwrap_or(0.0) + at(&d.showers_sum, i).unwrap_or(0.0)
+}
+
+/// Liquid equivalent of day `i`'s snowfall, mm
+///
+/// `snowfall_sum` is fresh snow depth; the water it holds is what is left of
+/// `precipitation_sum` after rain and showers. Without that total, fall back
+/// to Open-Meteo's own ratio of 7 cm of snow per 10 mm of water.
+fn daily_snow_water(d: &Daily, i: usize) -> f64 {
+    match at(&d.precipitation_sum, i) {
+        Some(total) => (total - daily_rain(d, i)).max(0.0),
+        None => at(&d.snowfall_sum, i).unwrap_or(0.0) * 10.0 / 7.0,
+    }
+}
+
+impl From<ForecastResponse> for Forecast {
+    fn from(r: ForecastResponse) -> Self {
+        let d = &r.daily;
+        let daily = d
+            .time
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &dt)| {
+                // Skip days the model has no temperatures for
+                Some(DailyForecast {
+                    dt,
+                    temp_min: at(&d.temperature_2m_min, i)?,
+                    temp_max: at(&d.temperature_2m_max, i)?,
+                    pop: at(&d.precipitation_probability_max, i).unwrap_or(0.0) / 100.0,
+                    rain: daily_rain(d, i),
+                    snow: daily_snow_water(d, i),
+                    snow_depth: at(&d.snowfall_sum, i).unwrap_or(0.0) * 10.0,
+                    wind_speed: at(&d.wind_speed_10m_max, i).unwrap_or(0.0),
+                    wind_deg: at(&d.wind_direction_10m_dominant, i),
+                    wind_gust: at(&d.wind_gusts_10m_max, i),
+                    uvi: at(&d.uv_index_max, i),
+                    sunrise: at(&d.sunrise, i),
+                    sunset: at(&d.sunset, i),
+                    clouds: at(&d.cloud_cover_mean, i).map(|v| v.round() as u8),
+                    description: describe(at(&d.weather_code, i)),
+                    condition: at(&d.weather_code, i)
+                        .map(wmo_condition)
+                        .unwrap_or_default(),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        let minutely = r
+            .minutely_15
+            .map(|m| {
+                m.time
+                    .iter()
+                    .zip(m.precipitation)
+                    .filter_map(|(&dt, p)| {
+                        Some(MinutelyPrecipitation {
+                            dt,
+                            // mm per 15 min -> mm/h
+                            intensity: p? * 4.0,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let hourly = r
+            .hourly
+            .map(|h| {
+                h.time
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, &dt)| {
+                        Some(HourlyForecast {
+                            dt,
+                            temp: at(&h.temperature_2m, i)?,
+                            humidity: at(&h.relative_humidity_2m, i).unwrap_or(0.0).round() as u8,
+                            pop: at(&h.precipitation_probability, i).unwrap_or(0.0) / 100.0,
+                            precipitation: at(&h.precipitation, i).unwrap_or(0.0),
+                            description: describe(at(&h.weather_code, i)),
+                            condition: at(&h.weather_code, i)
+                                .map(wmo_condition)
+                                .unwrap_or_default(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let c = &r.current;
+        let current = CurrentConditions {
+            dt: c.time,
+            temp: c.temperature_2m,
+            feels_like: c.apparent_temperature.unwrap_or(c.temperature_2m),
+            humidity: c.relative_humidity_2m.round() as u8,
+            dew_point: c.dew_point_2m,
+            pressure: c.pressure_msl,
+            clouds: c.cloud_cover.map(|v| v.round() as u8),
+            wind_speed: c.wind_speed_10m.unwrap_or(0.0),
+            wind_deg: c.wind_direction_10m,
+            wind_gust: c.wind_gusts_10m,
+            // Today's entry carries the sun times
+            sunrise: at(&d.sunrise, 0),
+            sunset: at(&d.sunset, 0),
+            description: wmo_description(c.weather_code).to_string(),
+        };
+
+        Forecast {
+            timezone: r.timezone,
+            timezone_offset: r.utc_offset_seconds,
+            current,
+            minutely,
+            hourly,
+            daily,
+            // Open-Meteo does not publish warnings
+            alerts: Vec::new(),
+            // Filled in separately from the air quality API
+            air_quality: None,
+            // Derived from the daily forecast when an array is configured
+            solar: None,
+        }
+    }
+}
+
+/// Convert every temperature from °C to K (`--units standard`)
+fn to_kelvin(f: &mut Forecast) {
+    const OFFSET: f64 = 273.15;
+    let c = &mut f.current;
+    c.temp += OFFSET;
+    c.feels_like += OFFSET;
+    c.dew_point = c.dew_point.map(|t| t + OFFSET);
+    for h in &mut f.hourly {
+        h.temp += OFFSET;
+    }
+    for d in &mut f.daily {
+        d.temp_min += OFFSET;
+        d.temp_max += OFFSET;
+        d.dew_point = d.dew_point.map(|t| t + OFFSET);
+    }
+}
+
+fn describe(code: Option<u8>) -> String {
+    code.map(wmo_description).unwrap_or("n/a").to_string()
+}
+
+/// WMO weather interpretation code -> description (OWM-style wording)
+fn wmo_description(code: u8) -> &'static str {
+    match code {
+        0 => "clear sky",
+        1 => "mainly clear",
+        2 => "partly cloudy",
+        3 => "overcast clouds",
+        45 => "fog",
+        48 => "depositing rime fog",
+        51 => "light drizzle",
+        53 => "drizzle",
+        55 => "heavy drizzle",
+        56 => "light freezing drizzle",
+        57 => "freezing drizzle",
+        61 => "light rain",
+        63 => "moderate rain",
+        65 => "heavy rain",
+        66 => "light freezing rain",
+        67 => "freezing rain",
+        71 => "light snow",
+        73 => "snow",
+        75 => "heavy snow",
+        77 => "snow grains",
+        80 => "light rain showers",
+        81 => "rain showers",
+        82 => "violent rain showers",
+        85 => "light snow showers",
+        86 => "heavy snow showers",
+        95 => "thunderstorm",
+        96 => "thunderstorm with light hail",
+        99 => "thunderstorm with heavy hail",
+        _ => "unknown",
+    }
+}
+
+/// WMO weather interpretation code -> condition
+fn wmo_condition(code: u8) -> Condition {
+    match code {
+        0 | 1 => Condition::Clear,
+        2 => Condition::PartlyCloudy,
+        3 => Condition::Cloudy,
+        45 | 48 => Condition::Fog,
+        51..=57 => Condition::Drizzle,
+        61..=67 | 80..=82 => Condition::Rain,
+        71..=77 | 85 | 86 => Condition::Snow,
+        95..=99 => Condition::Thunderstorm,
+        _ => Condition::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forecast_response_to_forecast() {
+        let json = r#"{
+            "latitude": 41.52, "longitude": -90.57,
+            "utc_offset_seconds": -18000,
+            "timezone": "America/Chicago",
+            "current": {"time": 1718900000, "interval": 900,
+                        "temperature_2m": 78.3, "apparent_temperature": 80.2,
+                        "relative_humidity_2m": 61, "dew_point_2m": 63.5, "pressure_msl": 1012.4,
+                        "cloud_cover": 42, "wind_speed_10m": 9.1, "wind_direction_10m": 215,
+                        "wind_gusts_10m": 18.3, "weather_code": 2},
+            "minutely_15": {"time": [1718900100, 1718901000], "precipitation": [0.0, 0.5]},
+            "hourly": {
+                "time": [1718899200, 1718902800],
+                "temperature_2m": [78.0, 77.1],
+                "relative_humidity_2m": [60, 72],
+                "precipitation_probability": [20, 70],
+                "precipitation": [0.0, 1.4],
+                "weather_code": [2, 63]
+            },
+            "daily": {
+                "time": [1718859600, 1718946000, 1719032400],
+                "weather_code": [61, 95, null],
+                "temperature_2m_max": [84.1, 80.0, null],
+                "temperature_2m_min": [64.0, 62.5, null],
+                "precipitation_probability_max": [80, 95, null],
+                "precipitation_sum": [4.1, 12.9, null],
+                "rain_sum": [4.1, 11.0, null],
+                "showers_sum": [0.0, 1.0, null],
+                "snowfall_sum": [0.0, 0.5, null],
+                "wind_speed_10m_max": [12.0, 20.5, null],
+                "wind_gusts_10m_max": [25.0, 41.2, null],
+                "wind_direction_10m_dominant": [190, 270, null],
+                "sunrise": [1718879000, 1718965400, null],
+                "sunset": [1718933000, 1719019400, null],
+                "cloud_cover_mean": [88, 64.6, null]
+            }
+        }"#;
+        let r: ForecastResponse = serde_json::from_str(json).unwrap();
+        let f: Forecast = r.into();
+
+        assert_eq!(f.timezone_offset, -18000);
+        assert_eq!(f.current.description, "partly cloudy");
+        assert_eq!(f.current.humidity, 61);
+        assert_eq!(f.minutely.len(), 2);
+        assert_eq!(f.minutely[1].intensity, 2.0);
+        assert_eq!(f.hourly.len(), 2);
+        assert_eq!(f.hourly[1].pop, 0.7);
+        assert_eq!(f.hourly[1].description, "moderate rain");
+        assert_eq!(f.daily.len(), 2);
+        assert_eq!(f.daily[0].description, "light rain");
+        assert_eq!(f.daily[1].description, "thunderstorm");
+        assert_eq!(f.daily[1].condition, Condition::Thunderstorm);
+        assert_eq!(f.daily[1].temp_min, 62.5);
+        assert_eq!(f.daily[1].pop, 0.95);
+        // 0.5 cm of snow is 0.9 mm of water, not 5 mm
+        assert_eq!(f.daily[1].rain, 12.0);
+        assert!((f.daily[1].snow - 0.9).abs() < 1e-9);
+        assert_eq!(f.daily[1].snow_depth, 5.0);
+        assert_eq!(f.daily[1].wind_gust, Some(41.2));
+        assert_eq!(f.hourly[1].humidity, 72);
+        assert_eq!(f.current.feels_like, 80.2);
+        assert_eq!(f.current.clouds, Some(42));
+        assert_eq!(f.current.wind_deg, Some(215.0));
+        assert_eq!(f.current.sunrise, Some(1718879000));
+        assert_eq!(f.daily[1].wind_deg, Some(270.0));
+        assert_eq!(f.daily[1].sunset, Some(1719019400));
+        assert_eq!(f.daily[1].clouds, Some(65));
+        assert!(f.alerts.is_empty());
+    }
+
+    #[test]
+    fn test_standard_units_are_kelvin() {
+        let json = r#"{
+            "utc_offset_seconds": 0, "timezone": "UTC",
+            "current": {"time": 0, "temperature_2m": 20.0, "relative_humidity_2m": 50,
+                        "dew_point_2m": 10.0, "weather_code": 0},
+            "daily": {
+                "time": [0], "weather_code": [0],
+                "temperature_2m_max": [25.0], "temperature_2m_min": [-5.0],
+                "precipitation_probability_max": [0], "rain_sum": [0], "snowfall_sum": [0],
+                "wind_speed_10m_max": [3.0], "wind_gusts_10m_max": [null]
+            }
+        }"#;
+        let r: ForecastResponse = serde_json::from_str(json).unwrap();
+        let mut f: Forecast = r.into();
+        // Missing apparent temperature falls back to the air temperature
+        assert_eq!(f.current.feels_like, 20.0);
+        to_kelvin(&mut f);
+        assert!((f.current.temp - 293.15).abs() < 1e-9);
+        assert!((f.current.feels_like - 293.15).abs() < 1e-9);
+        assert_eq!(f.current.dew_point.map(|t| t.round()), Some(283.0));
+        assert!((f.daily[0].temp_min - 268.15).abs() < 1e-9);
+        assert_eq!(f.daily[0].wind_speed, 3.0);
+    }
+
+    #[test]
+    fn test_snow_water_without_precipitation_total() {
+        let json = r#"{
+            "utc_offset_seconds": 0, "timezone": "UTC",
+            "current": {"time": 0, "temperature_2m": -2.0, "relative_humidity_2m": 90,
+                        "weather_code": 73},
+            "daily": {
+                "time": [0], "weather_code": [73],
+                "temperature_2m_max": [0.0], "temperature_2m_min": [-6.0],
+                "precipitation_probability_max": [90], "rain_sum": [0], "snowfall_sum": [7.0],
+                "wind_speed_10m_max": [3.0], "wind_gusts_10m_max": [null]
+            }
+        }"#;
+        let r: ForecastResponse = serde_json::from_str(json).unwrap();
+        let f: Forecast = r.into();
+        // 7 cm of fresh snow holds about 10 mm of water
+        assert!((f.daily[0].snow - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_european_aqi_bands() {
+        let json = r#"{"current": {"time": 0, "european_aqi": 45.0, "pm2_5": 9.5, "pm10": 15.0, "ozone": 80.0}}"#;
+        let r: AirQualityResponse = serde_json::from_str(json).unwrap();
+        let aq = r.current.into_air_quality().unwrap();
+        assert_eq!(aq.aqi, 3);
+        assert_eq!(aq.pm2_5, 9.5);
+
+        let json = r#"{"current": {"time": 0, "european_aqi": null, "pm2_5": null, "pm10": null, "ozone": null}}"#;
+        let r: AirQualityResponse = serde_json::from_str(json).unwrap();
+        assert!(r.current.into_air_quality().is_none());
+    }
+
+    #[test]
+    fn test_geocode_response_without_results() {
+        let v: GeoResponse = serde_json::from_str(r#"{"generationtime_ms": 0.5}"#).unwrap();
+        assert!(v.results.is_empty());
+    }
+}
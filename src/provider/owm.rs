@@ -0,0 +1,690 @@
+//! OpenWeatherMap backend (geocoding + One Call 3.0, falling back to 2.5)
+
+use std::env;
+
+use anyhow::{anyhow, Result};
+use common::retry::{is_retryable_http_error, retry_async_when};
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::warn;
+
+use super::WeatherProvider;
+use crate::model::{
+    AirQuality, Condition, CurrentConditions, DailyForecast, Forecast, HourlyForecast, Location,
+    MinutelyPrecipitation, WeatherAlert,
+};
+use crate::{normalize_city_query, split_zip_and_cc};
+
+/// Public API host; `OWM_BASE_URL` overrides it (mock servers, air-gapped demos)
+const DEFAULT_BASE_URL: &str = "https://api.openweathermap.org";
+
+pub struct OpenWeatherMap {
+    api_key: String,
+    base_url: String,
+}
+
+impl OpenWeatherMap {
+    /// Build from `OWM_API_KEY` and `OWM_BASE_URL`; `None` when the key is missing or empty
+    pub fn from_env() -> Option<Self> {
+        let api_key = env::var("OWM_API_KEY")
+            .ok()
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())?;
+        let base_url = env::var("OWM_BASE_URL")
+            .ok()
+            .map(|u| u.trim().trim_end_matches('/').to_string())
+            .filter(|u| !u.is_empty())
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        Some(OpenWeatherMap { api_key, base_url })
+    }
+
+    /// GET `{base_url}{path}` with `query` (URL-encoded) and the API key as
+    /// JSON, retrying transient failures (timeouts, 5xx, 429) with the
+    /// project's backoff
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        client: &Client,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> reqwest::Result<T> {
+        let url = format!("{}{}", self.base_url, path);
+        retry_async_when(
+            || async {
+                client
+                    .get(&url)
+                    .query(query)
+                    .query(&[("appid", &self.api_key)])
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<T>()
+                    .await
+            },
+            is_retryable_http_error,
+        )
+        .await
+    }
+}
+
+/// Keys without a One Call 3.0 subscription get 401/403 from /data/3.0
+fn is_unauthorized(e: &reqwest::Error) -> bool {
+    matches!(
+        e.status(),
+        Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+    )
+}
+
+#[derive(Debug, Deserialize)]
+struct GeoResult {
+    name: String,
+    lat: f64,
+    lon: f64,
+    country: String,
+    state: Option<String>,
+}
+
+impl GeoResult {
+    /// "Davenport, Iowa, US"
+    fn display_name(&self) -> String {
+        format!(
+            "{}{}, {}",
+            self.name,
+            self.state
+                .as_ref()
+                .map(|s| format!(", {}", s))
+                .unwrap_or_default(),
+            self.country
+        )
+    }
+}
+
+// Air pollution API (/data/2.5/air_pollution)
+#[derive(Debug, Deserialize)]
+struct AirPollution {
+    list: Vec<AirPollutionEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AirPollutionEntry {
+    main: AirPollutionMain,
+    components: AirComponents,
+}
+
+#[derive(Debug, Deserialize)]
+struct AirPollutionMain {
+    aqi: u8, // 1-5
+}
+
+#[derive(Debug, Deserialize)]
+struct AirComponents {
+    #[serde(default)]
+    pm2_5: f64,
+    #[serde(default)]
+    pm10: f64,
+    #[serde(default)]
+    o3: f64,
+}
+
+impl From<AirPollutionEntry> for AirQuality {
+    fn from(e: AirPollutionEntry) -> Self {
+        AirQuality {
+            aqi: e.main.aqi,
+            pm2_5: e.components.pm2_5,
+            pm10: e.components.pm10,
+            o3: e.components.o3,
+        }
+    }
+}
+
+// For ZIP geocoding (returns a single object)
+#[derive(Debug, Deserialize)]
+struct ZipGeoResult {
+    name: String,
+    lat: f64,
+    lon: f64,
+    country: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OneCall {
+    timezone: String,
+    timezone_offset: i32, // seconds
+    current: Current,
+    #[serde(default)]
+    minutely: Vec<Minutely>,
+    #[serde(default)]
+    hourly: Vec<Hourly>,
+    daily: Vec<Daily>,
+    #[serde(default)]
+    alerts: Vec<Alert>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Current {
+    dt: i64, // unix seconds
+    temp: f64,
+    feels_like: Option<f64>,
+    humidity: u8,
+    dew_point: Option<f64>,
+    pressure: Option<f64>, // hPa
+    clouds: Option<u8>,    // %
+    #[serde(default)]
+    wind_speed: f64,
+    wind_deg: Option<f64>,
+    wind_gust: Option<f64>,
+    sunrise: Option<i64>,
+    sunset: Option<i64>,
+    weather: Vec<Weather>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Minutely {
+    dt: i64,
+    precipitation: f64, // mm/h
+}
+
+#[derive(Debug, Deserialize)]
+struct Hourly {
+    dt: i64,
+    temp: f64,
+    humidity: u8,
+    #[serde(default)]
+    pop: f64,
+    rain: Option<OneHour>,
+    snow: Option<OneHour>,
+    weather: Vec<Weather>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OneHour {
+    #[serde(rename = "1h", default)]
+    one_hour: f64, // mm
+}
+
+#[derive(Debug, Deserialize)]
+struct Daily {
+    dt: i64,
+    temp: DailyTemp,
+    #[serde(default)]
+    pop: f64,
+    rain: Option<f64>, // mm
+    snow: Option<f64>, // mm
+    #[serde(default)]
+    wind_speed: f64,
+    wind_deg: Option<f64>,
+    wind_gust: Option<f64>,
+    uvi: Option<f64>,
+    pressure: Option<f64>, // hPa
+    dew_point: Option<f64>,
+    clouds: Option<u8>, // %
+    sunrise: Option<i64>,
+    sunset: Option<i64>,
+    weather: Vec<Weather>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DailyTemp {
+    min: f64,
+    max: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Weather {
+    #[serde(default)]
+    id: u16,
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Alert {
+    #[serde(default)]
+    sender_name: String,
+    event: String,
+    start: i64,
+    end: i64,
+    #[serde(default)]
+    description: String,
+}
+
+impl WeatherProvider for OpenWeatherMap {
+    fn name(&self) -> &'static str {
+        "OpenWeatherMap"
+    }
+
+    async fn geocode_zip(&self, client: &Client, zip_in: &str) -> Result<Location> {
+        let (zip, cc) = split_zip_and_cc(zip_in);
+        let z: ZipGeoResult = self
+            .get_json(client, "/geo/1.0/zip", &[("zip", format!("{zip},{cc}"))])
+            .await?;
+        Ok(Location {
+            lat: z.lat,
+            lon: z.lon,
+            name: format!("{}, {}", z.name, z.country),
+        })
+    }
+
+    async fn geocode_location(&self, client: &Client, input: &str) -> Result<Location> {
+        let q = normalize_city_query(input);
+        let mut v: Vec<GeoResult> = self
+            .get_json(
+                client,
+                "/geo/1.0/direct",
+                &[("q", q), ("limit", "1".to_string())],
+            )
+            .await?;
+        if v.is_empty() {
+            return Err(anyhow!(
+                "Could not find coordinates for \"{input}\".\nHint: try \"City,STATE,US\" (e.g., Davenport,IA,US) or use a ZIP code."
+            ));
+        }
+        let loc = v.remove(0);
+        Ok(Location {
+            lat: loc.lat,
+            lon: loc.lon,
+            name: loc.display_name(),
+        })
+    }
+
+    async fn reverse_geocode(&self, client: &Client, lat: f64, lon: f64) -> Result<String> {
+        let v: Vec<GeoResult> = self
+            .get_json(
+                client,
+                "/geo/1.0/reverse",
+                &[
+                    ("lat", lat.to_string()),
+                    ("lon", lon.to_string()),
+                    ("limit", "1".to_string()),
+                ],
+            )
+            .await?;
+        v.first()
+            .map(GeoResult::display_name)
+            .ok_or_else(|| anyhow!("no place found near {lat},{lon}"))
+    }
+
+    async fn fetch_forecast(
+        &self,
+        client: &Client,
+        location: &Location,
+        units: &str,
+        lang: Option<&str>,
+    ) -> Result<Forecast> {
+        let mut query = vec![
+            ("lat", location.lat.to_string()),
+            ("lon", location.lon.to_string()),
+            ("units", units.to_string()),
+        ];
+        if let Some(lang) = lang {
+            query.push(("lang", lang.to_string()));
+        }
+
+        // Accounts without a One Call 3.0 subscription still have 2.5
+        let data: OneCall = match self.get_json(client, "/data/3.0/onecall", &query).await {
+            Err(e) if is_unauthorized(&e) => {
+                warn!(error = %e, "One Call 3.0 not authorized; falling back to /data/2.5/onecall");
+                self.get_json(client, "/data/2.5/onecall", &query).await?
+            }
+            result => result?,
+        };
+        Ok(data.into())
+    }
+
+    async fn fetch_air_quality(
+        &self,
+        client: &Client,
+        location: &Location,
+    ) -> Result<Option<AirQuality>> {
+        let data: AirPollution = self
+            .get_json(
+                client,
+                "/data/2.5/air_pollution",
+                &[
+                    ("lat", location.lat.to_string()),
+                    ("lon", location.lon.to_string()),
+                ],
+            )
+            .await?;
+        Ok(data.list.into_iter().next().map(AirQuality::from))
+    }
+}
+
+fn first_description(weather: &[Weather], fallback: &str) -> String {
+    weather
+        .first()
+        .map(|w| w.description.clone())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Condition of the first weather entry from its OWM condition id
+/// (https://openweathermap.org/weather-conditions)
+fn first_condition(weather: &[Weather]) -> Condition {
+    match weather.first().map(|w| w.id) {
+        Some(200..=299) => Condition::Thunderstorm,
+        Some(300..=399) => Condition::Drizzle,
+        Some(500..=599) => Condition::Rain,
+        Some(600..=699) => Condition::Snow,
+        Some(700..=799) => Condition::Fog,
+        Some(800) => Condition::Clear,
+        Some(801 | 802) => Condition::PartlyCloudy,
+        Some(803 | 804) => Condition::Cloudy,
+        _ => Condition::Unknown,
+    }
+}
+
+/// Estimated snowfall depth from its water content, mm
+///
+/// One Call only reports snow as water; use Open-Meteo's ratio of 7 cm of
+/// snow per 10 mm of water so both providers agree.
+fn snow_depth(water: f64) -> f64 {
+    water * 7.0
+}
+
+impl From<OneCall> for Forecast {
+    fn from(oc: OneCall) -> Self {
+        Forecast {
+            timezone: oc.timezone,
+            timezone_offset: oc.timezone_offset,
+            current: CurrentConditions {
+                dt: oc.current.dt,
+                temp: oc.current.temp,
+                feels_like: oc.current.feels_like.unwrap_or(oc.current.temp),
+                humidity: oc.current.humidity,
+                dew_point: oc.current.dew_point,
+                pressure: oc.current.pressure,
+                clouds: oc.current.clouds,
+                wind_speed: oc.current.wind_speed,
+                wind_deg: oc.current.wind_deg,
+                wind_gust: oc.current.wind_gust,
+                sunrise: oc.current.sunrise,
+                sunset: oc.current.sunset,
+                description: first_description(&oc.current.weather, "no description"),
+            },
+            minutely: oc
+                .minutely
+                .into_iter()
+                .map(|m| MinutelyPrecipitation {
+                    dt: m.dt,
+                    intensity: m.precipitation,
+                })
+                .collect(),
+            hourly: oc
+                .hourly
+                .into_iter()
+                .map(|h| HourlyForecast {
+                    dt: h.dt,
+                    temp: h.temp,
+                    humidity: h.humidity,
+                    pop: h.pop,
+                    precipitation: h.rain.map(|r| r.one_hour).unwrap_or(0.0)
+                        + h.snow.map(|s| s.one_hour).unwrap_or(0.0),
+                    description: first_description(&h.weather, "n/a"),
+                    condition: first_condition(&h.weather),
+                })
+                .collect(),
+            daily: oc
+                .daily
+                .into_iter()
+                .map(|d| DailyForecast {
+                    dt: d.dt,
+                    temp_min: d.temp.min,
+                    temp_max: d.temp.max,
+                    pop: d.pop,
+                    rain: d.rain.unwrap_or(0.0),
+                    snow: d.snow.unwrap_or(0.0),
+                    snow_depth: snow_depth(d.snow.unwrap_or(0.0)),
+                    wind_speed: d.wind_speed,
+                    wind_deg: d.wind_deg,
+                    wind_gust: d.wind_gust,
+                    uvi: d.uvi,
+                    pressure: d.pressure,
+                    dew_point: d.dew_point,
+                    clouds: d.clouds,
+                    sunrise: d.sunrise,
+                    sunset: d.sunset,
+                    description: first_description(&d.weather, "n/a"),
+                    condition: first_condition(&d.weather),
+                })
+                .collect(),
+            alerts: oc
+                .alerts
+                .into_iter()
+                .map(|a| WeatherAlert {
+                    sender: a.sender_name,
+                    event: a.event,
+                    start: a.start,
+                    end: a.end,
+                    description: a.description,
+                })
+                .collect(),
+            // Filled in separately from the air pollution API
+            air_quality: None,
+            // Derived from the daily forecast when an array is configured
+            solar: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const MINIMAL_ONECALL: &str = r#"{"timezone": "UTC", "timezone_offset": 0,
+        "current": {"dt": 0, "temp": 10.0, "humidity": 50, "weather": []}, "daily": []}"#;
+
+    /// Tiny HTTP server on localhost answering every request with `respond(path)`
+    fn mock_server(respond: impl Fn(&str) -> (u16, &'static str) + Send + 'static) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 4096];
+                let n = stream.read(&mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                let (status, body) = respond(&path);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    fn provider(base_url: String) -> OpenWeatherMap {
+        OpenWeatherMap {
+            api_key: "test-key".to_string(),
+            base_url,
+        }
+    }
+
+    fn location() -> Location {
+        Location {
+            lat: 41.52,
+            lon: -90.57,
+            name: "Davenport, IA, US".to_string(),
+        }
+    }
+
+    fn client() -> Client {
+        Client::builder().no_proxy().build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_2_5_when_unauthorized() {
+        let base = mock_server(|path| {
+            assert!(path.contains("appid=test-key"));
+            if path.starts_with("/data/3.0/onecall") {
+                (401, r#"{"cod": 401, "message": "Invalid API key"}"#)
+            } else if path.starts_with("/data/2.5/onecall") {
+                assert!(path.contains("units=metric") && path.contains("lang=de"));
+                (200, MINIMAL_ONECALL)
+            } else {
+                (404, "{}")
+            }
+        });
+        let f = provider(base)
+            .fetch_forecast(&client(), &location(), "metric", Some("de"))
+            .await
+            .unwrap();
+        assert_eq!(f.current.temp, 10.0);
+    }
+
+    #[tokio::test]
+    async fn test_city_query_is_url_encoded() {
+        let base = mock_server(|path| {
+            assert!(path.starts_with("/geo/1.0/direct?"));
+            assert!(path.contains("q=Saint+Paul%2CMN%2CUS&limit=1"), "{path}");
+            (
+                200,
+                r#"[{"name": "Saint Paul", "lat": 44.95, "lon": -93.09, "country": "US", "state": "Minnesota"}]"#,
+            )
+        });
+        let loc = provider(base)
+            .geocode_location(&client(), "Saint Paul, MN")
+            .await
+            .unwrap();
+        assert_eq!(loc.name, "Saint Paul, Minnesota, US");
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_do_not_fall_back() {
+        static LEGACY_HITS: AtomicUsize = AtomicUsize::new(0);
+        let base = mock_server(|path| {
+            if path.starts_with("/data/2.5/onecall") {
+                LEGACY_HITS.fetch_add(1, Ordering::SeqCst);
+            }
+            (404, "{}")
+        });
+        let err = provider(base)
+            .fetch_forecast(&client(), &location(), "imperial", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("404"));
+        assert_eq!(LEGACY_HITS.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let base = mock_server(|_| {
+            if CALLS.fetch_add(1, Ordering::SeqCst) == 0 {
+                (503, "{}")
+            } else {
+                (
+                    200,
+                    r#"{"zip": "52726", "name": "Davenport", "lat": 41.52, "lon": -90.57, "country": "US"}"#,
+                )
+            }
+        });
+        let loc = provider(base)
+            .geocode_zip(&client(), "52726")
+            .await
+            .unwrap();
+        assert_eq!(loc.name, "Davenport, US");
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_onecall_to_forecast() {
+        let json = r#"{
+            "lat": 41.52, "lon": -90.57,
+            "timezone": "America/Chicago",
+            "timezone_offset": -18000,
+            "current": {
+                "dt": 1718900000, "temp": 78.3, "feels_like": 79.9, "humidity": 61,
+                "dew_point": 63.4, "pressure": 1012, "clouds": 20,
+                "wind_speed": 9.2, "wind_deg": 200, "wind_gust": 15.0,
+                "sunrise": 1718879000, "sunset": 1718933000,
+                "weather": [{"id": 800, "main": "Clear", "description": "clear sky", "icon": "01d"}]
+            },
+            "minutely": [
+                {"dt": 1718900040, "precipitation": 0},
+                {"dt": 1718900100, "precipitation": 1.2}
+            ],
+            "hourly": [
+                {"dt": 1718899200, "temp": 78.0, "humidity": 65, "pop": 0.6,
+                 "rain": {"1h": 0.8}, "snow": {"1h": 0.1},
+                 "weather": [{"id": 500, "main": "Rain", "description": "light rain", "icon": "10d"}]}
+            ],
+            "daily": [
+                {"dt": 1718902800, "temp": {"min": 64.0, "max": 84.1, "day": 80.0},
+                 "pop": 0.8, "rain": 5.2, "snow": 0.5, "wind_speed": 12.5, "wind_gust": 31.0,
+                 "weather": [{"id": 500, "main": "Rain", "description": "light rain", "icon": "10d"}]}
+            ],
+            "alerts": [
+                {"sender_name": "NWS Quad Cities IA IL", "event": "Tornado Warning",
+                 "start": 1718900000, "end": 1718903600,
+                 "description": "At 405 PM CDT, a severe thunderstorm capable of producing a tornado...",
+                 "tags": ["Tornado"]}
+            ]
+        }"#;
+        let oc: OneCall = serde_json::from_str(json).unwrap();
+        let f: Forecast = oc.into();
+
+        assert_eq!(f.timezone, "America/Chicago");
+        assert_eq!(f.current.description, "clear sky");
+        assert_eq!(f.current.humidity, 61);
+        assert_eq!(f.current.feels_like, 79.9);
+        assert_eq!(f.current.pressure, Some(1012.0));
+        assert_eq!(f.current.wind_deg, Some(200.0));
+        assert_eq!(f.current.sunset, Some(1718933000));
+        assert_eq!(f.minutely.len(), 2);
+        assert_eq!(f.minutely[1].intensity, 1.2);
+        assert_eq!(f.hourly[0].pop, 0.6);
+        assert!((f.hourly[0].precipitation - 0.9).abs() < 1e-9);
+        assert_eq!(f.daily.len(), 1);
+        assert_eq!(f.daily[0].temp_max, 84.1);
+        assert_eq!(f.daily[0].description, "light rain");
+        assert_eq!(f.daily[0].condition, Condition::Rain);
+        assert_eq!(f.daily[0].rain, 5.2);
+        assert_eq!(f.daily[0].snow, 0.5);
+        // 0.5 mm of water is about 3.5 mm of fresh snow
+        assert!((f.daily[0].snow_depth - 3.5).abs() < 1e-9);
+        assert_eq!(f.daily[0].wind_gust, Some(31.0));
+        assert_eq!(f.alerts[0].event, "Tornado Warning");
+        assert_eq!(f.alerts[0].sender, "NWS Quad Cities IA IL");
+    }
+
+    #[test]
+    fn test_onecall_without_alerts() {
+        let json = r#"{
+            "timezone": "UTC", "timezone_offset": 0,
+            "current": {"dt": 0, "temp": 10.0, "humidity": 50, "weather": []},
+            "daily": []
+        }"#;
+        let oc: OneCall = serde_json::from_str(json).unwrap();
+        let f: Forecast = oc.into();
+        assert!(f.alerts.is_empty());
+        assert!(f.minutely.is_empty());
+        assert!(f.hourly.is_empty());
+        assert_eq!(f.current.description, "no description");
+        // Missing feels-like falls back to the air temperature
+        assert_eq!(f.current.feels_like, 10.0);
+        assert_eq!(f.current.wind_gust, None);
+    }
+
+    #[test]
+    fn test_air_pollution_to_air_quality() {
+        let json = r#"{
+            "coord": {"lon": -90.57, "lat": 41.52},
+            "list": [{
+                "dt": 1718900000,
+                "main": {"aqi": 3},
+                "components": {"co": 230.3, "no2": 4.1, "o3": 98.7, "pm2_5": 14.2, "pm10": 21.9}
+            }]
+        }"#;
+        let ap: AirPollution = serde_json::from_str(json).unwrap();
+        let aq: AirQuality = ap.list.into_iter().next().unwrap().into();
+        assert_eq!(aq.aqi, 3);
+        assert_eq!(aq.label(), "Moderate");
+        assert_eq!(aq.pm2_5, 14.2);
+        assert_eq!(aq.o3, 98.7);
+    }
+}
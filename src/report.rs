@@ -0,0 +1,690 @@
+//! Daily summary rendering (`--format text|json|markdown|compact|narrative`)
+
+use chrono::{FixedOffset, TimeZone};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::alerts::format_alert_line;
+use crate::model::{
+    compass, temp_unit, wind_unit, AirQuality, DailyForecast, Forecast, Location, SolarDay,
+    SolarOutlook,
+};
+use crate::narrative::narrative;
+
+/// Output format for the daily summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text block (default)
+    Text,
+    /// Full normalized forecast as one JSON object per location
+    Json,
+    /// Markdown table (rendered by ntfy)
+    Markdown,
+    /// Single summary line
+    Compact,
+    /// Plain-language weekly outlook, short enough for a lock screen
+    Narrative,
+}
+
+/// JSON document for one location
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    location: &'a Location,
+    units: &'a str,
+    forecast: &'a Forecast,
+}
+
+/// Days listed by the text and markdown reports: the 7 after today
+fn report_days(data: &Forecast) -> impl Iterator<Item = &DailyForecast> {
+    data.daily.iter().skip(1).take(7)
+}
+
+fn day_label(dt: i64, offset: &FixedOffset) -> String {
+    offset
+        .timestamp_opt(dt, 0)
+        .unwrap()
+        .format("%a %d")
+        .to_string()
+}
+
+/// Local "HH:MM" for a unix timestamp
+pub fn clock(dt: i64, offset: &FixedOffset) -> String {
+    offset
+        .timestamp_opt(dt, 0)
+        .single()
+        .map(|t| t.format("%H:%M").to_string())
+        .unwrap_or_else(|| "--:--".to_string())
+}
+
+/// "9.2 mph SSW, gusts 15.0 mph"
+pub fn wind_text(speed: f64, deg: Option<f64>, gust: Option<f64>, units: &str) -> String {
+    let unit = wind_unit(units);
+    let mut text = format!("{:.1} {}", speed, unit);
+    if let Some(deg) = deg {
+        text.push_str(&format!(" {}", compass(deg)));
+    }
+    if let Some(gust) = gust {
+        text.push_str(&format!(", gusts {:.1} {}", gust, unit));
+    }
+    text
+}
+
+/// Precipitation amount in inches for imperial, mm otherwise
+pub fn precip_amount(mm: f64, units: &str) -> String {
+    if units == "imperial" {
+        format!("{:.2} in", mm / 25.4)
+    } else {
+        format!("{:.1} mm", mm)
+    }
+}
+
+/// "Feels like: 74.1°F | Dew point: 60.2°F | Pressure: 1012 hPa | Clouds: 20%"
+fn current_details(data: &Forecast, units: &str) -> String {
+    let degree = temp_unit(units);
+    let c = &data.current;
+    let mut parts = vec![format!("Feels like: {:.1}{}", c.feels_like, degree)];
+    if let Some(dp) = c.dew_point {
+        parts.push(format!("Dew point: {:.1}{}", dp, degree));
+    }
+    if let Some(p) = c.pressure {
+        parts.push(format!("Pressure: {:.0} hPa", p));
+    }
+    if let Some(clouds) = c.clouds {
+        parts.push(format!("Clouds: {}%", clouds));
+    }
+    parts.join(" | ")
+}
+
+/// "Sunrise: 05:31 | Sunset: 20:36", when the provider reports them
+fn sun_line(data: &Forecast, offset: &FixedOffset) -> Option<String> {
+    match (data.current.sunrise, data.current.sunset) {
+        (Some(rise), Some(set)) => Some(format!(
+            "Sunrise: {} | Sunset: {}",
+            clock(rise, offset),
+            clock(set, offset)
+        )),
+        _ => None,
+    }
+}
+
+/// "05:31-20:36" for a day's sunrise and sunset
+fn day_sun(day: &DailyForecast, offset: &FixedOffset) -> Option<String> {
+    match (day.sunrise, day.sunset) {
+        (Some(rise), Some(set)) => Some(format!("{}-{}", clock(rise, offset), clock(set, offset))),
+        _ => None,
+    }
+}
+
+/// Today's "05:31-20:36" from the current conditions, else from daily[0]
+fn today_sun(data: &Forecast, offset: &FixedOffset) -> Option<String> {
+    match (data.current.sunrise, data.current.sunset) {
+        (Some(rise), Some(set)) => Some(format!("{}-{}", clock(rise, offset), clock(set, offset))),
+        _ => data.daily.first().and_then(|d| day_sun(d, offset)),
+    }
+}
+
+/// "dew point 55.2°F, 1012 hPa, sun 05:31-20:36", or `None` when the
+/// provider reports none of them for the day
+fn day_details(day: &DailyForecast, offset: &FixedOffset, units: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(dp) = day.dew_point {
+        parts.push(format!("dew point {:.1}{}", dp, temp_unit(units)));
+    }
+    if let Some(p) = day.pressure {
+        parts.push(format!("{:.0} hPa", p));
+    }
+    if let Some(sun) = day_sun(day, offset) {
+        parts.push(format!("sun {}", sun));
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Today's maximum UV index
+fn uv_today(data: &Forecast) -> Option<f64> {
+    data.daily.first().and_then(|d| d.uvi)
+}
+
+/// "AQI 2 (Fair) | PM2.5 5.1 | PM10 8.0 | O3 60.0 µg/m³"
+fn air_quality_line(aq: &AirQuality) -> String {
+    format!(
+        "AQI {} ({}) | PM2.5 {:.1} | PM10 {:.1} | O3 {:.1} µg/m³",
+        aq.aqi,
+        aq.label(),
+        aq.pm2_5,
+        aq.pm10,
+        aq.o3
+    )
+}
+
+/// "Solar outlook (6.0 kWp, 30° tilt, facing S)"
+fn solar_heading(solar: &SolarOutlook) -> String {
+    format!(
+        "Solar outlook ({:.1} kWp, {:.0}° tilt, facing {})",
+        solar.kwp,
+        solar.tilt,
+        compass(solar.azimuth)
+    )
+}
+
+/// "Thu 20: 31.2 kWh (clear sky 36.0, clouds 20%)"
+fn solar_day_line(day: &SolarDay, offset: &FixedOffset) -> String {
+    let mut line = format!(
+        "{}: {:.1} kWh (clear sky {:.1}",
+        day_label(day.dt, offset),
+        day.kwh,
+        day.clear_sky_kwh
+    );
+    if let Some(clouds) = day.clouds {
+        line.push_str(&format!(", clouds {}%", clouds));
+    }
+    line.push(')');
+    line
+}
+
+/// Concise single-line summary used as the notification title
+pub fn summary_line(data: &Forecast, units: &str) -> String {
+    let degree = temp_unit(units);
+    let current_desc = data.current.description.as_str();
+
+    // Today is daily[0]
+    match data.daily.first() {
+        Some(today) => format!(
+            "Now: {:.1}{} ({}) | Today H/L: {:.1}{}/ {:.1}{}",
+            data.current.temp, degree, current_desc, today.temp_max, degree, today.temp_min, degree
+        ),
+        None => format!("Now: {:.1}{} ({})", data.current.temp, degree, current_desc),
+    }
+}
+
+/// Air quality / UV readings at or above the warning thresholds (`AQI_WARN`, `UV_WARN`)
+pub fn exposure_warnings(
+    data: &Forecast,
+    aqi_warn: Option<u8>,
+    uv_warn: Option<f64>,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let (Some(aq), Some(limit)) = (&data.air_quality, aqi_warn) {
+        if aq.aqi >= limit {
+            warnings.push(format!("AQI {} ({})", aq.aqi, aq.label()));
+        }
+    }
+    if let (Some(uv), Some(limit)) = (uv_today(data), uv_warn) {
+        if uv >= limit {
+            warnings.push(format!("UV {:.1}", uv));
+        }
+    }
+    warnings
+}
+
+/// Render the summary for stdout in the requested format
+pub fn render(
+    format: OutputFormat,
+    location: &Location,
+    data: &Forecast,
+    units: &str,
+) -> anyhow::Result<String> {
+    Ok(match format {
+        OutputFormat::Text => render_text(location, data, units),
+        OutputFormat::Json => serde_json::to_string(&JsonReport {
+            location,
+            units,
+            forecast: data,
+        })?,
+        OutputFormat::Markdown => render_markdown(location, data, units),
+        OutputFormat::Compact => {
+            let mut line = format!("{}: {}", location.name, summary_line(data, units));
+            line.push_str(&format!(
+                " | Feels {:.0}{} | Wind {:.0} {}",
+                data.current.feels_like,
+                temp_unit(units),
+                data.current.wind_speed,
+                wind_unit(units)
+            ));
+            if let Some(deg) = data.current.wind_deg {
+                line.push_str(&format!(" {}", compass(deg)));
+            }
+            if let Some(gust) = data.current.wind_gust {
+                line.push_str(&format!(" gusts {:.0}", gust));
+            }
+            if let Some(dp) = data.current.dew_point {
+                line.push_str(&format!(" | Dew {:.0}{}", dp, temp_unit(units)));
+            }
+            if let Some(p) = data.current.pressure {
+                line.push_str(&format!(" | {:.0} hPa", p));
+            }
+            if let Some(clouds) = data.current.clouds {
+                line.push_str(&format!(" | Clouds {}%", clouds));
+            }
+            if let Some(today) = data.daily.first() {
+                line.push_str(&format!(" | Precip {:.0}%", today.pop * 100.0));
+            }
+            if let Some(sun) = today_sun(data, &data.offset()) {
+                line.push_str(&format!(" | Sun {}", sun));
+            }
+            if let Some(aq) = &data.air_quality {
+                line.push_str(&format!(" | AQI {}", aq.aqi));
+            }
+            if let Some(uv) = uv_today(data) {
+                line.push_str(&format!(" | UV {:.0}", uv));
+            }
+            if let Some(today) = data.solar.as_ref().and_then(|s| s.days.first()) {
+                line.push_str(&format!(" | PV {:.0} kWh", today.kwh));
+            }
+            line
+        }
+        OutputFormat::Narrative => format!("{}: {}", location.name, narrative(data, units)),
+    })
+}
+
+/// Detailed multi-line body
+pub fn render_text(location: &Location, data: &Forecast, units: &str) -> String {
+    let offset = data.offset();
+    let degree = temp_unit(units);
+    let c = &data.current;
+
+    // timezone-aware timestamp for "current"
+    let current_time = offset.timestamp_opt(data.current.dt, 0).unwrap();
+
+    let mut lines = Vec::new();
+    lines.push(format!(
+        "Location: {}\nTimezone: {}\nNow: {} | {} | Temp: {:.1} {} | Humidity: {}%",
+        location.name,
+        data.timezone,
+        current_time,
+        data.current.description,
+        data.current.temp,
+        degree,
+        data.current.humidity
+    ));
+    lines.push(current_details(data, units));
+    lines.push(format!(
+        "Wind: {}",
+        wind_text(c.wind_speed, c.wind_deg, c.wind_gust, units)
+    ));
+    if let Some(sun) = sun_line(data, &offset) {
+        lines.push(sun);
+    }
+    if let Some(aq) = &data.air_quality {
+        lines.push(format!("Air quality: {}", air_quality_line(aq)));
+    }
+    if let Some(uv) = uv_today(data) {
+        lines.push(format!("UV index today: max {:.1}", uv));
+    }
+    lines.push("\nNext 7 days (high/low):".to_string());
+
+    for day in report_days(data) {
+        let label = day_label(day.dt, &offset);
+        let desc = &day.description;
+        let mut line = format!(
+            "  {label}: {:>5.1}{deg}/{:>5.1}{deg}  ({desc})  precip {:.0}% {}, wind {}",
+            day.temp_max,
+            day.temp_min,
+            day.pop * 100.0,
+            precip_amount(day.rain + day.snow, units),
+            wind_text(day.wind_speed, day.wind_deg, day.wind_gust, units),
+            deg = degree
+        );
+        if let Some(details) = day_details(day, &offset, units) {
+            line.push_str(&format!(", {}", details));
+        }
+        lines.push(line);
+    }
+
+    if let Some(solar) = &data.solar {
+        lines.push(format!("\n{}:", solar_heading(solar)));
+        for day in &solar.days {
+            lines.push(format!("  {}", solar_day_line(day, &offset)));
+        }
+    }
+
+    if !data.alerts.is_empty() {
+        lines.push("\nActive alerts:".to_string());
+        for alert in &data.alerts {
+            lines.push(format_alert_line(alert, &offset));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Markdown with a forecast table
+pub fn render_markdown(location: &Location, data: &Forecast, units: &str) -> String {
+    let offset = data.offset();
+    let degree = temp_unit(units);
+    let c = &data.current;
+
+    let mut lines = vec![
+        format!("**{}**", location.name),
+        String::new(),
+        format!(
+            "Now: **{:.1}{}**, {}, humidity {}%",
+            data.current.temp, degree, data.current.description, data.current.humidity
+        ),
+        String::new(),
+        current_details(data, units),
+        String::new(),
+        format!(
+            "Wind: {}",
+            wind_text(c.wind_speed, c.wind_deg, c.wind_gust, units)
+        ),
+    ];
+    if let Some(sun) = sun_line(data, &offset) {
+        lines.push(String::new());
+        lines.push(sun);
+    }
+    if let Some(aq) = &data.air_quality {
+        lines.push(String::new());
+        lines.push(format!("Air quality: {}", air_quality_line(aq)));
+    }
+    lines.push(String::new());
+    lines.push(
+        "| Day | High | Low | Precip | Wind | UV | Dew point | Pressure | Sun | Conditions |"
+            .to_string(),
+    );
+    lines.push(
+        "|-----|-----:|----:|-------:|-----:|---:|----------:|---------:|-----|------------|"
+            .to_string(),
+    );
+
+    for day in report_days(data) {
+        lines.push(format!(
+            "| {} | {:.1}{deg} | {:.1}{deg} | {:.0}% {} | {:.0} {}{} | {} | {} | {} | {} | {} |",
+            day_label(day.dt, &offset),
+            day.temp_max,
+            day.temp_min,
+            day.pop * 100.0,
+            precip_amount(day.rain + day.snow, units),
+            day.wind_speed,
+            wind_unit(units),
+            day.wind_deg
+                .map(|d| format!(" {}", compass(d)))
+                .unwrap_or_default(),
+            day.uvi
+                .map(|u| format!("{:.0}", u))
+                .unwrap_or_else(|| "-".to_string()),
+            day.dew_point
+                .map(|dp| format!("{:.1}{}", dp, degree))
+                .unwrap_or_else(|| "-".to_string()),
+            day.pressure
+                .map(|p| format!("{:.0} hPa", p))
+                .unwrap_or_else(|| "-".to_string()),
+            day_sun(day, &offset).unwrap_or_else(|| "-".to_string()),
+            day.description.replace('|', "/"),
+            deg = degree
+        ));
+    }
+
+    if let Some(solar) = &data.solar {
+        lines.push(String::new());
+        lines.push(format!("**{}**", solar_heading(solar)));
+        for day in &solar.days {
+            lines.push(format!("- {}", solar_day_line(day, &offset)));
+        }
+    }
+
+    if !data.alerts.is_empty() {
+        lines.push(String::new());
+        lines.push("**Active alerts**".to_string());
+        for alert in &data.alerts {
+            lines.push(format!("- {}", format_alert_line(alert, &offset).trim()));
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CurrentConditions, DailyForecast, WeatherAlert};
+
+    // 2024-06-20 00:00 UTC (Thursday)
+    const DAY0: i64 = 1_718_841_600;
+
+    fn location() -> Location {
+        Location {
+            lat: 41.5236,
+            lon: -90.5776,
+            name: "Davenport, IA, US".to_string(),
+        }
+    }
+
+    fn forecast() -> Forecast {
+        let daily = (0..3)
+            .map(|i| DailyForecast {
+                dt: DAY0 + i * 86_400 + 12 * 3600,
+                temp_min: 60.0 + i as f64,
+                temp_max: 80.0 + i as f64,
+                pop: 0.25,
+                rain: 1.0,
+                snow: 0.0,
+                wind_speed: 8.0,
+                wind_deg: Some(225.0),
+                wind_gust: None,
+                uvi: Some(6.0 + i as f64),
+                dew_point: Some(55.0 + i as f64),
+                pressure: Some(1010.0 + i as f64),
+                sunrise: Some(DAY0 + i * 86_400 + 5 * 3600 + 31 * 60),
+                sunset: Some(DAY0 + i * 86_400 + 20 * 3600 + 36 * 60),
+                description: "light rain".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        Forecast {
+            timezone: "UTC".to_string(),
+            timezone_offset: 0,
+            current: CurrentConditions {
+                dt: DAY0 + 10 * 3600,
+                temp: 72.34,
+                feels_like: 74.06,
+                humidity: 55,
+                dew_point: Some(55.2),
+                pressure: Some(1012.0),
+                clouds: Some(20),
+                wind_speed: 9.2,
+                wind_deg: Some(200.0),
+                wind_gust: Some(15.0),
+                sunrise: Some(DAY0 + 5 * 3600 + 31 * 60),
+                sunset: Some(DAY0 + 20 * 3600 + 36 * 60),
+                description: "clear sky".to_string(),
+            },
+            minutely: Vec::new(),
+            hourly: Vec::new(),
+            daily,
+            alerts: vec![WeatherAlert {
+                sender: "NWS".to_string(),
+                event: "Heat Advisory".to_string(),
+                start: DAY0,
+                end: DAY0 + 20 * 3600,
+                description: String::new(),
+            }],
+            air_quality: Some(AirQuality {
+                aqi: 4,
+                pm2_5: 38.2,
+                pm10: 51.0,
+                o3: 120.4,
+            }),
+            solar: None,
+        }
+    }
+
+    #[test]
+    fn test_summary_and_compact() {
+        let f = forecast();
+        assert_eq!(
+            summary_line(&f, "imperial"),
+            "Now: 72.3°F (clear sky) | Today H/L: 80.0°F/ 60.0°F"
+        );
+        let compact = render(OutputFormat::Compact, &location(), &f, "metric").unwrap();
+        assert_eq!(
+            compact,
+            "Davenport, IA, US: Now: 72.3°C (clear sky) | Today H/L: 80.0°C/ 60.0°C | Feels 74°C \
+             | Wind 9 m/s SSW gusts 15 | Dew 55°C | 1012 hPa | Clouds 20% | Precip 25% \
+             | Sun 05:31-20:36 | AQI 4 | UV 6"
+        );
+
+        // Fields the provider did not report are left out; sun times fall back to today's
+        let mut sparse = forecast();
+        sparse.current.wind_gust = None;
+        sparse.current.dew_point = None;
+        sparse.current.pressure = None;
+        sparse.current.clouds = None;
+        sparse.current.sunrise = None;
+        sparse.air_quality = None;
+        let compact = render(OutputFormat::Compact, &location(), &sparse, "metric").unwrap();
+        assert!(compact.ends_with("| Wind 9 m/s SSW | Precip 25% | Sun 05:31-20:36 | UV 6"));
+    }
+
+    #[test]
+    fn test_exposure_warnings() {
+        let f = forecast();
+        assert!(exposure_warnings(&f, None, None).is_empty());
+        assert_eq!(
+            exposure_warnings(&f, Some(3), Some(6.0)),
+            vec!["AQI 4 (Poor)".to_string(), "UV 6.0".to_string()]
+        );
+        assert!(exposure_warnings(&f, Some(5), Some(8.0)).is_empty());
+    }
+
+    #[test]
+    fn test_text_skips_today_and_lists_alerts() {
+        let text = render_text(&location(), &forecast(), "imperial");
+        assert!(text.starts_with("Location: Davenport, IA, US\nTimezone: UTC"));
+        assert!(!text.contains("Thu 20:"));
+        assert!(
+            text.contains("Air quality: AQI 4 (Poor) | PM2.5 38.2 | PM10 51.0 | O3 120.4 µg/m³")
+        );
+        assert!(text.contains("UV index today: max 6.0"));
+        assert!(text
+            .contains("Feels like: 74.1°F | Dew point: 55.2°F | Pressure: 1012 hPa | Clouds: 20%"));
+        assert!(text.contains("Wind: 9.2 mph SSW, gusts 15.0 mph"));
+        assert!(text.contains("Sunrise: 05:31 | Sunset: 20:36"));
+        assert!(text.contains(
+            "  Fri 21:  81.0°F/ 61.0°F  (light rain)  precip 25% 0.04 in, wind 8.0 mph SW, \
+             dew point 56.0°F, 1011 hPa, sun 05:31-20:36\n"
+        ));
+        assert!(text.contains("Active alerts:\n  Heat Advisory (until Thu Jun 20 20:00)"));
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let md = render_markdown(&location(), &forecast(), "imperial");
+        assert!(md.starts_with("**Davenport, IA, US**"));
+        assert!(md.contains(
+            "| Day | High | Low | Precip | Wind | UV | Dew point | Pressure | Sun | Conditions |"
+        ));
+        assert!(md.contains(
+            "| Fri 21 | 81.0°F | 61.0°F | 25% 0.04 in | 8 mph SW | 7 | 56.0°F | 1011 hPa \
+             | 05:31-20:36 | light rain |"
+        ));
+        assert!(md.contains("Wind: 9.2 mph SSW, gusts 15.0 mph"));
+        assert!(md.contains("- Heat Advisory (until Thu Jun 20 20:00)"));
+    }
+
+    #[test]
+    fn test_standard_units_and_missing_details() {
+        let mut f = forecast();
+        f.current.dew_point = None;
+        f.current.pressure = None;
+        f.current.wind_deg = None;
+        f.current.wind_gust = None;
+        f.current.sunrise = None;
+        for day in &mut f.daily {
+            day.dew_point = None;
+            day.pressure = None;
+            day.sunset = None;
+        }
+        let text = render_text(&location(), &f, "standard");
+        assert!(text.contains("Temp: 72.3 K"));
+        assert!(text.contains("Feels like: 74.1K | Clouds: 20%\n"));
+        assert!(text.contains("Wind: 9.2 m/s\n"));
+        assert!(!text.contains("Sunrise"));
+        assert!(text.contains("precip 25% 1.0 mm, wind 8.0 m/s SW\n"));
+        let md = render_markdown(&location(), &f, "standard");
+        assert!(md.contains("| 7 | - | - | - | light rain |"));
+    }
+
+    #[test]
+    fn test_text_and_markdown_cover_the_same_days() {
+        let mut f = forecast();
+        let template = f.daily[0].clone();
+        f.daily = (0..10)
+            .map(|i| DailyForecast {
+                dt: DAY0 + i * 86_400 + 12 * 3600,
+                ..template.clone()
+            })
+            .collect();
+        let text = render_text(&location(), &f, "imperial");
+        let md = render_markdown(&location(), &f, "imperial");
+
+        let offset = f.offset();
+        let in_text: Vec<_> = f
+            .daily
+            .iter()
+            .map(|d| day_label(d.dt, &offset))
+            .filter(|l| text.contains(&format!("  {l}:")))
+            .collect();
+        let in_md: Vec<_> = f
+            .daily
+            .iter()
+            .map(|d| day_label(d.dt, &offset))
+            .filter(|l| md.contains(&format!("| {l} |")))
+            .collect();
+        assert_eq!(in_text, in_md);
+        // Tomorrow through a week from today
+        assert_eq!(in_text.first().map(String::as_str), Some("Fri 21"));
+        assert_eq!(in_text.last().map(String::as_str), Some("Thu 27"));
+        assert_eq!(in_text.len(), 7);
+    }
+
+    #[test]
+    fn test_json_contains_full_forecast() {
+        let json = render(OutputFormat::Json, &location(), &forecast(), "imperial").unwrap();
+        let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(v["location"]["name"], "Davenport, IA, US");
+        assert_eq!(v["units"], "imperial");
+        assert_eq!(v["forecast"]["current"]["humidity"], 55);
+        assert_eq!(v["forecast"]["daily"].as_array().unwrap().len(), 3);
+        assert_eq!(v["forecast"]["daily"][1]["temp_max"], 81.0);
+        assert_eq!(v["forecast"]["alerts"][0]["event"], "Heat Advisory");
+        assert!(v["forecast"]["solar"].is_null());
+    }
+
+    #[test]
+    fn test_solar_outlook_sections() {
+        let mut f = forecast();
+        f.solar = Some(SolarOutlook {
+            kwp: 6.0,
+            tilt: 30.0,
+            azimuth: 180.0,
+            days: vec![
+                SolarDay {
+                    dt: DAY0 + 12 * 3600,
+                    kwh: 31.24,
+                    clear_sky_kwh: 36.0,
+                    clouds: Some(20),
+                },
+                SolarDay {
+                    dt: DAY0 + 36 * 3600,
+                    kwh: 18.0,
+                    clear_sky_kwh: 35.9,
+                    clouds: None,
+                },
+            ],
+        });
+        let text = render_text(&location(), &f, "imperial");
+        assert!(text.contains(
+            "Solar outlook (6.0 kWp, 30° tilt, facing S):\n  \
+             Thu 20: 31.2 kWh (clear sky 36.0, clouds 20%)\n  \
+             Fri 21: 18.0 kWh (clear sky 35.9)"
+        ));
+        let md = render_markdown(&location(), &f, "imperial");
+        assert!(md.contains("**Solar outlook (6.0 kWp, 30° tilt, facing S)**\n- Thu 20: 31.2 kWh"));
+        let compact = render(OutputFormat::Compact, &location(), &f, "imperial").unwrap();
+        assert!(compact.ends_with(" | UV 6 | PV 31 kWh"));
+        let json = render(OutputFormat::Json, &location(), &f, "imperial").unwrap();
+        let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(v["forecast"]["solar"]["kwp"], 6.0);
+        assert_eq!(v["forecast"]["solar"]["days"][0]["kwh"], 31.24);
+    }
+}
@@ -0,0 +1,562 @@
+//! Threshold rules evaluated against the daily and hourly forecast
+//!
+//! One rule per line (file) or `;`-separated (env), e.g.
+//!
+//! ```text
+//! Frost: low < 32 tomorrow
+//! Heat: heat_index > 100
+//! Wind: gust above 40
+//! Snow: snow > 2
+//! ```
+//!
+//! Values are in the report's unit system: °F/mph/inches for imperial,
+//! °C/m/s/mm for metric, K/m/s/mm for standard. A unit on the value ("0°C",
+//! "20 m/s") is converted to the report's units instead. `pop` is a
+//! percentage and `snow` is the depth of fresh snow, not its water content.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{FixedOffset, TimeZone};
+
+use crate::model::{from_celsius, temp_unit, to_celsius, wind_unit, Forecast};
+
+const MPH_TO_MS: f64 = 0.44704;
+const INCH_TO_MM: f64 = 25.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    High,
+    Low,
+    HeatIndex,
+    Wind,
+    Gust,
+    Rain,
+    Snow,
+    Pop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Above,
+    AtLeast,
+    Below,
+    AtMost,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Today,
+    Tomorrow,
+    /// Every day in the forecast
+    AnyDay,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    pub metric: Metric,
+    pub op: Op,
+    pub threshold: f64,
+    /// Unit system the threshold was written in; `None` means the run's own
+    pub units: Option<&'static str>,
+    pub scope: Scope,
+}
+
+/// A rule that fired for a specific day
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleHit {
+    pub rule: String,
+    /// e.g. "Sat 14"
+    pub day: String,
+    pub message: String,
+}
+
+impl fmt::Display for RuleHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}: {}", self.rule, self.day, self.message)
+    }
+}
+
+impl Metric {
+    fn label(self) -> &'static str {
+        match self {
+            Metric::High => "high",
+            Metric::Low => "low",
+            Metric::HeatIndex => "heat index",
+            Metric::Wind => "wind",
+            Metric::Gust => "gusts",
+            Metric::Rain => "rain",
+            Metric::Snow => "snow",
+            Metric::Pop => "precip chance",
+        }
+    }
+
+    fn unit(self, units: &str) -> String {
+        match self {
+            Metric::High | Metric::Low | Metric::HeatIndex => temp_unit(units).to_string(),
+            Metric::Wind | Metric::Gust => format!(" {}", wind_unit(units)),
+            Metric::Rain | Metric::Snow if units == "imperial" => " in".to_string(),
+            Metric::Rain | Metric::Snow => " mm".to_string(),
+            Metric::Pop => "%".to_string(),
+        }
+    }
+
+    /// Units a rule on this metric may be written in
+    fn unit_names(self) -> &'static str {
+        match self {
+            Metric::High | Metric::Low | Metric::HeatIndex => "°F, °C or K",
+            Metric::Wind | Metric::Gust => "mph or m/s",
+            Metric::Rain | Metric::Snow => "in or mm",
+            Metric::Pop => "%",
+        }
+    }
+
+    /// Unit system `unit` belongs to for this metric; `None` for `%`, which
+    /// is the same in every system
+    fn unit_system(self, unit: &str, rule: &str) -> Result<Option<&'static str>> {
+        let temp = matches!(self, Metric::High | Metric::Low | Metric::HeatIndex);
+        let wind = matches!(self, Metric::Wind | Metric::Gust);
+        let precip = matches!(self, Metric::Rain | Metric::Snow);
+        Ok(Some(match unit.to_lowercase().as_str() {
+            "°f" | "f" if temp => "imperial",
+            "°c" | "c" if temp => "metric",
+            "k" if temp => "standard",
+            "mph" if wind => "imperial",
+            "m/s" if wind => "metric",
+            "in" if precip => "imperial",
+            "mm" if precip => "metric",
+            "%" if self == Metric::Pop => return Ok(None),
+            other => bail!(
+                "unit '{other}' does not fit {} in rule '{rule}' (expected {})",
+                self.label(),
+                self.unit_names()
+            ),
+        }))
+    }
+}
+
+impl FromStr for Metric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "high" | "max" => Metric::High,
+            "low" | "min" => Metric::Low,
+            "heat_index" | "heatindex" => Metric::HeatIndex,
+            "wind" => Metric::Wind,
+            "gust" | "gusts" => Metric::Gust,
+            "rain" => Metric::Rain,
+            "snow" => Metric::Snow,
+            "pop" => Metric::Pop,
+            other => bail!(
+                "unknown metric '{other}' (expected high, low, heat_index, wind, gust, rain, snow, pop)"
+            ),
+        })
+    }
+}
+
+impl Op {
+    fn matches(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Op::Above => value > threshold,
+            Op::AtLeast => value >= threshold,
+            Op::Below => value < threshold,
+            Op::AtMost => value <= threshold,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Op::Above => "above",
+            Op::AtLeast => "at least",
+            Op::Below => "below",
+            Op::AtMost => "at most",
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            ">" | "above" => Op::Above,
+            ">=" => Op::AtLeast,
+            "<" | "below" => Op::Below,
+            "<=" => Op::AtMost,
+            other => bail!("unknown comparison '{other}' (expected <, <=, >, >=, below, above)"),
+        })
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    /// `[name:] <metric> <op> <value>[unit] [unit] [today|tomorrow]`
+    ///
+    /// A unit ("0°C", "40 mph") must fit the metric and is converted at
+    /// evaluation time.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (name, expr) = match s.split_once(':') {
+            Some((n, e)) => (n.trim().to_string(), e.trim()),
+            None => (s.to_string(), s),
+        };
+
+        let tokens: Vec<&str> = expr.split_whitespace().collect();
+        if tokens.len() < 3 {
+            bail!("invalid rule '{s}': expected '<metric> <op> <value> [today|tomorrow]'");
+        }
+
+        let metric: Metric = tokens[0].parse()?;
+        let op: Op = tokens[1].parse()?;
+        let value = tokens[2].trim_end_matches(|c: char| !c.is_ascii_digit() && c != '.');
+        let threshold: f64 = value
+            .parse()
+            .map_err(|_| anyhow!("invalid threshold '{}' in rule '{s}'", tokens[2]))?;
+        let mut units = match &tokens[2][value.len()..] {
+            "" => None,
+            suffix => metric.unit_system(suffix, s)?,
+        };
+
+        let mut scope = Scope::AnyDay;
+        for token in &tokens[3..] {
+            match token.to_lowercase().as_str() {
+                "today" => scope = Scope::Today,
+                "tomorrow" => scope = Scope::Tomorrow,
+                unit @ ("°f" | "f" | "°c" | "c" | "k" | "mph" | "m/s" | "in" | "mm" | "%") => {
+                    units = metric.unit_system(unit, s)?;
+                }
+                other => {
+                    bail!("unexpected '{other}' in rule '{s}' (expected a unit, today or tomorrow)")
+                }
+            }
+        }
+
+        Ok(Rule {
+            name,
+            metric,
+            op,
+            threshold,
+            units,
+            scope,
+        })
+    }
+}
+
+impl Rule {
+    /// Threshold converted to the run's unit system
+    fn threshold_in(&self, units: &str) -> f64 {
+        let Some(from) = self.units else {
+            return self.threshold;
+        };
+        let t = self.threshold;
+        match self.metric {
+            Metric::High | Metric::Low | Metric::HeatIndex => {
+                from_celsius(to_celsius(t, from), units)
+            }
+            Metric::Wind | Metric::Gust => {
+                let ms = if from == "imperial" { t * MPH_TO_MS } else { t };
+                if units == "imperial" {
+                    ms / MPH_TO_MS
+                } else {
+                    ms
+                }
+            }
+            Metric::Rain | Metric::Snow => {
+                let mm = if from == "imperial" {
+                    t * INCH_TO_MM
+                } else {
+                    t
+                };
+                if units == "imperial" {
+                    mm / INCH_TO_MM
+                } else {
+                    mm
+                }
+            }
+            Metric::Pop => t,
+        }
+    }
+}
+
+/// Parse rules separated by newlines or `;`, skipping blanks and `#` comments
+pub fn parse_rules(text: &str) -> Result<Vec<Rule>> {
+    text.split(['\n', ';'])
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(Rule::from_str)
+        .collect()
+}
+
+/// Evaluate every rule against every day it applies to
+pub fn evaluate(rules: &[Rule], forecast: &Forecast, units: &str) -> Vec<RuleHit> {
+    let offset = forecast.offset();
+    let mut hits = Vec::new();
+
+    for rule in rules {
+        let days = match rule.scope {
+            Scope::Today => 0..1,
+            Scope::Tomorrow => 1..2,
+            Scope::AnyDay => 0..forecast.daily.len(),
+        };
+
+        let threshold = rule.threshold_in(units);
+        for i in days {
+            let Some(value) = day_value(forecast, i, rule.metric, units, &offset) else {
+                continue;
+            };
+            if rule.op.matches(value, threshold) {
+                let unit = rule.metric.unit(units);
+                hits.push(RuleHit {
+                    rule: rule.name.clone(),
+                    day: day_label(forecast.daily[i].dt, &offset),
+                    message: format!(
+                        "{} {:.1}{unit} ({} {}{unit})",
+                        rule.metric.label(),
+                        value,
+                        rule.op.label(),
+                        (threshold * 10.0).round() / 10.0
+                    ),
+                });
+            }
+        }
+    }
+
+    hits
+}
+
+fn day_value(
+    forecast: &Forecast,
+    i: usize,
+    metric: Metric,
+    units: &str,
+    offset: &FixedOffset,
+) -> Option<f64> {
+    let day = forecast.daily.get(i)?;
+    let mm = |v: f64| {
+        if units == "imperial" {
+            v / INCH_TO_MM
+        } else {
+            v
+        }
+    };
+
+    match metric {
+        Metric::High => Some(day.temp_max),
+        Metric::Low => Some(day.temp_min),
+        Metric::Wind => Some(day.wind_speed),
+        Metric::Gust => day.wind_gust,
+        Metric::Rain => Some(mm(day.rain)),
+        Metric::Snow => Some(mm(day.snow_depth)),
+        Metric::Pop => Some(day.pop * 100.0),
+        Metric::HeatIndex => {
+            let date = offset.timestamp_opt(day.dt, 0).single()?.date_naive();
+            forecast
+                .hourly
+                .iter()
+                .filter(|h| {
+                    offset
+                        .timestamp_opt(h.dt, 0)
+                        .single()
+                        .map(|t| t.date_naive() == date)
+                        .unwrap_or(false)
+                })
+                .map(|h| heat_index(h.temp, h.humidity as f64, units))
+                .reduce(f64::max)
+        }
+    }
+}
+
+/// NWS heat index (Rothfusz regression with Steadman's simple formula below 80°F)
+pub fn heat_index(temp: f64, rh: f64, units: &str) -> f64 {
+    let t = match units {
+        "imperial" => temp,
+        "standard" => (temp - 273.15) * 9.0 / 5.0 + 32.0,
+        _ => temp * 9.0 / 5.0 + 32.0,
+    };
+
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    let hi = if (simple + t) / 2.0 < 80.0 {
+        simple
+    } else {
+        -42.379 + 2.04901523 * t + 10.14333127 * rh
+            - 0.22475541 * t * rh
+            - 0.00683783 * t * t
+            - 0.05481717 * rh * rh
+            + 0.00122874 * t * t * rh
+            + 0.00085282 * t * rh * rh
+            - 0.00000199 * t * t * rh * rh
+    };
+
+    match units {
+        "imperial" => hi,
+        "standard" => (hi - 32.0) * 5.0 / 9.0 + 273.15,
+        _ => (hi - 32.0) * 5.0 / 9.0,
+    }
+}
+
+fn day_label(dt: i64, offset: &FixedOffset) -> String {
+    offset
+        .timestamp_opt(dt, 0)
+        .single()
+        .map(|t| t.format("%a %d").to_string())
+        .unwrap_or_else(|| dt.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Condition, CurrentConditions, DailyForecast, HourlyForecast};
+
+    // 2024-06-20 00:00 UTC (Thursday)
+    const DAY0: i64 = 1_718_841_600;
+
+    fn day(i: i64, low: f64, high: f64, gust: Option<f64>, snow_depth: f64) -> DailyForecast {
+        DailyForecast {
+            dt: DAY0 + i * 86_400 + 12 * 3600,
+            temp_min: low,
+            temp_max: high,
+            pop: 0.2,
+            rain: 0.0,
+            snow_depth,
+            wind_speed: 10.0,
+            wind_gust: gust,
+            uvi: None,
+            description: "clear sky".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn forecast() -> Forecast {
+        Forecast {
+            timezone: "UTC".to_string(),
+            timezone_offset: 0,
+            current: CurrentConditions {
+                dt: DAY0,
+                temp: 70.0,
+                humidity: 50,
+                description: "clear sky".to_string(),
+                ..Default::default()
+            },
+            minutely: Vec::new(),
+            hourly: vec![HourlyForecast {
+                dt: DAY0 + 86_400 + 15 * 3600,
+                temp: 96.0,
+                humidity: 60,
+                pop: 0.0,
+                precipitation: 0.0,
+                description: "clear sky".to_string(),
+                condition: Condition::Clear,
+            }],
+            daily: vec![
+                day(0, 40.0, 70.0, Some(20.0), 0.0),
+                day(1, 30.0, 96.0, Some(45.0), 0.0),
+                day(2, 25.0, 35.0, None, 76.2),
+            ],
+            alerts: Vec::new(),
+            air_quality: None,
+            solar: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_rule_forms() {
+        let r: Rule = "Frost: low < 32 tomorrow".parse().unwrap();
+        assert_eq!(r.name, "Frost");
+        assert_eq!(r.metric, Metric::Low);
+        assert_eq!(r.op, Op::Below);
+        assert_eq!(r.threshold, 32.0);
+        assert_eq!(r.scope, Scope::Tomorrow);
+
+        let r: Rule = "gust above 40".parse().unwrap();
+        assert_eq!(r.name, "gust above 40");
+        assert_eq!(r.op, Op::Above);
+        assert_eq!(r.scope, Scope::AnyDay);
+
+        // Units are allowed for readability
+        let r: Rule = "Frost: low below 32°F tomorrow".parse().unwrap();
+        assert_eq!(r.threshold, 32.0);
+        assert_eq!(r.scope, Scope::Tomorrow);
+        let r: Rule = "Wind: gusts above 40 mph".parse().unwrap();
+        assert_eq!(r.metric, Metric::Gust);
+        assert_eq!(r.threshold, 40.0);
+
+        assert!("Bad: humidity > 3".parse::<Rule>().is_err());
+        assert!("Bad: low < cold".parse::<Rule>().is_err());
+        assert!("Bad: low < 32 someday".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_rule_units_convert_or_are_rejected() {
+        let rules = parse_rules("Frost: low < 0°C tomorrow; Wind: gust > 20 m/s").unwrap();
+        assert_eq!(rules[0].units, Some("metric"));
+        let hits = evaluate(&rules, &forecast(), "imperial");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].message, "low 30.0°F (below 32°F)");
+        assert_eq!(hits[1].message, "gusts 45.0 mph (above 44.7 mph)");
+
+        let rules = parse_rules("Snow: snow > 2 in").unwrap();
+        let hits = evaluate(&rules, &forecast(), "metric");
+        assert_eq!(hits[0].message, "snow 76.2 mm (above 50.8 mm)");
+
+        let err = "Snow: snow > 2 mph".parse::<Rule>().unwrap_err();
+        assert!(err.to_string().contains("expected in or mm"), "{err}");
+        let err = "Frost: low < 32mph".parse::<Rule>().unwrap_err();
+        assert!(err.to_string().contains("expected °F, °C or K"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_rules_list() {
+        let rules =
+            parse_rules("Frost: low < 32; Heat: heat_index > 100\n# comment\n\nWind: gust > 40")
+                .unwrap();
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[2].name, "Wind");
+    }
+
+    #[test]
+    fn test_evaluate_names_rule_and_day() {
+        let rules =
+            parse_rules("Frost: low < 32 tomorrow; Wind: gust > 40; Snow: snow > 2").unwrap();
+        let hits = evaluate(&rules, &forecast(), "imperial");
+
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].rule, "Frost");
+        assert_eq!(hits[0].day, "Fri 21");
+        assert_eq!(hits[0].message, "low 30.0°F (below 32°F)");
+        assert_eq!(hits[1].rule, "Wind");
+        assert_eq!(hits[1].day, "Fri 21");
+        // 76.2 mm of fresh snow = 3 in
+        assert_eq!(hits[2].rule, "Snow");
+        assert_eq!(hits[2].day, "Sat 22");
+        assert_eq!(hits[2].message, "snow 3.0 in (above 2 in)");
+    }
+
+    #[test]
+    fn test_today_scope_only_checks_today() {
+        let rules = parse_rules("Frost: low < 32 today").unwrap();
+        assert!(evaluate(&rules, &forecast(), "imperial").is_empty());
+    }
+
+    #[test]
+    fn test_heat_index_rule_uses_hourly() {
+        let rules = parse_rules("Heat: heat_index > 100").unwrap();
+        let hits = evaluate(&rules, &forecast(), "imperial");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].day, "Fri 21");
+    }
+
+    #[test]
+    fn test_heat_index_values() {
+        // NWS table: 96°F at 60% RH -> ~116°F
+        assert!((heat_index(96.0, 60.0, "imperial") - 116.0).abs() < 1.5);
+        // Below 80°F the simple formula applies and stays close to temperature
+        assert!((heat_index(70.0, 50.0, "imperial") - 69.5).abs() < 1.0);
+        // Metric round trip: 35°C at 60% RH -> ~45°C
+        assert!((heat_index(35.0, 60.0, "metric") - 45.0).abs() < 1.5);
+        assert!((heat_index(308.15, 60.0, "standard") - 318.15).abs() < 1.5);
+    }
+}
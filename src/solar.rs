@@ -0,0 +1,281 @@
+//! Solar production outlook for a home PV array
+//!
+//! Clear-sky irradiance on the panel plane is integrated over each day's
+//! daylight hours from the sun's position at the location, then scaled down
+//! by the forecast cloud cover. The result is an estimate for planning
+//! (when to run the dishwasher), not a yield guarantee.
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, TimeZone, Utc};
+
+use crate::model::{DailyForecast, Forecast, Location, SolarDay, SolarOutlook};
+
+/// Days estimated (today + 6)
+const DAYS: usize = 7;
+
+/// Integration step, seconds
+const STEP_SECS: i64 = 600;
+
+/// Inverter, wiring, temperature and soiling losses
+const PERFORMANCE_RATIO: f64 = 0.8;
+
+/// Clear-sky diffuse irradiance as a share of the direct beam
+const DIFFUSE_FRACTION: f64 = 0.1;
+
+/// Ground reflectance seen by tilted panels
+const ALBEDO: f64 = 0.2;
+
+/// Cloud cover assumed when the provider reports none, percent
+const UNKNOWN_CLOUDS: f64 = 50.0;
+
+/// A configured PV array
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PvArray {
+    /// Peak power in kWp
+    pub kwp: f64,
+    /// Tilt from horizontal, 0-90 degrees
+    pub tilt: f64,
+    /// Compass direction the panels face (0 = N, 90 = E, 180 = S, 270 = W)
+    pub azimuth: f64,
+}
+
+impl PvArray {
+    pub fn new(kwp: f64, tilt: f64, azimuth: f64) -> Result<Self> {
+        if kwp.is_nan() || kwp <= 0.0 {
+            bail!("solar array size must be positive, got {kwp} kWp");
+        }
+        if !(0.0..=90.0).contains(&tilt) {
+            bail!("solar tilt must be 0-90 degrees, got {tilt}");
+        }
+        if !(0.0..360.0).contains(&azimuth) {
+            bail!("solar azimuth must be 0-359 degrees, got {azimuth}");
+        }
+        Ok(PvArray { kwp, tilt, azimuth })
+    }
+}
+
+/// Expected production for the first `DAYS` days of `data`
+pub fn outlook(array: &PvArray, location: &Location, data: &Forecast) -> SolarOutlook {
+    let offset = data.offset();
+    let days = data
+        .daily
+        .iter()
+        .take(DAYS)
+        .filter_map(|day| {
+            let midnight = offset
+                .timestamp_opt(day.dt, 0)
+                .single()?
+                .date_naive()
+                .and_hms_opt(0, 0, 0)?
+                .and_local_timezone(offset)
+                .single()?
+                .timestamp();
+            let (start, end) = daylight(day, midnight);
+            let insolation = plane_insolation(array, location, start, end);
+            let clear_sky_kwh = array.kwp * insolation * PERFORMANCE_RATIO;
+            let clouds = day.clouds.map(f64::from).unwrap_or(UNKNOWN_CLOUDS);
+            Some(SolarDay {
+                dt: day.dt,
+                kwh: clear_sky_kwh * cloud_factor(clouds),
+                clear_sky_kwh,
+                clouds: day.clouds,
+            })
+        })
+        .collect();
+
+    SolarOutlook {
+        kwp: array.kwp,
+        tilt: array.tilt,
+        azimuth: array.azimuth,
+        days,
+    }
+}
+
+/// Sunrise to sunset when the provider reports them, else the whole local day
+fn daylight(day: &DailyForecast, midnight: i64) -> (i64, i64) {
+    match (day.sunrise, day.sunset) {
+        (Some(rise), Some(set)) if rise < set => (rise, set),
+        _ => (midnight, midnight + 86_400),
+    }
+}
+
+/// Share of clear-sky irradiance left under `clouds` percent cover
+/// (Kasten & Czeplak, 1980)
+fn cloud_factor(clouds: f64) -> f64 {
+    1.0 - 0.75 * (clouds.clamp(0.0, 100.0) / 100.0).powf(3.4)
+}
+
+/// Clear-sky irradiation on the panel plane between two unix times, kWh/m²
+fn plane_insolation(array: &PvArray, location: &Location, start: i64, end: i64) -> f64 {
+    let lat = location.lat.to_radians();
+    let tilt = array.tilt.to_radians();
+    let mut wh = 0.0;
+    let mut t = start;
+    while t < end {
+        let step = STEP_SECS.min(end - t);
+        // Sample the middle of the step
+        let (cos_zenith, sun_azimuth) = sun_position(lat, location.lon, t + step / 2);
+        if cos_zenith > 0.0 {
+            // Meinel clear-sky direct normal irradiance, W/m²
+            let dni = 1353.0 * 0.7f64.powf(air_mass(cos_zenith).powf(0.678));
+            let dhi = dni * DIFFUSE_FRACTION;
+            let ghi = dni * cos_zenith + dhi;
+            let sin_zenith = (1.0 - cos_zenith * cos_zenith).sqrt();
+            let cos_incidence = cos_zenith * tilt.cos()
+                + sin_zenith * tilt.sin() * (sun_azimuth - array.azimuth.to_radians()).cos();
+            let poa = dni * cos_incidence.max(0.0)
+                + dhi * (1.0 + tilt.cos()) / 2.0
+                + ghi * ALBEDO * (1.0 - tilt.cos()) / 2.0;
+            wh += poa * step as f64 / 3600.0;
+        }
+        t += step;
+    }
+    wh / 1000.0
+}
+
+/// Relative optical air mass (Kasten & Young, 1989)
+fn air_mass(cos_zenith: f64) -> f64 {
+    let zenith = cos_zenith.acos().to_degrees();
+    1.0 / (cos_zenith + 0.50572 * (96.07995 - zenith).powf(-1.6364))
+}
+
+/// Cosine of the solar zenith angle and the sun's azimuth (radians from
+/// north, clockwise) at unix time `t` (NOAA approximation)
+fn sun_position(lat: f64, lon: f64, t: i64) -> (f64, f64) {
+    use std::f64::consts::PI;
+
+    let ordinal = Utc
+        .timestamp_opt(t, 0)
+        .single()
+        .map(|d| d.ordinal0())
+        .unwrap_or(0);
+    let utc_minutes = t.rem_euclid(86_400) as f64 / 60.0;
+    // Fractional year
+    let g = 2.0 * PI / 365.0 * (ordinal as f64 + (utc_minutes / 60.0 - 12.0) / 24.0);
+    let declination = 0.006918 - 0.399912 * g.cos() + 0.070257 * g.sin()
+        - 0.006758 * (2.0 * g).cos()
+        + 0.000907 * (2.0 * g).sin()
+        - 0.002697 * (3.0 * g).cos()
+        + 0.00148 * (3.0 * g).sin();
+    let equation_of_time = 229.18
+        * (0.000075 + 0.001868 * g.cos()
+            - 0.032077 * g.sin()
+            - 0.014615 * (2.0 * g).cos()
+            - 0.040849 * (2.0 * g).sin());
+    let solar_minutes = utc_minutes + equation_of_time + 4.0 * lon;
+    let hour_angle = (solar_minutes / 4.0 - 180.0).to_radians();
+
+    let cos_zenith =
+        lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos();
+    let azimuth = hour_angle
+        .sin()
+        .atan2(hour_angle.cos() * lat.sin() - declination.tan() * lat.cos())
+        + PI;
+    (cos_zenith, azimuth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CurrentConditions;
+
+    // 2024-06-20 00:00 UTC (Thursday)
+    const JUNE: i64 = 1_718_841_600;
+    // 2024-12-20 00:00 UTC
+    const DECEMBER: i64 = 1_734_652_800;
+
+    fn location(lat: f64) -> Location {
+        Location {
+            lat,
+            lon: -90.5776,
+            name: "Davenport, IA, US".to_string(),
+        }
+    }
+
+    fn forecast(day0: i64, clouds: &[Option<u8>]) -> Forecast {
+        Forecast {
+            timezone: "America/Chicago".to_string(),
+            timezone_offset: -5 * 3600,
+            current: CurrentConditions::default(),
+            minutely: Vec::new(),
+            hourly: Vec::new(),
+            daily: clouds
+                .iter()
+                .enumerate()
+                .map(|(i, &clouds)| DailyForecast {
+                    dt: day0 + i as i64 * 86_400 + 17 * 3600,
+                    clouds,
+                    ..Default::default()
+                })
+                .collect(),
+            alerts: Vec::new(),
+            air_quality: None,
+            solar: None,
+        }
+    }
+
+    fn south(kwp: f64) -> PvArray {
+        PvArray::new(kwp, 30.0, 180.0).unwrap()
+    }
+
+    #[test]
+    fn test_clear_june_day_is_plausible() {
+        let out = outlook(&south(6.0), &location(41.5236), &forecast(JUNE, &[Some(0)]));
+        let day = &out.days[0];
+        // ~7 kWh/m² on a 30° south plane in midsummer at 41.5°N
+        assert!(
+            (30.0..42.0).contains(&day.clear_sky_kwh),
+            "{}",
+            day.clear_sky_kwh
+        );
+        assert_eq!(day.kwh, day.clear_sky_kwh);
+        // Production scales with array size
+        let double = outlook(
+            &south(12.0),
+            &location(41.5236),
+            &forecast(JUNE, &[Some(0)]),
+        );
+        assert!((double.days[0].kwh - 2.0 * day.kwh).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_clouds_and_season_reduce_output() {
+        let f = forecast(JUNE, &[Some(0), Some(60), Some(100), None]);
+        let days = outlook(&south(6.0), &location(41.5236), &f).days;
+        assert_eq!(days.len(), 4);
+        assert!(days[0].kwh > days[1].kwh && days[1].kwh > days[2].kwh);
+        // Overcast still lets a quarter through
+        assert!((days[2].kwh / days[2].clear_sky_kwh - 0.25).abs() < 1e-9);
+        assert_eq!(days[3].clouds, None);
+        assert!(days[3].kwh < days[3].clear_sky_kwh);
+
+        let winter = outlook(
+            &south(6.0),
+            &location(41.5236),
+            &forecast(DECEMBER, &[Some(0)]),
+        );
+        assert!(winter.days[0].clear_sky_kwh < days[0].clear_sky_kwh * 0.7);
+        // No sun at all in the polar night
+        let polar = outlook(
+            &south(6.0),
+            &location(80.0),
+            &forecast(DECEMBER, &[Some(0)]),
+        );
+        assert!(polar.days[0].kwh < 0.01);
+    }
+
+    #[test]
+    fn test_orientation_and_validation() {
+        let f = forecast(DECEMBER, &[Some(0)]);
+        let loc = location(41.5236);
+        let facing =
+            |azimuth| outlook(&PvArray::new(6.0, 40.0, azimuth).unwrap(), &loc, &f).days[0].kwh;
+        assert!(facing(180.0) > facing(90.0));
+        assert!(facing(90.0) > facing(0.0));
+        assert!((facing(90.0) - facing(270.0)).abs() < 0.5);
+
+        assert!(PvArray::new(0.0, 30.0, 180.0).is_err());
+        assert!(PvArray::new(5.0, 95.0, 180.0).is_err());
+        assert!(PvArray::new(5.0, 30.0, 360.0).is_err());
+    }
+}